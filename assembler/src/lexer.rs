@@ -18,6 +18,43 @@ pub enum TokenType {
     Move,
     Lea,
     Nop,
+    Jmp,
+    Jsr,
+    Rts,
+    Bra,
+    Bsr,
+    Beq,
+    Bne,
+    Bcs,
+    Bcc,
+    Bmi,
+    Bpl,
+    Bvs,
+    Bvc,
+    Bhi,
+    Bls,
+    Bge,
+    Blt,
+    Bgt,
+    Ble,
+    Bpe,
+    Bpo,
+    Bsreq,
+    Bsrne,
+    Bsrcs,
+    Bsrcc,
+    Bsrmi,
+    Bsrpl,
+    Bsrvs,
+    Bsrvc,
+    Bsrhi,
+    Bsrls,
+    Bsrge,
+    Bsrlt,
+    Bsrgt,
+    Bsrle,
+    Bsrpe,
+    Bsrpo,
 
     Byte,
     Word,
@@ -105,6 +142,43 @@ impl<'a> Token<'a> {
             "nop" => TokenType::Nop,
             "move" => TokenType::Move,
             "lea" => TokenType::Lea,
+            "jmp" => TokenType::Jmp,
+            "jsr" => TokenType::Jsr,
+            "rts" => TokenType::Rts,
+            "bra" => TokenType::Bra,
+            "bsr" => TokenType::Bsr,
+            "beq" => TokenType::Beq,
+            "bne" => TokenType::Bne,
+            "bcs" => TokenType::Bcs,
+            "bcc" => TokenType::Bcc,
+            "bmi" => TokenType::Bmi,
+            "bpl" => TokenType::Bpl,
+            "bvs" => TokenType::Bvs,
+            "bvc" => TokenType::Bvc,
+            "bhi" => TokenType::Bhi,
+            "bls" => TokenType::Bls,
+            "bge" => TokenType::Bge,
+            "blt" => TokenType::Blt,
+            "bgt" => TokenType::Bgt,
+            "ble" => TokenType::Ble,
+            "bpe" => TokenType::Bpe,
+            "bpo" => TokenType::Bpo,
+            "bsreq" => TokenType::Bsreq,
+            "bsrne" => TokenType::Bsrne,
+            "bsrcs" => TokenType::Bsrcs,
+            "bsrcc" => TokenType::Bsrcc,
+            "bsrmi" => TokenType::Bsrmi,
+            "bsrpl" => TokenType::Bsrpl,
+            "bsrvs" => TokenType::Bsrvs,
+            "bsrvc" => TokenType::Bsrvc,
+            "bsrhi" => TokenType::Bsrhi,
+            "bsrls" => TokenType::Bsrls,
+            "bsrge" => TokenType::Bsrge,
+            "bsrlt" => TokenType::Bsrlt,
+            "bsrgt" => TokenType::Bsrgt,
+            "bsrle" => TokenType::Bsrle,
+            "bsrpe" => TokenType::Bsrpe,
+            "bsrpo" => TokenType::Bsrpo,
             "b" => TokenType::Byte,
             "w" => TokenType::Word,
             "dw" => TokenType::Dword,
@@ -316,6 +390,43 @@ impl<'a> Token<'a> {
             TokenType::Identifier
                 | TokenType::Move
                 | TokenType::Lea
+                | TokenType::Jmp
+                | TokenType::Jsr
+                | TokenType::Rts
+                | TokenType::Bra
+                | TokenType::Bsr
+                | TokenType::Beq
+                | TokenType::Bne
+                | TokenType::Bcs
+                | TokenType::Bcc
+                | TokenType::Bmi
+                | TokenType::Bpl
+                | TokenType::Bvs
+                | TokenType::Bvc
+                | TokenType::Bhi
+                | TokenType::Bls
+                | TokenType::Bge
+                | TokenType::Blt
+                | TokenType::Bgt
+                | TokenType::Ble
+                | TokenType::Bpe
+                | TokenType::Bpo
+                | TokenType::Bsreq
+                | TokenType::Bsrne
+                | TokenType::Bsrcs
+                | TokenType::Bsrcc
+                | TokenType::Bsrmi
+                | TokenType::Bsrpl
+                | TokenType::Bsrvs
+                | TokenType::Bsrvc
+                | TokenType::Bsrhi
+                | TokenType::Bsrls
+                | TokenType::Bsrge
+                | TokenType::Bsrlt
+                | TokenType::Bsrgt
+                | TokenType::Bsrle
+                | TokenType::Bsrpe
+                | TokenType::Bsrpo
                 | TokenType::Byte
                 | TokenType::Word
                 | TokenType::Dword
@@ -354,6 +465,46 @@ impl<'a> Token<'a> {
         )
     }
 
+    pub fn is_branch(&self) -> bool {
+        matches!(
+            self.token_type,
+            TokenType::Bra
+                | TokenType::Bsr
+                | TokenType::Beq
+                | TokenType::Bne
+                | TokenType::Bcs
+                | TokenType::Bcc
+                | TokenType::Bmi
+                | TokenType::Bpl
+                | TokenType::Bvs
+                | TokenType::Bvc
+                | TokenType::Bhi
+                | TokenType::Bls
+                | TokenType::Bge
+                | TokenType::Blt
+                | TokenType::Bgt
+                | TokenType::Ble
+                | TokenType::Bpe
+                | TokenType::Bpo
+                | TokenType::Bsreq
+                | TokenType::Bsrne
+                | TokenType::Bsrcs
+                | TokenType::Bsrcc
+                | TokenType::Bsrmi
+                | TokenType::Bsrpl
+                | TokenType::Bsrvs
+                | TokenType::Bsrvc
+                | TokenType::Bsrhi
+                | TokenType::Bsrls
+                | TokenType::Bsrge
+                | TokenType::Bsrlt
+                | TokenType::Bsrgt
+                | TokenType::Bsrle
+                | TokenType::Bsrpe
+                | TokenType::Bsrpo
+        )
+    }
+
     pub fn is_number(&self) -> bool {
         matches!(
             self.token_type,
@@ -485,18 +636,11 @@ impl<'a> Tokenizer<'a> {
     }
 
     fn is_hexadecimal(symbol: u8) -> bool {
-        match symbol {
-            b'a'..=b'f' | b'A'..=b'F' => true,
-            b'0'..=b'9' => true,
-            _ => false,
-        }
+        symbol.is_ascii_hexdigit()
     }
 
     fn is_binary(symbol: u8) -> bool {
-        match symbol {
-            b'0'..=b'1' => true,
-            _ => false,
-        }
+        matches!(symbol, b'0'..=b'1')
     }
 
     pub fn next(&mut self) -> Token<'a> {
@@ -509,7 +653,7 @@ impl<'a> Tokenizer<'a> {
         match self.source.get(self.position) {
             Some(ch) if ch.is_ascii_alphabetic() || *ch == b'_' => {
                 let (start, end) = self.digest_identifier();
-                return Token::new_identifier(&self.source[start..end], start, end, self.line);
+                Token::new_identifier(&self.source[start..end], start, end, self.line)
             }
             Some(ch)
                 if *ch == b'#'
@@ -517,31 +661,25 @@ impl<'a> Tokenizer<'a> {
             {
                 self.advance();
                 let (start, end) = self.digest_decmial_number();
-                return Token::new_number(&self.source[start..end], start, end, self.line);
+                Token::new_number(&self.source[start..end], start, end, self.line)
             }
             Some(ch) if *ch == b'$' && Tokenizer::is_hexadecimal(*self.peek().unwrap()) => {
                 self.advance();
 
                 let (start, end) = self.digest_hex_number();
-                return Token::new_hex_number(&self.source[start..end], start, end, self.line);
+                Token::new_hex_number(&self.source[start..end], start, end, self.line)
             }
             Some(ch) if *ch == b'%' && Tokenizer::is_binary(*self.peek().unwrap()) => {
                 self.advance();
                 let (start, end) = self.digest_binary_number();
-                return Token::new_binary_number(&self.source[start..end], start, end, self.line);
+                Token::new_binary_number(&self.source[start..end], start, end, self.line)
             }
             Some(_) => {
                 //NOTE: Scanner is desperate it does not know what the next symbol is so it __must__
                 //      be a operator!
                 let (operator, start, end) = self.digest_operator();
 
-                return Token::new_operator(
-                    operator,
-                    &self.source[start..end],
-                    start,
-                    end,
-                    self.line,
-                );
+                Token::new_operator(operator, &self.source[start..end], start, end, self.line)
             }
             _ => unreachable!(),
         }
@@ -1152,4 +1290,42 @@ mod tests {
             }
         )
     }
+
+    #[test]
+    fn test_control_flow_keywords() {
+        let source =
+            "jmp jsr rts bra bsr beq bne bcs bcc bmi bpl bvs bvc bhi bls bge blt bgt ble bpe bpo";
+        let expected = [
+            TokenType::Jmp,
+            TokenType::Jsr,
+            TokenType::Rts,
+            TokenType::Bra,
+            TokenType::Bsr,
+            TokenType::Beq,
+            TokenType::Bne,
+            TokenType::Bcs,
+            TokenType::Bcc,
+            TokenType::Bmi,
+            TokenType::Bpl,
+            TokenType::Bvs,
+            TokenType::Bvc,
+            TokenType::Bhi,
+            TokenType::Bls,
+            TokenType::Bge,
+            TokenType::Blt,
+            TokenType::Bgt,
+            TokenType::Ble,
+            TokenType::Bpe,
+            TokenType::Bpo,
+        ];
+
+        let mut tokenizer = Tokenizer::new(source);
+        for token_type in expected {
+            let token = tokenizer.next();
+            assert_eq!(token.token_type(), token_type);
+            assert!(token.is_keyword());
+        }
+
+        assert_eq!(tokenizer.next().token_type(), TokenType::EndOfFile);
+    }
 }
@@ -8,9 +8,24 @@ use lexer::TokenType;
 use parser::{Ast, Parser};
 use std::collections::{HashMap, HashSet};
 
+//NOTE: A word in the output that has to be patched once the label it refers to is known
+struct LabelFixup {
+    label: String,
+    index: usize,
+    //NOTE: Branches encode a displacement relative to their own address, jumps an absolute address
+    relative_to: Option<usize>,
+}
+
 pub struct Assembler {
     label_definitions: HashMap<String, usize>,
     missing_labels: HashSet<String>,
+    label_fixups: Vec<LabelFixup>,
+}
+
+impl Default for Assembler {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Assembler {
@@ -18,9 +33,14 @@ impl Assembler {
         Self {
             label_definitions: HashMap::new(),
             missing_labels: HashSet::new(),
+            label_fixups: vec![],
         }
     }
 
+    pub fn missing_labels(&self) -> &HashSet<String> {
+        &self.missing_labels
+    }
+
     pub fn encode_register(tt: TokenType) -> u32 {
         match tt {
             TokenType::D0 => 0x00,
@@ -60,9 +80,12 @@ impl Assembler {
     }
     pub fn encode_dest(ast: &Ast) -> u32 {
         match ast {
-            Ast::MemoryTarget { repr, operation } => todo!(),
+            Ast::MemoryTarget {
+                repr: _,
+                operation: _,
+            } => todo!(),
             Ast::Register { repr } => Assembler::encode_register(repr.token_type()) << 14,
-            Ast::Number { repr } => todo!(),
+            Ast::Number { repr: _ } => todo!(),
             _ => unreachable!(),
         }
     }
@@ -70,7 +93,10 @@ impl Assembler {
     //TODO: The return type doesn't feel good...
     pub fn encode_source(ast: &Ast) -> (u32, Option<TokenType>) {
         match ast {
-            Ast::MemoryTarget { repr, operation } => todo!(),
+            Ast::MemoryTarget {
+                repr: _,
+                operation: _,
+            } => todo!(),
             Ast::Register { repr } => (Assembler::encode_register(repr.token_type()) << 19, None),
             Ast::Number { repr } => {
                 let addr_mode = 0x01 << 8;
@@ -97,9 +123,9 @@ impl Assembler {
         match ast {
             Ast::Size { repr } => {
                 match repr.token_type() {
-                    TokenType::Byte => 0x00 << 29,
-                    TokenType::Word => 0x01 << 29,
-                    TokenType::Dword => 0x02 << 29,
+                    TokenType::Byte => 0x00 << 30,
+                    TokenType::Word => 0x01 << 30,
+                    TokenType::Dword => 0x02 << 30,
                     //NOTE: Until this point all possible errors have been handled
                     //      if we reach this it would be a bug!
                     _ => unreachable!(),
@@ -113,33 +139,141 @@ impl Assembler {
         match tt {
             TokenType::Move => 0x01,
             TokenType::Lea => 0x02,
+            TokenType::Jmp => 0x14,
+            TokenType::Jsr => 0x16,
+            TokenType::Rts => 0x17,
+            TokenType::Bsr
+            | TokenType::Bsreq
+            | TokenType::Bsrne
+            | TokenType::Bsrcs
+            | TokenType::Bsrcc
+            | TokenType::Bsrmi
+            | TokenType::Bsrpl
+            | TokenType::Bsrvs
+            | TokenType::Bsrvc
+            | TokenType::Bsrhi
+            | TokenType::Bsrls
+            | TokenType::Bsrge
+            | TokenType::Bsrlt
+            | TokenType::Bsrgt
+            | TokenType::Bsrle
+            | TokenType::Bsrpe
+            | TokenType::Bsrpo => 0x18,
+            TokenType::Bra
+            | TokenType::Beq
+            | TokenType::Bne
+            | TokenType::Bcs
+            | TokenType::Bcc
+            | TokenType::Bmi
+            | TokenType::Bpl
+            | TokenType::Bvs
+            | TokenType::Bvc
+            | TokenType::Bhi
+            | TokenType::Bls
+            | TokenType::Bge
+            | TokenType::Blt
+            | TokenType::Bgt
+            | TokenType::Ble
+            | TokenType::Bpe
+            | TokenType::Bpo => 0x15,
+            _ => unreachable!(),
+        }
+    }
+
+    //NOTE: Branch conditions are stored inside the destination register field
+    fn encode_condition(tt: TokenType) -> u32 {
+        let condition = match tt {
+            TokenType::Bra | TokenType::Bsr => 0x00,
+            TokenType::Beq | TokenType::Bsreq => 0x01,
+            TokenType::Bne | TokenType::Bsrne => 0x02,
+            TokenType::Bcs | TokenType::Bsrcs => 0x03,
+            TokenType::Bcc | TokenType::Bsrcc => 0x04,
+            TokenType::Bmi | TokenType::Bsrmi => 0x05,
+            TokenType::Bpl | TokenType::Bsrpl => 0x06,
+            TokenType::Bvs | TokenType::Bsrvs => 0x07,
+            TokenType::Bvc | TokenType::Bsrvc => 0x08,
+            TokenType::Bhi | TokenType::Bsrhi => 0x09,
+            TokenType::Bls | TokenType::Bsrls => 0x0A,
+            TokenType::Bge | TokenType::Bsrge => 0x0B,
+            TokenType::Blt | TokenType::Bsrlt => 0x0C,
+            TokenType::Bgt | TokenType::Bsrgt => 0x0D,
+            TokenType::Ble | TokenType::Bsrle => 0x0E,
+            TokenType::Bpe | TokenType::Bsrpe => 0x0F,
+            TokenType::Bpo | TokenType::Bsrpo => 0x10,
+            _ => unreachable!(),
+        };
+        condition << 14
+    }
+
+    fn number_value(tt: TokenType) -> u32 {
+        match tt {
+            TokenType::ByteHexNumber(value) | TokenType::ByteBinaryNumber(value) => value as u32,
+            TokenType::ByteDecimalNumber(value) => value as u32,
+            TokenType::WordHexNumber(value) | TokenType::WordBinaryNumber(value) => value as u32,
+            TokenType::WordDecimalNumber(value) => value as u32,
+            TokenType::DwordHexNumber(value) | TokenType::DwordBinaryNumber(value) => value,
+            TokenType::DwordDecimalNumber(value) => value as u32,
+            _ => unreachable!(),
+        }
+    }
+
+    //NOTE: Emits the extension word holding the target of a jump or branch, labels get patched later
+    fn encode_target(
+        &mut self,
+        assembled: &mut Vec<u32>,
+        target: &Ast,
+        relative_to: Option<usize>,
+    ) {
+        match target {
+            Ast::Label { repr } => {
+                self.label_fixups.push(LabelFixup {
+                    label: repr.get_repr().to_string(),
+                    index: assembled.len(),
+                    relative_to,
+                });
+                assembled.push(0x00000000);
+            }
+            Ast::Number { repr } => {
+                let address = Assembler::number_value(repr.token_type());
+                match relative_to {
+                    Some(origin) => assembled.push(address.wrapping_sub(origin as u32)),
+                    None => assembled.push(address),
+                }
+            }
             _ => unreachable!(),
         }
     }
-    pub fn assemble(&mut self, source: &str) {
+
+    fn resolve_labels(&mut self, assembled: &mut [u32]) {
+        for fixup in self.label_fixups.drain(..) {
+            let Some(&address) = self.label_definitions.get(&fixup.label) else {
+                self.missing_labels.insert(fixup.label);
+                continue;
+            };
+
+            assembled[fixup.index] = match fixup.relative_to {
+                Some(origin) => (address as u32).wrapping_sub(origin as u32),
+                None => address as u32,
+            };
+        }
+    }
+
+    pub fn assemble(&mut self, source: &str) -> Vec<u32> {
         let mut assembled: Vec<u32> = vec![];
         let mut parser = Parser::new(source);
 
-        let mut current_position_in_bytes = 0;
         loop {
+            let current_position_in_bytes = assembled.len() * 4;
             let ast = match parser.parse() {
-                Ok(Ast::ProgramEnd) => return,
+                Ok(Ast::ProgramEnd) => break,
                 Ok(ast) => ast,
-                Err(e) => {
+                Err(_e) => {
                     todo!()
                     //panic!("ERROR: {}:{}:{}", e.line, e.position, e.message);
                 }
             };
 
             match ast {
-                Ast::Label { repr } => {
-                    if self.label_definitions.contains_key(repr.get_repr()) {
-                        //TODO: Insert the value stored inside our label hashmap
-                    } else {
-                        //TODO: This is for labels coming later in the soruce!
-                        self.missing_labels.insert(repr.get_repr().to_string());
-                    }
-                }
                 Ast::LabelDefinition { repr } => {
                     //TODO: Collisions
                     self.label_definitions
@@ -163,26 +297,136 @@ impl Assembler {
                         Some(TokenType::WordBinaryNumber(_)) => todo!(),
                         Some(TokenType::DwordHexNumber(value)) => {
                             assembled.push(value);
-                            current_position_in_bytes += 4;
                         }
                         Some(TokenType::DwordDecimalNumber(value)) => {
                             assembled.push(value as u32);
-                            current_position_in_bytes += 4;
                         }
                         Some(TokenType::DwordBinaryNumber(_)) => todo!(),
                         _ => (),
                     };
                 }
-                Ast::Lea { dest, src } => todo!(),
-                Ast::Nop { repr } => {
+                Ast::Lea { dest: _, src: _ } => todo!(),
+                Ast::Nop { repr: _ } => {
                     assembled.push(0x00000000);
                 }
+                Ast::Jump { repr, target } => {
+                    let opcode = Assembler::generate_operation_opcode(repr.token_type());
+                    let size = 0x02 << 30;
+
+                    if let Ast::Register { .. } = target.as_ref() {
+                        let dest = Assembler::encode_dest(target.as_ref());
+                        assembled.push(size | dest | opcode);
+                    } else {
+                        let addr_mode = 0x01 << 8;
+                        assembled.push(size | addr_mode | opcode);
+                        self.encode_target(&mut assembled, target.as_ref(), None);
+                    }
+                }
+                Ast::Branch { repr, target } => {
+                    let opcode = Assembler::generate_operation_opcode(repr.token_type());
+                    let condition = Assembler::encode_condition(repr.token_type());
+                    let size = 0x02 << 30;
+                    let addr_mode = 0x01 << 8;
+
+                    assembled.push(size | condition | addr_mode | opcode);
+                    self.encode_target(
+                        &mut assembled,
+                        target.as_ref(),
+                        Some(current_position_in_bytes),
+                    );
+                }
+                Ast::Rts { repr } => {
+                    let opcode = Assembler::generate_operation_opcode(repr.token_type());
+                    assembled.push((0x02 << 30) | opcode);
+                }
                 _ => todo!(),
             }
-            current_position_in_bytes += 4;
-            //println!("{:?}", to_parse);
-
-            println!("{:#x?}", assembled);
         }
+
+        self.resolve_labels(&mut assembled);
+        assembled
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_assemble_move_size() {
+        let mut assembler = Assembler::new();
+
+        let assembled = assembler.assemble("move.dw D2, $AABBCCDD");
+
+        assert_eq!(assembled, vec![0x80008101, 0xAABBCCDD]);
+    }
+
+    #[test]
+    fn test_assemble_backward_branch() {
+        let mut assembler = Assembler::new();
+
+        let assembled = assembler.assemble("loop:\nnop\nbne loop");
+
+        assert_eq!(assembled, vec![0x00000000, 0x80008115, (-4i32) as u32]);
+        assert!(assembler.missing_labels().is_empty());
+    }
+
+    #[test]
+    fn test_assemble_forward_jump_and_subroutine() {
+        let mut assembler = Assembler::new();
+
+        let source = "jsr function\njmp end\nfunction:\nrts\nend:\nbsr function";
+        let assembled = assembler.assemble(source);
+
+        assert_eq!(
+            assembled,
+            vec![
+                0x80000116,
+                0x00000010,
+                0x80000114,
+                0x00000014,
+                0x80000017,
+                0x80000118,
+                (-4i32) as u32,
+            ]
+        );
+        assert!(assembler.missing_labels().is_empty());
+    }
+
+    #[test]
+    fn test_assemble_conditional_subroutine() {
+        let mut assembler = Assembler::new();
+
+        let assembled = assembler.assemble("function:\nrts\nbsreq function\nbsrpo function");
+
+        assert_eq!(
+            assembled,
+            vec![
+                0x80000017,
+                0x80004118,
+                (-4i32) as u32,
+                0x80040118,
+                (-12i32) as u32,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_assemble_jump_register() {
+        let mut assembler = Assembler::new();
+
+        let assembled = assembler.assemble("jmp A3");
+
+        assert_eq!(assembled, vec![0x80000000 | (0x13 << 14) | 0x14]);
+    }
+
+    #[test]
+    fn test_assemble_missing_label() {
+        let mut assembler = Assembler::new();
+
+        let assembled = assembler.assemble("bra nowhere");
+
+        assert_eq!(assembled, vec![0x80000115, 0x00000000]);
+        assert!(assembler.missing_labels().contains("nowhere"));
     }
 }
@@ -8,6 +8,7 @@ enum Opcode {
 }
 
 struct OpcodeGrammar<'a> {
+    #[allow(dead_code)]
     opcode: Opcode,
     dest_reg_modes: &'a [RegisterModes],
     dest_mem_modes: &'a [MemoryModes],
@@ -141,6 +142,17 @@ pub enum Ast<'a> {
         dest: Box<Ast<'a>>,
         src: Box<Ast<'a>>,
     },
+    Jump {
+        repr: Token<'a>,
+        target: Box<Ast<'a>>,
+    },
+    Branch {
+        repr: Token<'a>,
+        target: Box<Ast<'a>>,
+    },
+    Rts {
+        repr: Token<'a>,
+    },
 
     Size {
         repr: Token<'a>,
@@ -331,20 +343,20 @@ impl<'a> Parser<'a> {
         }
     }
 
-    fn parse_immediate_value(&mut self, current_token: TokenType) -> Option<Ast<'a>> {
+    fn parse_immediate_value(&mut self, _current_token: TokenType) -> Option<Ast<'a>> {
         let tt = self.curr_token.token_type();
         self.match_number_type(tt)
     }
 
-    fn parse_direct(&mut self, current_token: TokenType) -> Option<Ast<'a>> {
+    fn parse_direct(&mut self, _current_token: TokenType) -> Option<Ast<'a>> {
         None
     }
 
-    fn parse_direct_inc(&mut self, current_token: TokenType) -> Option<Ast<'a>> {
+    fn parse_direct_inc(&mut self, _current_token: TokenType) -> Option<Ast<'a>> {
         None
     }
 
-    fn parse_direct_dec(&mut self, current_token: TokenType) -> Option<Ast<'a>> {
+    fn parse_direct_dec(&mut self, _current_token: TokenType) -> Option<Ast<'a>> {
         None
     }
 
@@ -396,7 +408,7 @@ impl<'a> Parser<'a> {
         ))
     }
 
-    fn parse_move(&mut self, grammar: &OpcodeGrammar) -> Result<Ast, ParserError> {
+    fn parse_move(&mut self, grammar: &OpcodeGrammar) -> Result<Ast<'_>, ParserError> {
         //Why are my parsers always so messy -.- how do i improve this...?!?
         if !self.match_token(TokenType::Dot) {
             return Err(ParserError::UnexpectedSymbol(
@@ -439,7 +451,7 @@ impl<'a> Parser<'a> {
         })
     }
 
-    fn parse_lea(&mut self, grammar: &OpcodeGrammar) -> Result<Ast, ParserError> {
+    fn parse_lea(&mut self, _grammar: &OpcodeGrammar) -> Result<Ast<'_>, ParserError> {
         if !self.match_token(TokenType::Dot) {
             return Err(ParserError::UnexpectedSymbol(
                 self.curr_token.get_line(),
@@ -479,7 +491,53 @@ impl<'a> Parser<'a> {
         })
     }
 
-    fn parse_label_definition(&mut self) -> Result<Ast, ParserError> {
+    //NOTE: Targets of jumps and branches are either labels, absolute addresses or (for jumps) a register
+    //      holding the address.
+    fn parse_target(&mut self, allow_register: bool) -> Result<Ast<'a>, ParserError> {
+        let tt = self.curr_token.token_type();
+
+        if tt == TokenType::Identifier {
+            let label = Ast::Label {
+                repr: self.curr_token,
+            };
+            self.advance();
+            return Ok(label);
+        }
+
+        if allow_register {
+            if let Some(register) = self.match_all_registers(tt) {
+                return Ok(register);
+            }
+        }
+
+        self.parse_arg(&[], &[MemoryModes::ImmediateValue])
+    }
+
+    fn parse_jump(&mut self) -> Result<Ast<'_>, ParserError> {
+        let repr = self.curr_token;
+        self.advance();
+
+        let target = self.parse_target(true)?;
+
+        Ok(Ast::Jump {
+            repr,
+            target: Box::new(target),
+        })
+    }
+
+    fn parse_branch(&mut self) -> Result<Ast<'_>, ParserError> {
+        let repr = self.curr_token;
+        self.advance();
+
+        let target = self.parse_target(false)?;
+
+        Ok(Ast::Branch {
+            repr,
+            target: Box::new(target),
+        })
+    }
+
+    fn parse_label_definition(&mut self) -> Result<Ast<'_>, ParserError> {
         let label = self.curr_token;
         self.advance();
 
@@ -495,7 +553,7 @@ impl<'a> Parser<'a> {
         Ok(Ast::LabelDefinition { repr: label })
     }
 
-    pub fn parse(&mut self) -> Result<Ast, ParserError> {
+    pub fn parse(&mut self) -> Result<Ast<'_>, ParserError> {
         match self.curr_token.token_type() {
             TokenType::Identifier => self.parse_label_definition(),
             TokenType::Move => {
@@ -513,6 +571,14 @@ impl<'a> Parser<'a> {
                     repr: self.curr_token,
                 })
             }
+            TokenType::Jmp | TokenType::Jsr => self.parse_jump(),
+            _ if self.curr_token.is_branch() => self.parse_branch(),
+            TokenType::Rts => {
+                let repr = self.curr_token;
+                self.advance();
+
+                Ok(Ast::Rts { repr })
+            }
             TokenType::EndOfFile => Ok(Ast::ProgramEnd),
             _ => todo!(),
         }
@@ -609,4 +675,79 @@ mod test {
             _ => unreachable!(),
         }
     }
+
+    #[test]
+    fn test_parse_jump_to_label() {
+        let source = "jmp start";
+
+        let mut parser = Parser::new(source);
+
+        match parser.parse() {
+            Ok(Ast::Jump { repr, target }) => {
+                assert_eq!(repr.token_type(), TokenType::Jmp);
+                assert!(matches!(target.as_ref(), Ast::Label { .. }));
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_parse_jsr_register() {
+        let source = "jsr A3";
+
+        let mut parser = Parser::new(source);
+
+        match parser.parse() {
+            Ok(Ast::Jump { repr, target }) => {
+                assert_eq!(repr.token_type(), TokenType::Jsr);
+                assert!(matches!(target.as_ref(), Ast::Register { .. }));
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_parse_branch() {
+        let source = "beq loop\nbra $00001000";
+
+        let mut parser = Parser::new(source);
+
+        match parser.parse() {
+            Ok(Ast::Branch { repr, target }) => {
+                assert_eq!(repr.token_type(), TokenType::Beq);
+                assert!(matches!(target.as_ref(), Ast::Label { .. }));
+            }
+            _ => unreachable!(),
+        }
+
+        match parser.parse() {
+            Ok(Ast::Branch { repr, target }) => {
+                assert_eq!(repr.token_type(), TokenType::Bra);
+                assert!(matches!(target.as_ref(), Ast::Number { .. }));
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_parse_branch_error_register_target() {
+        let source = "bne D0";
+
+        let mut parser = Parser::new(source);
+
+        assert!(matches!(
+            parser.parse(),
+            Err(ParserError::InvalidOperand(_, _, _))
+        ));
+    }
+
+    #[test]
+    fn test_parse_rts() {
+        let source = "rts";
+
+        let mut parser = Parser::new(source);
+
+        assert!(matches!(parser.parse(), Ok(Ast::Rts { .. })));
+        assert!(matches!(parser.parse(), Ok(Ast::ProgramEnd)));
+    }
 }
@@ -0,0 +1,124 @@
+use std::{fmt::Display, path::Path};
+
+use crate::{
+    bus::{BusError, Device, BIOS_END, BIOS_START},
+    machine::Rom,
+};
+
+//NOTE: The first dword of the image is the address the cpu starts executing at after a reset
+pub const RESET_VECTOR: u32 = BIOS_START;
+pub const BIOS_SIZE: usize = (BIOS_END - BIOS_START) as usize;
+
+#[derive(Debug)]
+pub enum BiosError {
+    Io(std::io::Error),
+    //NOTE: The image doesn't fit into the BIOS region, contains the size of the image
+    TooLarge(usize),
+    //NOTE: The image is too small to contain the reset vector, contains the size of the image
+    MissingResetVector(usize),
+}
+
+impl Display for BiosError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BiosError::Io(error) => write!(f, "failed to read the bios image, {}", error),
+            BiosError::TooLarge(size) => write!(
+                f,
+                "the bios image is {} bytes but the bios region only holds {} bytes",
+                size, BIOS_SIZE
+            ),
+            BiosError::MissingResetVector(size) => write!(
+                f,
+                "the bios image is {} bytes, too small to contain the reset vector",
+                size
+            ),
+        }
+    }
+}
+
+impl From<std::io::Error> for BiosError {
+    fn from(error: std::io::Error) -> Self {
+        BiosError::Io(error)
+    }
+}
+
+//NOTE: The rom mapped at BIOS_START, accesses behave like any other rom window
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Bios {
+    rom: Rom,
+}
+
+impl Bios {
+    pub fn from_bytes(image: Vec<u8>) -> Result<Self, BiosError> {
+        if image.len() > BIOS_SIZE {
+            return Err(BiosError::TooLarge(image.len()));
+        }
+        if image.len() < 4 {
+            return Err(BiosError::MissingResetVector(image.len()));
+        }
+
+        Ok(Self {
+            rom: Rom::new(image),
+        })
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, BiosError> {
+        Self::from_bytes(std::fs::read(path)?)
+    }
+
+    pub fn reset_vector(&self) -> u32 {
+        let offset = (RESET_VECTOR - BIOS_START) as usize;
+        u32::from_be_bytes(self.rom.image()[offset..offset + 4].try_into().unwrap())
+    }
+}
+
+impl Device for Bios {
+    fn read_byte(&mut self, offset: u32) -> Result<u8, BusError> {
+        self.rom.read_byte(offset)
+    }
+
+    fn write_byte(&mut self, offset: u32, value: u8) -> Result<(), BusError> {
+        self.rom.write_byte(offset, value)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_image_validation() {
+        assert!(matches!(
+            Bios::from_bytes(vec![0x00; BIOS_SIZE + 1]),
+            Err(BiosError::TooLarge(size)) if size == BIOS_SIZE + 1
+        ));
+        assert!(matches!(
+            Bios::from_bytes(vec![0x00; 3]),
+            Err(BiosError::MissingResetVector(3))
+        ));
+        assert!(Bios::from_bytes(vec![0x00; BIOS_SIZE]).is_ok());
+    }
+
+    #[test]
+    fn test_reads_and_writes() {
+        let mut bios = Bios::from_bytes(vec![0x00, 0x00, 0x10, 0x00, 0xAB]).unwrap();
+
+        assert_eq!(bios.reset_vector(), 0x00001000);
+        assert_eq!(bios.read_byte(0x04), Ok(0xAB));
+        assert_eq!(bios.read_byte(0x05), Ok(0x00));
+        assert_eq!(bios.write_byte(0x04, 0xFF), Err(BusError::ReadOnly(0x04)));
+        assert_eq!(bios.read_byte(0x04), Ok(0xAB));
+    }
+
+    #[test]
+    fn test_load_from_file() {
+        let path = std::env::temp_dir().join(format!("dielectric_bios_{}.bin", std::process::id()));
+        std::fs::write(&path, [0x00, 0x00, 0x00, 0x40]).unwrap();
+
+        let bios = Bios::load(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(bios.unwrap().reset_vector(), 0x00000040);
+        assert!(matches!(Bios::load(&path), Err(BiosError::Io(_))));
+    }
+}
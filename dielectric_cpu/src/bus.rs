@@ -0,0 +1,778 @@
+use std::{fmt::Display, ops::Range};
+
+use crate::{cpu::opcode_size::OpcodeSize, Memory, MemoryWrite};
+
+//NOTE: Regions reserved by docs/Memory.md
+pub const BIOS_START: u32 = 0x00000000;
+pub const BIOS_END: u32 = 0x00002000;
+pub const JUMP_TABLE_START: u32 = 0x00002000;
+pub const JUMP_TABLE_END: u32 = 0x00002400;
+pub const VIDEO_MEMORY_START: u32 = 0x00002400;
+pub const VIDEO_MEMORY_END: u32 = 0x00080900;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum BusError {
+    //NOTE: Nothing answers at the address
+    OutOfBounds(u32),
+    //NOTE: A word or dword access that isn't aligned to its size while the bus is strict
+    Misaligned(u32),
+    //NOTE: The device at the address refuses the access, i.e. writes into rom
+    ReadOnly(u32),
+}
+
+impl BusError {
+    pub fn address(&self) -> u32 {
+        match self {
+            BusError::OutOfBounds(address)
+            | BusError::Misaligned(address)
+            | BusError::ReadOnly(address) => *address,
+        }
+    }
+}
+
+impl Display for BusError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BusError::OutOfBounds(address) => write!(f, "{:#010x} is out of bounds", address),
+            BusError::Misaligned(address) => write!(f, "{:#010x} is misaligned", address),
+            BusError::ReadOnly(address) => write!(f, "{:#010x} is read only", address),
+        }
+    }
+}
+
+//NOTE: Everything the cpu can talk to. Values are stored in big endian, wider accesses are built from byte accesses
+//      unless an implementation knows better.
+pub trait Bus {
+    fn read_byte(&mut self, address: u32) -> Result<u8, BusError>;
+    fn write_byte(&mut self, address: u32, value: u8) -> Result<(), BusError>;
+
+    fn read_word(&mut self, address: u32) -> Result<u16, BusError> {
+        let hi = self.read_byte(address)?;
+        let lo = self.read_byte(next_address(address, 1)?)?;
+        Ok((hi as u16) << 8 | lo as u16)
+    }
+
+    fn write_word(&mut self, address: u32, value: u16) -> Result<(), BusError> {
+        let next = next_address(address, 1)?;
+        self.write_byte(address, (value >> 8) as u8)?;
+        self.write_byte(next, value as u8)
+    }
+
+    fn read_dword(&mut self, address: u32) -> Result<u32, BusError> {
+        let hi = self.read_word(address)?;
+        let lo = self.read_word(next_address(address, 2)?)?;
+        Ok((hi as u32) << 16 | lo as u32)
+    }
+
+    fn write_dword(&mut self, address: u32, value: u32) -> Result<(), BusError> {
+        let next = next_address(address, 2)?;
+        self.write_word(address, (value >> 16) as u16)?;
+        self.write_word(next, value as u16)
+    }
+
+    //NOTE: Reads instruction words and their extension words, unlike data reads they don't trigger watchpoints
+    fn fetch_dword(&mut self, address: u32) -> Result<u32, BusError> {
+        self.read_dword(address)
+    }
+
+    fn memory_bus_read(&mut self, size: &OpcodeSize, address: u32) -> Result<u32, BusError> {
+        match size {
+            OpcodeSize::Byte => self.read_byte(address).map(|value| value as u32),
+            OpcodeSize::Word => self.read_word(address).map(|value| value as u32),
+            OpcodeSize::Dword => self.read_dword(address),
+        }
+    }
+
+    fn memory_bus_write(&mut self, command: MemoryWrite) -> Result<(), BusError> {
+        match command {
+            MemoryWrite::Byte { address, value } => self.write_byte(address, value),
+            MemoryWrite::Word { address, value } => self.write_word(address, value),
+            MemoryWrite::Dword { address, value } => self.write_dword(address, value),
+        }
+    }
+}
+
+//NOTE: Accesses never wrap around the top of the address space
+fn next_address(address: u32, offset: u32) -> Result<u32, BusError> {
+    address
+        .checked_add(offset)
+        .ok_or(BusError::OutOfBounds(address))
+}
+
+//NOTE: A peripheral mapped into the address space, it only ever sees offsets relative to the start of its range
+pub trait Device {
+    fn read_byte(&mut self, offset: u32) -> Result<u8, BusError>;
+    fn write_byte(&mut self, offset: u32, value: u8) -> Result<(), BusError>;
+
+    //NOTE: Called after every instruction with the cycles it took, devices that keep time advance here.
+    //      Returns the IRQ lines the device raises, one bit per line.
+    fn tick(&mut self, _cycles: u32) -> u16 {
+        0x00
+    }
+
+    //NOTE: Devices with state of their own hand it to snapshots here, stateless ones like roms return None
+    fn save_state(&self) -> Option<Vec<u8>> {
+        None
+    }
+
+    //NOTE: Puts back a state taken with save_state, returns false and leaves the device untouched if the state doesn't
+    //      belong to the device
+    fn restore_state(&mut self, _state: &[u8]) -> bool {
+        false
+    }
+}
+
+//NOTE: Strict buses fault on word and dword accesses that aren't aligned to their size, permissive ones allow them
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum AccessMode {
+    Strict,
+    #[default]
+    Permissive,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Access {
+    Read,
+    Write,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum WatchKind {
+    Read,
+    Write,
+    ReadWrite,
+}
+
+impl WatchKind {
+    fn matches(&self, access: Access) -> bool {
+        matches!(
+            (self, access),
+            (WatchKind::ReadWrite, _)
+                | (WatchKind::Read, Access::Read)
+                | (WatchKind::Write, Access::Write)
+        )
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Watchpoint {
+    pub range: Range<u32>,
+    pub kind: WatchKind,
+}
+
+//NOTE: The first byte access that matched a watchpoint
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct WatchpointHit {
+    pub address: u32,
+    pub access: Access,
+}
+
+//NOTE: A completed data access, word and dword accesses are a single entry. Faulting accesses aren't logged.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct MemoryAccess {
+    pub address: u32,
+    pub access: Access,
+    pub size: OpcodeSize,
+    pub value: u32,
+}
+
+impl Display for MemoryAccess {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let access = match self.access {
+            Access::Read => "R",
+            Access::Write => "W",
+        };
+        write!(
+            f,
+            "{}.{} {:#010x}={:#x}",
+            access, self.size, self.address, self.value
+        )
+    }
+}
+
+impl Display for WatchpointHit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.access {
+            Access::Read => write!(f, "read watchpoint at {:#010x}", self.address),
+            Access::Write => write!(f, "write watchpoint at {:#010x}", self.address),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum MapError {
+    EmptyRange { start: u32, end: u32 },
+    Overlap { start: u32, end: u32 },
+}
+
+impl Display for MapError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MapError::EmptyRange { start, end } => {
+                write!(f, "the range {:#010x}..{:#010x} is empty", start, end)
+            }
+            MapError::Overlap { start, end } => write!(
+                f,
+                "the range {:#010x}..{:#010x} overlaps an already mapped device",
+                start, end
+            ),
+        }
+    }
+}
+
+struct MappedDevice {
+    start: u32,
+    end: u32,
+    device: Box<dyn Device>,
+}
+
+//NOTE: Routes every access to the device mapped at the address, everything that isn't mapped ends up in ram
+pub struct SystemBus {
+    ram: Memory,
+    devices: Vec<MappedDevice>,
+    access_mode: AccessMode,
+    watchpoints: Vec<Watchpoint>,
+    watchpoint_hit: Option<WatchpointHit>,
+    //NOTE: Instruction fetches don't trigger watchpoints and aren't logged
+    fetching: bool,
+    access_log: Option<Vec<MemoryAccess>>,
+}
+
+impl Default for SystemBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SystemBus {
+    pub fn new() -> Self {
+        Self::with_ram_size(crate::MEMORY_SIZE as u32)
+    }
+
+    //NOTE: Ram covers the addresses 0..size
+    pub fn with_ram_size(size: u32) -> Self {
+        Self {
+            ram: Memory::new(size),
+            devices: vec![],
+            access_mode: AccessMode::default(),
+            watchpoints: vec![],
+            watchpoint_hit: None,
+            fetching: false,
+            access_log: None,
+        }
+    }
+
+    pub fn access_mode(&self) -> AccessMode {
+        self.access_mode
+    }
+
+    pub fn set_access_mode(&mut self, access_mode: AccessMode) {
+        self.access_mode = access_mode;
+    }
+
+    pub fn add_watchpoint(&mut self, range: Range<u32>, kind: WatchKind) {
+        self.watchpoints.push(Watchpoint { range, kind });
+    }
+
+    //NOTE: Removes every watchpoint that starts at the address, returns whether there was one
+    pub fn remove_watchpoints(&mut self, start: u32) -> bool {
+        let before = self.watchpoints.len();
+        self.watchpoints
+            .retain(|watchpoint| watchpoint.range.start != start);
+        self.watchpoints.len() != before
+    }
+
+    pub fn watchpoints(&self) -> &[Watchpoint] {
+        &self.watchpoints
+    }
+
+    //NOTE: Returns the first hit since the last call
+    pub fn take_watchpoint_hit(&mut self) -> Option<WatchpointHit> {
+        self.watchpoint_hit.take()
+    }
+
+    //NOTE: Logging is off by default, turning it off drops everything that was logged
+    pub fn set_access_logging(&mut self, enabled: bool) {
+        self.access_log = if enabled { Some(vec![]) } else { None };
+    }
+
+    //NOTE: Returns the accesses logged since the last call
+    pub fn take_accesses(&mut self) -> Vec<MemoryAccess> {
+        self.access_log
+            .as_mut()
+            .map(std::mem::take)
+            .unwrap_or_default()
+    }
+
+    fn log(&mut self, address: u32, access: Access, size: OpcodeSize, value: u32) {
+        if self.fetching {
+            return;
+        }
+        if let Some(log) = &mut self.access_log {
+            log.push(MemoryAccess {
+                address,
+                access,
+                size,
+                value,
+            });
+        }
+    }
+
+    fn watch(&mut self, address: u32, access: Access) {
+        if self.fetching || self.watchpoint_hit.is_some() {
+            return;
+        }
+
+        let hit = self.watchpoints.iter().any(|watchpoint| {
+            watchpoint.range.contains(&address) && watchpoint.kind.matches(access)
+        });
+        if hit {
+            self.watchpoint_hit = Some(WatchpointHit { address, access });
+        }
+    }
+
+    fn check_alignment(&self, address: u32, size: u32) -> Result<(), BusError> {
+        if self.access_mode == AccessMode::Strict && !address.is_multiple_of(size) {
+            return Err(BusError::Misaligned(address));
+        }
+        Ok(())
+    }
+
+    //NOTE: Checked up front so a faulting access never leaves a partial write behind. Every byte has to go to the same
+    //      device or to ram, an access is never split between two targets.
+    fn check_range(&mut self, address: u32, size: u32) -> Result<(), BusError> {
+        next_address(address, size - 1)?;
+        let target = self.target_at(address)?;
+        for address in address + 1..address + size {
+            if self.target_at(address)? != target {
+                return Err(BusError::OutOfBounds(address));
+            }
+        }
+        Ok(())
+    }
+
+    //NOTE: The index of the device the address is mapped to or None for ram
+    fn target_at(&self, address: u32) -> Result<Option<usize>, BusError> {
+        match self
+            .devices
+            .iter()
+            .position(|mapped| (mapped.start..mapped.end).contains(&address))
+        {
+            Some(index) => Ok(Some(index)),
+            None if self.ram.contains(address) => Ok(None),
+            None => Err(BusError::OutOfBounds(address)),
+        }
+    }
+
+    //NOTE: Maps the device to the addresses start..end
+    pub fn map_device(
+        &mut self,
+        start: u32,
+        end: u32,
+        device: Box<dyn Device>,
+    ) -> Result<(), MapError> {
+        if start >= end {
+            return Err(MapError::EmptyRange { start, end });
+        }
+
+        if self
+            .devices
+            .iter()
+            .any(|mapped| start < mapped.end && mapped.start < end)
+        {
+            return Err(MapError::Overlap { start, end });
+        }
+
+        self.devices.push(MappedDevice { start, end, device });
+        Ok(())
+    }
+
+    //NOTE: Lets every mapped device know that cycles passed, returns the IRQ lines raised by any of them
+    pub fn tick(&mut self, cycles: u32) -> u16 {
+        self.devices
+            .iter_mut()
+            .fold(0x00, |lines, mapped| lines | mapped.device.tick(cycles))
+    }
+
+    //NOTE: The start address and the state of every mapped device that has one, used by snapshots
+    pub(crate) fn device_states(&self) -> Vec<(u32, Vec<u8>)> {
+        self.devices
+            .iter()
+            .filter_map(|mapped| Some((mapped.start, mapped.device.save_state()?)))
+            .collect()
+    }
+
+    //NOTE: Every device with state needs one in the list and every state a device at its address. Either all devices
+    //      are restored or none, on failure the address of the first device that didn't fit is returned.
+    pub(crate) fn restore_device_states(&mut self, states: &[(u32, Vec<u8>)]) -> Result<(), u32> {
+        let current = self.device_states();
+        let stored = |index: usize| states.get(index).map(|(start, _)| *start);
+        let mapped = |index: usize| current.get(index).map(|(start, _)| *start);
+        if let Some(index) =
+            (0..current.len().max(states.len())).find(|&index| mapped(index) != stored(index))
+        {
+            return Err(stored(index).or(mapped(index)).unwrap());
+        }
+
+        for (index, (start, state)) in states.iter().enumerate() {
+            if !self.device_mut(*start).restore_state(state) {
+                //NOTE: Puts back the devices restored so far, their old states came from them so they fit
+                for (start, state) in &current[..index] {
+                    self.device_mut(*start).restore_state(state);
+                }
+                return Err(*start);
+            }
+        }
+        Ok(())
+    }
+
+    fn device_mut(&mut self, start: u32) -> &mut Box<dyn Device> {
+        let mapped = self.devices.iter_mut().find(|mapped| mapped.start == start);
+        &mut mapped.unwrap().device
+    }
+
+    //NOTE: Direct access to the ram behind the bus without going through devices, used by snapshots
+    pub(crate) fn ram(&self) -> &Memory {
+        &self.ram
+    }
+
+    pub(crate) fn ram_mut(&mut self) -> &mut Memory {
+        &mut self.ram
+    }
+
+    fn device_at(&mut self, address: u32) -> Option<&mut MappedDevice> {
+        self.devices
+            .iter_mut()
+            .find(|mapped| (mapped.start..mapped.end).contains(&address))
+    }
+}
+
+impl SystemBus {
+    fn load(&mut self, address: u32) -> Result<u8, BusError> {
+        let value = match self.device_at(address) {
+            //NOTE: Devices report their errors relative to their own range
+            Some(mapped) => {
+                let start = mapped.start;
+                mapped
+                    .device
+                    .read_byte(address - start)
+                    .map_err(|error| rebase(error, start))
+            }
+            None => self.ram.read_byte(address),
+        }?;
+
+        self.watch(address, Access::Read);
+        Ok(value)
+    }
+
+    fn store(&mut self, address: u32, value: u8) -> Result<(), BusError> {
+        match self.device_at(address) {
+            Some(mapped) => {
+                let start = mapped.start;
+                mapped
+                    .device
+                    .write_byte(address - start, value)
+                    .map_err(|error| rebase(error, start))
+            }
+            None => self.ram.write_byte(address, value),
+        }?;
+
+        self.watch(address, Access::Write);
+        Ok(())
+    }
+}
+
+impl Bus for SystemBus {
+    fn read_byte(&mut self, address: u32) -> Result<u8, BusError> {
+        let value = self.load(address)?;
+        self.log(address, Access::Read, OpcodeSize::Byte, value as u32);
+        Ok(value)
+    }
+
+    fn write_byte(&mut self, address: u32, value: u8) -> Result<(), BusError> {
+        self.store(address, value)?;
+        self.log(address, Access::Write, OpcodeSize::Byte, value as u32);
+        Ok(())
+    }
+
+    fn fetch_dword(&mut self, address: u32) -> Result<u32, BusError> {
+        self.fetching = true;
+        let value = self.read_dword(address);
+        self.fetching = false;
+        value
+    }
+
+    fn read_word(&mut self, address: u32) -> Result<u16, BusError> {
+        self.check_alignment(address, 2)?;
+        self.check_range(address, 2)?;
+        let value = (self.load(address)? as u16) << 8 | self.load(address + 1)? as u16;
+        self.log(address, Access::Read, OpcodeSize::Word, value as u32);
+        Ok(value)
+    }
+
+    fn write_word(&mut self, address: u32, value: u16) -> Result<(), BusError> {
+        self.check_alignment(address, 2)?;
+        self.check_range(address, 2)?;
+        self.store(address, (value >> 8) as u8)?;
+        self.store(address + 1, value as u8)?;
+        self.log(address, Access::Write, OpcodeSize::Word, value as u32);
+        Ok(())
+    }
+
+    fn read_dword(&mut self, address: u32) -> Result<u32, BusError> {
+        self.check_alignment(address, 4)?;
+        self.check_range(address, 4)?;
+        let mut value = 0;
+        for offset in 0..4 {
+            value = value << 8 | self.load(address + offset)? as u32;
+        }
+        self.log(address, Access::Read, OpcodeSize::Dword, value);
+        Ok(value)
+    }
+
+    fn write_dword(&mut self, address: u32, value: u32) -> Result<(), BusError> {
+        self.check_alignment(address, 4)?;
+        self.check_range(address, 4)?;
+        for offset in 0..4 {
+            self.store(address + offset, (value >> (24 - offset * 8)) as u8)?;
+        }
+        self.log(address, Access::Write, OpcodeSize::Dword, value);
+        Ok(())
+    }
+}
+
+fn rebase(error: BusError, start: u32) -> BusError {
+    match error {
+        BusError::OutOfBounds(offset) => BusError::OutOfBounds(start + offset),
+        BusError::Misaligned(offset) => BusError::Misaligned(start + offset),
+        BusError::ReadOnly(offset) => BusError::ReadOnly(start + offset),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{cell::RefCell, rc::Rc};
+
+    use super::*;
+
+    type Writes = Rc<RefCell<Vec<(u32, u8)>>>;
+
+    //NOTE: Remembers every write so the tests can check what reached the device
+    struct RecordingDevice {
+        writes: Writes,
+    }
+
+    impl Device for RecordingDevice {
+        fn read_byte(&mut self, offset: u32) -> Result<u8, BusError> {
+            Ok(offset as u8)
+        }
+
+        fn write_byte(&mut self, offset: u32, value: u8) -> Result<(), BusError> {
+            self.writes.borrow_mut().push((offset, value));
+            Ok(())
+        }
+    }
+
+    fn recording_device() -> (Box<dyn Device>, Writes) {
+        let writes = Rc::new(RefCell::new(vec![]));
+        let device = RecordingDevice {
+            writes: writes.clone(),
+        };
+        (Box::new(device), writes)
+    }
+
+    #[test]
+    fn test_unmapped_addresses_go_to_ram() {
+        let mut bus = SystemBus::new();
+
+        bus.write_dword(0x00100000, 0xDEADBEEF).unwrap();
+
+        assert_eq!(bus.read_dword(0x00100000).unwrap(), 0xDEADBEEF);
+        assert_eq!(bus.read_word(0x00100002).unwrap(), 0xBEEF);
+        assert_eq!(bus.read_byte(0x00100000).unwrap(), 0xDE);
+    }
+
+    #[test]
+    fn test_device_routing() {
+        let mut bus = SystemBus::new();
+        let (device, writes) = recording_device();
+        bus.map_device(0x00010000, 0x00010010, device).unwrap();
+
+        bus.write_word(0x00010004, 0xCAFE).unwrap();
+        bus.write_byte(0x00010010, 0xAA).unwrap();
+
+        assert_eq!(*writes.borrow(), vec![(0x04, 0xCA), (0x05, 0xFE)]);
+        assert_eq!(bus.read_dword(0x0001000C).unwrap(), 0x0C0D0E0F);
+        assert_eq!(bus.read_byte(0x00010010).unwrap(), 0xAA);
+        assert_eq!(bus.read_byte(0x0000FFFF).unwrap(), 0x00);
+    }
+
+    #[test]
+    fn test_map_errors() {
+        let mut bus = SystemBus::new();
+        bus.map_device(0x1000, 0x2000, recording_device().0)
+            .unwrap();
+
+        assert_eq!(
+            bus.map_device(0x1800, 0x2800, recording_device().0),
+            Err(MapError::Overlap {
+                start: 0x1800,
+                end: 0x2800
+            })
+        );
+        assert_eq!(
+            bus.map_device(0x0800, 0x1001, recording_device().0),
+            Err(MapError::Overlap {
+                start: 0x0800,
+                end: 0x1001
+            })
+        );
+        assert_eq!(
+            bus.map_device(0x3000, 0x3000, recording_device().0),
+            Err(MapError::EmptyRange {
+                start: 0x3000,
+                end: 0x3000
+            })
+        );
+        assert!(bus.map_device(0x2000, 0x3000, recording_device().0).is_ok());
+    }
+
+    #[test]
+    fn test_out_of_bounds_accesses() {
+        let mut bus = SystemBus::new();
+        let end = crate::MEMORY_SIZE as u32;
+
+        assert_eq!(bus.read_byte(end), Err(BusError::OutOfBounds(end)));
+        assert_eq!(
+            bus.write_dword(0xFFFFFFFE, 0x00),
+            Err(BusError::OutOfBounds(0xFFFFFFFE))
+        );
+
+        //NOTE: Accesses crossing the end of ram fault without touching the bytes that are still inside
+        bus.write_byte(end - 2, 0x12).unwrap();
+        assert_eq!(
+            bus.write_dword(end - 2, 0xDEADBEEF),
+            Err(BusError::OutOfBounds(end))
+        );
+        assert_eq!(bus.read_byte(end - 2), Ok(0x12));
+        assert_eq!(bus.read_word(end - 2), Ok(0x1200));
+    }
+
+    #[test]
+    fn test_accesses_are_never_split_between_targets() {
+        let mut bus = SystemBus::new();
+        let end = crate::MEMORY_SIZE as u32;
+        let (first, first_writes) = recording_device();
+        let (second, second_writes) = recording_device();
+        bus.map_device(0x00010000, 0x00010010, first).unwrap();
+        bus.map_device(end, end + 0x10, second).unwrap();
+        bus.map_device(end + 0x14, end + 0x20, recording_device().0)
+            .unwrap();
+
+        //NOTE: Device into ram, ram into device and device over a gap into another device
+        assert_eq!(
+            bus.write_dword(0x0001000E, 0xDEADBEEF),
+            Err(BusError::OutOfBounds(0x00010010))
+        );
+        assert_eq!(
+            bus.write_word(0x0000FFFF, 0xCAFE),
+            Err(BusError::OutOfBounds(0x00010000))
+        );
+        assert_eq!(bus.read_dword(end - 2), Err(BusError::OutOfBounds(end)));
+        assert_eq!(
+            bus.write_dword(end + 0x0E, 0xDEADBEEF),
+            Err(BusError::OutOfBounds(end + 0x10))
+        );
+        assert_eq!(
+            bus.read_dword(end + 0x12),
+            Err(BusError::OutOfBounds(end + 0x12))
+        );
+
+        assert!(first_writes.borrow().is_empty());
+        assert!(second_writes.borrow().is_empty());
+        assert_eq!(bus.read_byte(0x0000FFFF), Ok(0x00));
+        assert_eq!(bus.read_byte(0x00010010), Ok(0x00));
+    }
+
+    #[test]
+    fn test_access_modes() {
+        let mut bus = SystemBus::new();
+        assert_eq!(bus.access_mode(), AccessMode::Permissive);
+
+        bus.write_dword(0x00000101, 0xDEADBEEF).unwrap();
+        assert_eq!(bus.read_word(0x00000103), Ok(0xBEEF));
+
+        bus.set_access_mode(AccessMode::Strict);
+        assert_eq!(
+            bus.read_word(0x00000103),
+            Err(BusError::Misaligned(0x00000103))
+        );
+        assert_eq!(
+            bus.write_dword(0x00000102, 0x00),
+            Err(BusError::Misaligned(0x00000102))
+        );
+        assert_eq!(bus.read_dword(0x00000100), Ok(0x00DEADBE));
+        assert_eq!(bus.read_byte(0x00000101), Ok(0xDE));
+    }
+
+    #[test]
+    fn test_device_errors_are_reported_at_the_bus_address() {
+        struct ReadOnlyDevice;
+
+        impl Device for ReadOnlyDevice {
+            fn read_byte(&mut self, _offset: u32) -> Result<u8, BusError> {
+                Ok(0xFF)
+            }
+
+            fn write_byte(&mut self, offset: u32, _value: u8) -> Result<(), BusError> {
+                Err(BusError::ReadOnly(offset))
+            }
+        }
+
+        let mut bus = SystemBus::new();
+        bus.map_device(0x00010000, 0x00010100, Box::new(ReadOnlyDevice))
+            .unwrap();
+
+        assert_eq!(bus.read_word(0x00010010), Ok(0xFFFF));
+        assert_eq!(
+            bus.write_byte(0x00010010, 0x00),
+            Err(BusError::ReadOnly(0x00010010))
+        );
+    }
+
+    #[test]
+    fn test_watchpoints() {
+        let mut bus = SystemBus::new();
+        bus.add_watchpoint(0x00001000..0x00001004, WatchKind::Write);
+        bus.add_watchpoint(0x00002000..0x00002001, WatchKind::ReadWrite);
+
+        bus.read_dword(0x00001000).unwrap();
+        assert_eq!(bus.take_watchpoint_hit(), None);
+
+        bus.write_dword(0x00000FFE, 0xDEADBEEF).unwrap();
+        bus.write_byte(0x00002000, 0x00).unwrap();
+        assert_eq!(
+            bus.take_watchpoint_hit(),
+            Some(WatchpointHit {
+                address: 0x00001000,
+                access: Access::Write
+            })
+        );
+        assert_eq!(bus.take_watchpoint_hit(), None);
+
+        assert_eq!(bus.fetch_dword(0x00001FFE), Ok(0x00000000));
+        assert_eq!(bus.take_watchpoint_hit(), None);
+        bus.read_word(0x00001FFF).unwrap();
+        assert_eq!(
+            bus.take_watchpoint_hit(),
+            Some(WatchpointHit {
+                address: 0x00002000,
+                access: Access::Read
+            })
+        );
+
+        assert!(bus.remove_watchpoints(0x00002000));
+        assert!(!bus.remove_watchpoints(0x00002000));
+        assert_eq!(bus.watchpoints().len(), 1);
+    }
+}
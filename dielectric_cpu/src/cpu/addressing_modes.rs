@@ -1,5 +1,3 @@
-use super::opcode::Execute;
-
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum AddressingMode {
     Atomic,
@@ -27,18 +25,20 @@ impl From<AddressingMode> for u32 {
     }
 }
 
-impl From<u32> for AddressingMode {
-    fn from(value: u32) -> Self {
+impl TryFrom<u32> for AddressingMode {
+    type Error = u32;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
         match value {
-            0x00 => AddressingMode::Atomic,
-            0x01 => AddressingMode::Immediate,
-            0x02 => AddressingMode::MemoryDest,
-            0x03 => AddressingMode::MemorySrc,
-            0x04 => AddressingMode::MemoryDestInc,
-            0x05 => AddressingMode::MemorySrcInc,
-            0x06 => AddressingMode::MemoryDestDec,
-            0x07 => AddressingMode::MemorySrcDec,
-            _ => unreachable!(),
+            0x00 => Ok(AddressingMode::Atomic),
+            0x01 => Ok(AddressingMode::Immediate),
+            0x02 => Ok(AddressingMode::MemoryDest),
+            0x03 => Ok(AddressingMode::MemorySrc),
+            0x04 => Ok(AddressingMode::MemoryDestInc),
+            0x05 => Ok(AddressingMode::MemorySrcInc),
+            0x06 => Ok(AddressingMode::MemoryDestDec),
+            0x07 => Ok(AddressingMode::MemorySrcDec),
+            _ => Err(value),
         }
     }
 }
@@ -0,0 +1,366 @@
+use super::opcode_size::OpcodeSize;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub(crate) struct AluResult {
+    pub(crate) value: u32,
+    pub(crate) carry: bool,
+    pub(crate) overflow: bool,
+}
+
+//NOTE: All calculations are done on the lower bits selected by the size, the upper bits of the operands are ignored!
+pub(crate) fn add(size: &OpcodeSize, lhs: u32, rhs: u32, carry_in: bool) -> AluResult {
+    let mask = size.retrieve_data(u32::MAX);
+    let lhs = size.retrieve_data(lhs);
+    let rhs = size.retrieve_data(rhs);
+
+    let full = lhs as u64 + rhs as u64 + carry_in as u64;
+    let value = (full as u32) & mask;
+
+    AluResult {
+        value,
+        carry: full > mask as u64,
+        overflow: (lhs ^ value) & (rhs ^ value) & size.sign_bit() != 0,
+    }
+}
+
+pub(crate) fn sub(size: &OpcodeSize, lhs: u32, rhs: u32, borrow_in: bool) -> AluResult {
+    let mask = size.retrieve_data(u32::MAX);
+    let lhs = size.retrieve_data(lhs);
+    let rhs = size.retrieve_data(rhs);
+
+    let value = lhs.wrapping_sub(rhs).wrapping_sub(borrow_in as u32) & mask;
+
+    AluResult {
+        value,
+        carry: (rhs as u64 + borrow_in as u64) > lhs as u64,
+        overflow: (lhs ^ rhs) & (lhs ^ value) & size.sign_bit() != 0,
+    }
+}
+
+pub(crate) fn logic(size: &OpcodeSize, value: u32) -> AluResult {
+    AluResult {
+        value: size.retrieve_data(value),
+        carry: false,
+        overflow: false,
+    }
+}
+
+fn bit_width(size: &OpcodeSize) -> u32 {
+    size.size_in_bytes() * 8
+}
+
+fn bit_is_set(value: u32, bit: u32) -> bool {
+    bit < 32 && (value >> bit) & 0x01 != 0
+}
+
+//NOTE: For all shifts and rotates the carry holds the last bit that was shifted out, a count of zero clears it.
+pub(crate) fn shift_left(size: &OpcodeSize, value: u32, count: u32) -> AluResult {
+    let width = bit_width(size);
+    let value = size.retrieve_data(value);
+
+    let (result, carry) = match count {
+        0 => (value, false),
+        count if count < width => (value << count, bit_is_set(value, width - count)),
+        count if count == width => (0x00, bit_is_set(value, 0)),
+        _ => (0x00, false),
+    };
+
+    logic_with_carry(size, result, carry)
+}
+
+pub(crate) fn shift_right(size: &OpcodeSize, value: u32, count: u32) -> AluResult {
+    let width = bit_width(size);
+    let value = size.retrieve_data(value);
+
+    let (result, carry) = match count {
+        0 => (value, false),
+        count if count < width => (value >> count, bit_is_set(value, count - 1)),
+        count if count == width => (0x00, bit_is_set(value, width - 1)),
+        _ => (0x00, false),
+    };
+
+    logic_with_carry(size, result, carry)
+}
+
+pub(crate) fn arithmetic_shift_right(size: &OpcodeSize, value: u32, count: u32) -> AluResult {
+    let width = bit_width(size);
+    let value = size.retrieve_data(value);
+    let negative = value & size.sign_bit() != 0x00;
+
+    //NOTE: Move the value into the upper bits so the native arithmetic shift takes care of the sign
+    let extended = (value << (32 - width)) as i32;
+    let (result, carry) = match count {
+        0 => (value, false),
+        count if count < width => (
+            (extended >> (count + 32 - width)) as u32,
+            bit_is_set(value, count - 1),
+        ),
+        _ => (if negative { u32::MAX } else { 0x00 }, negative),
+    };
+
+    logic_with_carry(size, result, carry)
+}
+
+pub(crate) fn rotate_left(size: &OpcodeSize, value: u32, count: u32) -> AluResult {
+    let width = bit_width(size);
+    let value = size.retrieve_data(value);
+    let count_mod = count % width;
+
+    let result = if count_mod == 0 {
+        value
+    } else {
+        (value << count_mod) | (value >> (width - count_mod))
+    };
+    let result = size.retrieve_data(result);
+
+    //NOTE: The last bit rotated out of the top ends up in bit zero
+    logic_with_carry(size, result, count != 0 && bit_is_set(result, 0))
+}
+
+pub(crate) fn rotate_right(size: &OpcodeSize, value: u32, count: u32) -> AluResult {
+    let width = bit_width(size);
+    let value = size.retrieve_data(value);
+    let count_mod = count % width;
+
+    let result = if count_mod == 0 {
+        value
+    } else {
+        (value >> count_mod) | (value << (width - count_mod))
+    };
+    let result = size.retrieve_data(result);
+
+    //NOTE: The last bit rotated out of the bottom ends up in the sign bit
+    logic_with_carry(size, result, count != 0 && result & size.sign_bit() != 0x00)
+}
+
+fn logic_with_carry(size: &OpcodeSize, value: u32, carry: bool) -> AluResult {
+    AluResult {
+        carry,
+        ..logic(size, value)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_add_carry_per_size() {
+        let result = add(&OpcodeSize::Byte, 0xFF, 0x01, false);
+        assert_eq!(result.value, 0x00);
+        assert!(result.carry);
+        assert!(!result.overflow);
+
+        let result = add(&OpcodeSize::Word, 0xFFFF, 0x01, false);
+        assert_eq!(result.value, 0x0000);
+        assert!(result.carry);
+        assert!(!result.overflow);
+
+        let result = add(&OpcodeSize::Dword, 0xFFFFFFFF, 0x01, false);
+        assert_eq!(result.value, 0x00000000);
+        assert!(result.carry);
+        assert!(!result.overflow);
+    }
+
+    #[test]
+    fn test_add_overflow_per_size() {
+        let result = add(&OpcodeSize::Byte, 0x7F, 0x01, false);
+        assert_eq!(result.value, 0x80);
+        assert!(!result.carry);
+        assert!(result.overflow);
+
+        let result = add(&OpcodeSize::Word, 0x7FFF, 0x01, false);
+        assert_eq!(result.value, 0x8000);
+        assert!(!result.carry);
+        assert!(result.overflow);
+
+        let result = add(&OpcodeSize::Dword, 0x7FFFFFFF, 0x01, false);
+        assert_eq!(result.value, 0x80000000);
+        assert!(!result.carry);
+        assert!(result.overflow);
+
+        let result = add(&OpcodeSize::Dword, 0x80000000, 0x80000000, false);
+        assert_eq!(result.value, 0x00000000);
+        assert!(result.carry);
+        assert!(result.overflow);
+    }
+
+    #[test]
+    fn test_add_ignores_upper_bits() {
+        let result = add(&OpcodeSize::Byte, 0xAABBCC10, 0x11223320, false);
+        assert_eq!(result.value, 0x30);
+        assert!(!result.carry);
+    }
+
+    #[test]
+    fn test_add_with_carry_in() {
+        let result = add(&OpcodeSize::Byte, 0xFE, 0x01, true);
+        assert_eq!(result.value, 0x00);
+        assert!(result.carry);
+
+        let result = add(&OpcodeSize::Dword, 0x10, 0x20, true);
+        assert_eq!(result.value, 0x31);
+        assert!(!result.carry);
+    }
+
+    #[test]
+    fn test_sub_borrow_per_size() {
+        let result = sub(&OpcodeSize::Byte, 0x00, 0x01, false);
+        assert_eq!(result.value, 0xFF);
+        assert!(result.carry);
+        assert!(!result.overflow);
+
+        let result = sub(&OpcodeSize::Word, 0x0000, 0x0001, false);
+        assert_eq!(result.value, 0xFFFF);
+        assert!(result.carry);
+        assert!(!result.overflow);
+
+        let result = sub(&OpcodeSize::Dword, 0x00000000, 0x00000001, false);
+        assert_eq!(result.value, 0xFFFFFFFF);
+        assert!(result.carry);
+        assert!(!result.overflow);
+    }
+
+    #[test]
+    fn test_sub_overflow_per_size() {
+        let result = sub(&OpcodeSize::Byte, 0x80, 0x01, false);
+        assert_eq!(result.value, 0x7F);
+        assert!(!result.carry);
+        assert!(result.overflow);
+
+        let result = sub(&OpcodeSize::Word, 0x8000, 0x0001, false);
+        assert_eq!(result.value, 0x7FFF);
+        assert!(!result.carry);
+        assert!(result.overflow);
+
+        let result = sub(&OpcodeSize::Dword, 0x80000000, 0x00000001, false);
+        assert_eq!(result.value, 0x7FFFFFFF);
+        assert!(!result.carry);
+        assert!(result.overflow);
+    }
+
+    #[test]
+    fn test_sub_with_borrow_in() {
+        let result = sub(&OpcodeSize::Byte, 0x01, 0x01, true);
+        assert_eq!(result.value, 0xFF);
+        assert!(result.carry);
+
+        let result = sub(&OpcodeSize::Word, 0x10, 0x01, true);
+        assert_eq!(result.value, 0x0E);
+        assert!(!result.carry);
+    }
+
+    #[test]
+    fn test_logic_clears_carry_and_overflow() {
+        let result = logic(&OpcodeSize::Word, 0xFFFF8000);
+        assert_eq!(result.value, 0x8000);
+        assert!(!result.carry);
+        assert!(!result.overflow);
+    }
+
+    #[test]
+    fn test_shift_left_per_size() {
+        let result = shift_left(&OpcodeSize::Byte, 0x81, 1);
+        assert_eq!(result.value, 0x02);
+        assert!(result.carry);
+
+        let result = shift_left(&OpcodeSize::Word, 0x4001, 2);
+        assert_eq!(result.value, 0x0004);
+        assert!(result.carry);
+
+        let result = shift_left(&OpcodeSize::Dword, 0x40000001, 1);
+        assert_eq!(result.value, 0x80000002);
+        assert!(!result.carry);
+
+        let result = shift_left(&OpcodeSize::Byte, 0x01, 8);
+        assert_eq!(result.value, 0x00);
+        assert!(result.carry);
+
+        let result = shift_left(&OpcodeSize::Dword, 0xFFFFFFFF, 33);
+        assert_eq!(result.value, 0x00);
+        assert!(!result.carry);
+    }
+
+    #[test]
+    fn test_shift_right_per_size() {
+        let result = shift_right(&OpcodeSize::Byte, 0xFF81, 1);
+        assert_eq!(result.value, 0x40);
+        assert!(result.carry);
+
+        let result = shift_right(&OpcodeSize::Word, 0x8002, 2);
+        assert_eq!(result.value, 0x2000);
+        assert!(result.carry);
+
+        let result = shift_right(&OpcodeSize::Dword, 0x80000000, 31);
+        assert_eq!(result.value, 0x01);
+        assert!(!result.carry);
+
+        let result = shift_right(&OpcodeSize::Word, 0x8000, 16);
+        assert_eq!(result.value, 0x00);
+        assert!(result.carry);
+    }
+
+    #[test]
+    fn test_shift_count_zero_keeps_value() {
+        let result = shift_left(&OpcodeSize::Dword, 0xDEADBEEF, 0);
+        assert_eq!(result.value, 0xDEADBEEF);
+        assert!(!result.carry);
+
+        let result = rotate_right(&OpcodeSize::Dword, 0xDEADBEEF, 0);
+        assert_eq!(result.value, 0xDEADBEEF);
+        assert!(!result.carry);
+    }
+
+    #[test]
+    fn test_arithmetic_shift_right_per_size() {
+        let result = arithmetic_shift_right(&OpcodeSize::Byte, 0x81, 1);
+        assert_eq!(result.value, 0xC0);
+        assert!(result.carry);
+
+        let result = arithmetic_shift_right(&OpcodeSize::Word, 0x8000, 15);
+        assert_eq!(result.value, 0xFFFF);
+        assert!(!result.carry);
+
+        let result = arithmetic_shift_right(&OpcodeSize::Dword, 0x40000002, 2);
+        assert_eq!(result.value, 0x10000000);
+        assert!(result.carry);
+
+        let result = arithmetic_shift_right(&OpcodeSize::Byte, 0x80, 20);
+        assert_eq!(result.value, 0xFF);
+        assert!(result.carry);
+
+        let result = arithmetic_shift_right(&OpcodeSize::Word, 0x7FFF, 16);
+        assert_eq!(result.value, 0x0000);
+        assert!(!result.carry);
+    }
+
+    #[test]
+    fn test_rotate_left_per_size() {
+        let result = rotate_left(&OpcodeSize::Byte, 0x81, 1);
+        assert_eq!(result.value, 0x03);
+        assert!(result.carry);
+
+        let result = rotate_left(&OpcodeSize::Word, 0x1234, 4);
+        assert_eq!(result.value, 0x2341);
+        assert!(result.carry);
+
+        let result = rotate_left(&OpcodeSize::Dword, 0x80000000, 32);
+        assert_eq!(result.value, 0x80000000);
+        assert!(!result.carry);
+    }
+
+    #[test]
+    fn test_rotate_right_per_size() {
+        let result = rotate_right(&OpcodeSize::Byte, 0x81, 1);
+        assert_eq!(result.value, 0xC0);
+        assert!(result.carry);
+
+        let result = rotate_right(&OpcodeSize::Word, 0x1234, 4);
+        assert_eq!(result.value, 0x4123);
+        assert!(!result.carry);
+
+        let result = rotate_right(&OpcodeSize::Dword, 0x00000001, 1);
+        assert_eq!(result.value, 0x80000000);
+        assert!(result.carry);
+    }
+}
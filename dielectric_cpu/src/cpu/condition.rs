@@ -0,0 +1,227 @@
+use std::fmt::Display;
+
+use super::status_register::{Flags, StatusRegister};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Condition {
+    Always,
+    Equal,
+    NotEqual,
+    CarrySet,
+    CarryClear,
+    Minus,
+    Plus,
+    OverflowSet,
+    OverflowClear,
+    Higher,
+    LowerOrSame,
+    GreaterOrEqual,
+    Less,
+    Greater,
+    LessOrEqual,
+    ParityEven,
+    ParityOdd,
+}
+
+impl From<Condition> for u32 {
+    fn from(value: Condition) -> Self {
+        match value {
+            Condition::Always => 0x00,
+            Condition::Equal => 0x01,
+            Condition::NotEqual => 0x02,
+            Condition::CarrySet => 0x03,
+            Condition::CarryClear => 0x04,
+            Condition::Minus => 0x05,
+            Condition::Plus => 0x06,
+            Condition::OverflowSet => 0x07,
+            Condition::OverflowClear => 0x08,
+            Condition::Higher => 0x09,
+            Condition::LowerOrSame => 0x0A,
+            Condition::GreaterOrEqual => 0x0B,
+            Condition::Less => 0x0C,
+            Condition::Greater => 0x0D,
+            Condition::LessOrEqual => 0x0E,
+            Condition::ParityEven => 0x0F,
+            Condition::ParityOdd => 0x10,
+        }
+    }
+}
+
+impl TryFrom<u32> for Condition {
+    type Error = u32;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            0x00 => Ok(Condition::Always),
+            0x01 => Ok(Condition::Equal),
+            0x02 => Ok(Condition::NotEqual),
+            0x03 => Ok(Condition::CarrySet),
+            0x04 => Ok(Condition::CarryClear),
+            0x05 => Ok(Condition::Minus),
+            0x06 => Ok(Condition::Plus),
+            0x07 => Ok(Condition::OverflowSet),
+            0x08 => Ok(Condition::OverflowClear),
+            0x09 => Ok(Condition::Higher),
+            0x0A => Ok(Condition::LowerOrSame),
+            0x0B => Ok(Condition::GreaterOrEqual),
+            0x0C => Ok(Condition::Less),
+            0x0D => Ok(Condition::Greater),
+            0x0E => Ok(Condition::LessOrEqual),
+            0x0F => Ok(Condition::ParityEven),
+            0x10 => Ok(Condition::ParityOdd),
+            _ => Err(value),
+        }
+    }
+}
+
+//NOTE: These are the suffixes used by the branch mnemonics i.e. BEQ, BNE, BRA...
+impl Display for Condition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Condition::Always => write!(f, "RA"),
+            Condition::Equal => write!(f, "EQ"),
+            Condition::NotEqual => write!(f, "NE"),
+            Condition::CarrySet => write!(f, "CS"),
+            Condition::CarryClear => write!(f, "CC"),
+            Condition::Minus => write!(f, "MI"),
+            Condition::Plus => write!(f, "PL"),
+            Condition::OverflowSet => write!(f, "VS"),
+            Condition::OverflowClear => write!(f, "VC"),
+            Condition::Higher => write!(f, "HI"),
+            Condition::LowerOrSame => write!(f, "LS"),
+            Condition::GreaterOrEqual => write!(f, "GE"),
+            Condition::Less => write!(f, "LT"),
+            Condition::Greater => write!(f, "GT"),
+            Condition::LessOrEqual => write!(f, "LE"),
+            Condition::ParityEven => write!(f, "PE"),
+            Condition::ParityOdd => write!(f, "PO"),
+        }
+    }
+}
+
+impl Condition {
+    pub(crate) fn evaluate(&self, status_register: &StatusRegister) -> bool {
+        let zero = status_register.is_set(Flags::Zero);
+        let negative = status_register.is_set(Flags::Negative);
+        let carry = status_register.is_set(Flags::Carry);
+        let overflow = status_register.is_set(Flags::Overflow);
+        let parity = status_register.is_set(Flags::Parity);
+
+        match self {
+            Condition::Always => true,
+            Condition::Equal => zero,
+            Condition::NotEqual => !zero,
+            Condition::CarrySet => carry,
+            Condition::CarryClear => !carry,
+            Condition::Minus => negative,
+            Condition::Plus => !negative,
+            Condition::OverflowSet => overflow,
+            Condition::OverflowClear => !overflow,
+            //NOTE: Unsigned comparisons after a CMP
+            Condition::Higher => !carry && !zero,
+            Condition::LowerOrSame => carry || zero,
+            //NOTE: Signed comparisons after a CMP
+            Condition::GreaterOrEqual => negative == overflow,
+            Condition::Less => negative != overflow,
+            Condition::Greater => !zero && negative == overflow,
+            Condition::LessOrEqual => zero || negative != overflow,
+            Condition::ParityEven => parity,
+            Condition::ParityOdd => !parity,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn status_with(flags: &[Flags]) -> StatusRegister {
+        let mut status_register = StatusRegister::new();
+        for flag in flags {
+            status_register.raise(*flag);
+        }
+        status_register
+    }
+
+    #[test]
+    fn test_condition_encoding_roundtrip() {
+        for pattern in 0x00..=0x10 {
+            let condition = Condition::try_from(pattern).unwrap();
+            let encoded: u32 = condition.into();
+            assert_eq!(encoded, pattern);
+        }
+
+        assert_eq!(Condition::try_from(0x11), Err(0x11));
+        assert_eq!(Condition::try_from(0x1F), Err(0x1F));
+    }
+
+    #[test]
+    fn test_simple_conditions() {
+        let cases = [
+            (Flags::Zero, Condition::Equal, Condition::NotEqual),
+            (Flags::Carry, Condition::CarrySet, Condition::CarryClear),
+            (Flags::Negative, Condition::Minus, Condition::Plus),
+            (
+                Flags::Overflow,
+                Condition::OverflowSet,
+                Condition::OverflowClear,
+            ),
+            (Flags::Parity, Condition::ParityEven, Condition::ParityOdd),
+        ];
+
+        for (flag, when_set, when_clear) in cases {
+            let set = status_with(&[flag]);
+            let clear = status_with(&[]);
+
+            assert!(when_set.evaluate(&set));
+            assert!(!when_set.evaluate(&clear));
+            assert!(when_clear.evaluate(&clear));
+            assert!(!when_clear.evaluate(&set));
+        }
+
+        assert!(Condition::Always.evaluate(&status_with(&[])));
+    }
+
+    #[test]
+    fn test_unsigned_conditions() {
+        let higher = status_with(&[]);
+        let same = status_with(&[Flags::Zero]);
+        let lower = status_with(&[Flags::Carry]);
+
+        assert!(Condition::Higher.evaluate(&higher));
+        assert!(!Condition::Higher.evaluate(&same));
+        assert!(!Condition::Higher.evaluate(&lower));
+
+        assert!(!Condition::LowerOrSame.evaluate(&higher));
+        assert!(Condition::LowerOrSame.evaluate(&same));
+        assert!(Condition::LowerOrSame.evaluate(&lower));
+    }
+
+    #[test]
+    fn test_signed_conditions() {
+        let greater = status_with(&[]);
+        let greater_overflowed = status_with(&[Flags::Negative, Flags::Overflow]);
+        let equal = status_with(&[Flags::Zero]);
+        let less = status_with(&[Flags::Negative]);
+        let less_overflowed = status_with(&[Flags::Overflow]);
+
+        for status in [&greater, &greater_overflowed] {
+            assert!(Condition::Greater.evaluate(status));
+            assert!(Condition::GreaterOrEqual.evaluate(status));
+            assert!(!Condition::Less.evaluate(status));
+            assert!(!Condition::LessOrEqual.evaluate(status));
+        }
+
+        assert!(!Condition::Greater.evaluate(&equal));
+        assert!(Condition::GreaterOrEqual.evaluate(&equal));
+        assert!(!Condition::Less.evaluate(&equal));
+        assert!(Condition::LessOrEqual.evaluate(&equal));
+
+        for status in [&less, &less_overflowed] {
+            assert!(!Condition::Greater.evaluate(status));
+            assert!(!Condition::GreaterOrEqual.evaluate(status));
+            assert!(Condition::Less.evaluate(status));
+            assert!(Condition::LessOrEqual.evaluate(status));
+        }
+    }
+}
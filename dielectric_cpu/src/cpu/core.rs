@@ -1,15 +1,62 @@
+use std::{collections::BTreeSet, fmt::Display, ops::Range, path::Path};
+
 use super::decoder::BitPattern;
-use super::opcode::Opcode;
-use crate::cpu::opcode::Execute;
-use crate::cpu::status_register::StatusRegister;
-use crate::Memory;
+use super::exception::Exception;
+use super::interrupt::{InterruptController, IRQ_LINES};
+use super::opcode::{Opcode, EXCEPTION_CYCLES};
+use crate::bios::{Bios, BiosError, RESET_VECTOR};
+use crate::bus::{Bus, BusError, Device, MapError, SystemBus, WatchKind, WatchpointHit};
+use crate::cpu::opcode::{push_dword, Execute};
+use crate::cpu::register::Register;
+use crate::cpu::status_register::{Flags, StatusRegister};
+use crate::machine::{MachineConfig, MachineError};
+use crate::snapshot::{Snapshot, SnapshotError};
+use crate::trace::{TraceRecord, Tracer};
 use crate::RegisterFile;
 
+//NOTE: What the cpu did during a single step, mostly useful for tooling and tests
+#[derive(Debug, PartialEq, Eq)]
+pub struct Step {
+    pub address: u32,
+    pub opcode: Opcode,
+    pub exception: Option<Exception>,
+    //NOTE: The IRQ line that was dispatched right before the instruction, the instruction is the start of its handler
+    pub interrupt: Option<u8>,
+    //NOTE: Cycles spent on the step including the interrupt dispatch and exception entry
+    pub cycles: u32,
+    pub event: Option<DebugEvent>,
+}
+
+//NOTE: Why a step stopped run_until and run_for_cycles. Watchpoints trigger on the access inside the instruction,
+//      breakpoints once the pc reaches them so the instruction at the breakpoint hasn't been executed yet.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum DebugEvent {
+    Breakpoint(u32),
+    Watchpoint(WatchpointHit),
+}
+
+impl Display for DebugEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DebugEvent::Breakpoint(address) => write!(f, "breakpoint at {:#010x}", address),
+            DebugEvent::Watchpoint(hit) => write!(f, "{}", hit),
+        }
+    }
+}
+
 pub struct Cpu {
     register_file: RegisterFile,
     status_register: StatusRegister,
     pc: u32,
-    memory: Memory,
+    memory: SystemBus,
+    interrupt_controller: InterruptController,
+    cycles: u64,
+    halted: bool,
+    breakpoints: BTreeSet<u32>,
+    event: Option<DebugEvent>,
+    tracer: Option<Box<dyn Tracer>>,
+    //NOTE: Where the start address is read from on reset
+    reset_vector: u32,
 }
 
 //NOTE(Kay): Make clippy happy!
@@ -21,11 +68,56 @@ impl Default for Cpu {
 
 impl Cpu {
     pub fn new() -> Self {
+        Self::with_bus(SystemBus::new(), RESET_VECTOR)
+    }
+
+    fn with_bus(memory: SystemBus, reset_vector: u32) -> Self {
         Self {
             register_file: RegisterFile::new(),
-            memory: Memory::new(),
+            memory,
             status_register: StatusRegister::new(),
             pc: 0,
+            interrupt_controller: InterruptController::new(),
+            cycles: 0,
+            halted: false,
+            breakpoints: BTreeSet::new(),
+            event: None,
+            tracer: None,
+            reset_vector,
+        }
+    }
+
+    //NOTE: Builds the machine the config describes and resets the cpu so it starts at the address stored in the
+    //      reset vector. A reset vector nothing answers at halts the cpu right away.
+    pub fn with_config(config: MachineConfig) -> Result<Self, MachineError> {
+        let (memory, reset_vector) = config.into_bus()?;
+        let mut cpu = Self::with_bus(memory, reset_vector);
+        cpu.reset();
+        Ok(cpu)
+    }
+
+    //NOTE: Maps the image read only at BIOS_START and resets the cpu so it starts at the reset vector of the image
+    pub fn with_bios(bios: Bios) -> Self {
+        Self::with_config(MachineConfig::new().bios(bios))
+            .expect("the bios is the only window of the machine")
+    }
+
+    pub fn with_bios_file<P: AsRef<Path>>(path: P) -> Result<Self, BiosError> {
+        Ok(Self::with_bios(Bios::load(path)?))
+    }
+
+    //NOTE: Clears the registers, flags and pending interrupts and continues at the address stored in the reset
+    //      vector. Memory and devices keep their contents. The stack pointer starts at 0 so the bios has to set it
+    //      up before anything is pushed.
+    pub fn reset(&mut self) {
+        self.register_file = RegisterFile::new();
+        self.status_register = StatusRegister::new();
+        self.interrupt_controller = InterruptController::new();
+        self.halted = false;
+
+        match self.memory.read_dword(self.reset_vector) {
+            Ok(address) => self.pc = address,
+            Err(_) => self.halted = true,
         }
     }
 
@@ -34,34 +126,378 @@ impl Cpu {
         raw_opcode.into()
     }
 
-    pub fn cycle(&mut self) {
-        //TODO: Check if we need to handle interrupts!
-        //TODO: We need to count the amount of elapsed cycles after every step!
-        let value = self.memory.read_dword(self.pc);
-        let opcode = self.decoder(value);
+    pub fn pc(&self) -> u32 {
+        self.pc
+    }
+
+    pub fn set_pc(&mut self, pc: u32) {
+        self.pc = pc;
+    }
+
+    pub fn register(&self, register: Register) -> u32 {
+        self.register_file.read_value(&register)
+    }
+
+    pub fn set_register(&mut self, register: Register, value: u32) {
+        self.register_file.write_value(&register, value);
+    }
+
+    //NOTE: Loads go through the bus like any other write, loading into rom or outside of memory faults and stops at
+    //      the faulting byte
+    pub fn load_bytes(&mut self, address: u32, bytes: &[u8]) -> Result<(), BusError> {
+        for (offset, byte) in bytes.iter().enumerate() {
+            let address = address
+                .checked_add(offset as u32)
+                .ok_or(BusError::OutOfBounds(address))?;
+            self.memory.write_byte(address, *byte)?;
+        }
+        Ok(())
+    }
+
+    //NOTE: Loads 32 bit instruction words like the assembler emits them, stored in big endian
+    pub fn load_words(&mut self, address: u32, words: &[u32]) -> Result<(), BusError> {
+        let bytes: Vec<u8> = words.iter().flat_map(|word| word.to_be_bytes()).collect();
+        self.load_bytes(address, &bytes)
+    }
+
+    pub fn status(&self) -> &StatusRegister {
+        &self.status_register
+    }
+
+    //NOTE: Overwrites every flag at once, meant for debuggers and tools
+    pub fn set_status(&mut self, status_bits: u16) {
+        self.status_register.restore(status_bits);
+    }
+
+    pub fn interrupt_controller(&mut self) -> &mut InterruptController {
+        &mut self.interrupt_controller
+    }
+
+    //NOTE: Lines past the last IRQ line don't exist and are ignored
+    pub fn raise_interrupt(&mut self, line: u8) {
+        if line < IRQ_LINES {
+            self.interrupt_controller.raise(line);
+        }
+    }
+
+    pub fn map_device(
+        &mut self,
+        start: u32,
+        end: u32,
+        device: Box<dyn Device>,
+    ) -> Result<(), MapError> {
+        self.memory.map_device(start, end, device)
+    }
+
+    pub fn bus(&mut self) -> &mut SystemBus {
+        &mut self.memory
+    }
+
+    pub fn cycles(&self) -> u64 {
+        self.cycles
+    }
+
+    //NOTE: Returns false if there already was a breakpoint at the address
+    pub fn add_breakpoint(&mut self, address: u32) -> bool {
+        self.breakpoints.insert(address)
+    }
+
+    pub fn remove_breakpoint(&mut self, address: u32) -> bool {
+        self.breakpoints.remove(&address)
+    }
+
+    pub fn breakpoints(&self) -> impl Iterator<Item = u32> + '_ {
+        self.breakpoints.iter().copied()
+    }
+
+    pub fn add_watchpoint(&mut self, range: Range<u32>, kind: WatchKind) {
+        self.memory.add_watchpoint(range, kind);
+    }
+
+    pub fn remove_watchpoints(&mut self, start: u32) -> bool {
+        self.memory.remove_watchpoints(start)
+    }
+
+    //NOTE: The tracer gets a record of every instruction executed from now on, logging the memory accesses slows
+    //      the bus down a bit so it is only done while a tracer is installed
+    pub fn set_tracer(&mut self, tracer: Box<dyn Tracer>) {
+        self.memory.set_access_logging(true);
+        self.register_file.take_written();
+        self.tracer = Some(tracer);
+    }
+
+    //NOTE: Removes the tracer, call finish on it to flush the trace
+    pub fn take_tracer(&mut self) -> Option<Box<dyn Tracer>> {
+        self.memory.set_access_logging(false);
+        self.tracer.take()
+    }
+
+    //NOTE: Captures registers, flags, pc, cycles, pending interrupts, ram and the state of the mapped devices so a long
+    //      run can be resumed later
+    pub fn snapshot(&self) -> Snapshot {
+        let ram = self.memory.ram();
+        Snapshot {
+            pc: self.pc,
+            status: self.status_register.status_bits(),
+            cycles: self.cycles,
+            halted: self.halted,
+            pending_interrupts: self.interrupt_controller.pending_lines(),
+            registers: self.register_file.registers,
+            memory_size: ram.size(),
+            pages: ram
+                .used_pages()
+                .map(|(address, bytes)| (address, bytes.to_vec()))
+                .collect(),
+            devices: self.memory.device_states(),
+        }
+    }
+
+    //NOTE: Ram not covered by a page of the snapshot is cleared. The snapshot is checked before anything changes so
+    //      a failed restore leaves the cpu untouched.
+    pub fn restore(&mut self, snapshot: &Snapshot) -> Result<(), SnapshotError> {
+        let ram = self.memory.ram();
+        if snapshot.memory_size != ram.size() {
+            return Err(SnapshotError::MemorySize(snapshot.memory_size));
+        }
+        if let Some((address, _)) = snapshot.pages.iter().find(|(address, bytes)| {
+            *address as u64 + bytes.len() as u64 > snapshot.memory_size as u64
+        }) {
+            return Err(SnapshotError::InvalidPage(*address));
+        }
+        //NOTE: Last check since it already puts the device states back, it doesn't touch anything if it fails
+        self.memory
+            .restore_device_states(&snapshot.devices)
+            .map_err(SnapshotError::DeviceState)?;
+
+        let ram = self.memory.ram_mut();
+        ram.clear();
+        for (address, bytes) in &snapshot.pages {
+            ram.write_bytes(*address, bytes)
+                .map_err(|_| SnapshotError::InvalidPage(*address))?;
+        }
+
+        self.register_file = RegisterFile::new();
+        self.register_file.registers = snapshot.registers;
+        self.status_register.restore(snapshot.status);
+        self.interrupt_controller =
+            InterruptController::with_pending_lines(snapshot.pending_interrupts);
+        self.pc = snapshot.pc;
+        self.cycles = snapshot.cycles;
+        self.halted = snapshot.halted;
+        self.event = None;
+        Ok(())
+    }
+
+    //NOTE: The event of the last step, tells why run_until or run_for_cycles stopped early
+    pub fn debug_event(&self) -> Option<DebugEvent> {
+        self.event
+    }
+
+    //NOTE: The cpu halts when it can't enter an exception handler, i.e. the stack or the jump table fault on the bus
+    pub fn is_halted(&self) -> bool {
+        self.halted
+    }
+
+    //NOTE: A halted cpu doesn't fetch anything anymore, the step only reports the pc it stopped at.
+    //      If the fetch itself faults nothing is decoded and the opcode is reported as Unknown.
+    pub fn step(&mut self) -> Step {
+        if self.halted {
+            return Step {
+                address: self.pc,
+                opcode: Opcode::Unknown,
+                exception: None,
+                interrupt: None,
+                cycles: 0,
+                event: None,
+            };
+        }
+
+        let cycles_before = self.cycles;
+        //NOTE: Drops hits and accesses made between steps, i.e. a debugger reading memory or setting registers
+        self.memory.take_watchpoint_hit();
+        let tracing = self.tracer.is_some();
+        if tracing {
+            self.memory.take_accesses();
+            self.register_file.take_written();
+        }
+        let interrupt = self.dispatch_interrupt();
+
+        let address = self.pc;
+        let mut word = None;
+        let mut extension_word = None;
+        let (opcode, exception) = match self.memory.fetch_dword(address) {
+            Ok(value) => {
+                let opcode = self.decoder(value);
+                word = Some(value);
+                //NOTE: Fetched before the instruction runs so the trace shows what was executed
+                if tracing && opcode.length() == 8 {
+                    extension_word = self.memory.fetch_dword(address.wrapping_add(4)).ok();
+                }
+                let exception = self.execution_stage(&opcode);
+                (opcode, exception)
+            }
+            Err(error) => {
+                let exception = Exception::BusError(error);
+                self.enter_exception(exception);
+                (Opcode::Unknown, Some(exception))
+            }
+        };
+        let cycles = (self.cycles - cycles_before) as u32;
+        //NOTE: Lines raised by devices are dispatched before the next instruction
+        let lines = self.memory.tick(cycles);
+        self.interrupt_controller.raise_lines(lines);
+
+        if let Some(tracer) = &mut self.tracer {
+            tracer.trace(&TraceRecord {
+                address,
+                word,
+                extension_word,
+                registers: self.register_file.take_written(),
+                accesses: self.memory.take_accesses(),
+                status: self.status_register.status_bits(),
+            });
+        }
+
+        self.event = match self.memory.take_watchpoint_hit() {
+            Some(hit) => Some(DebugEvent::Watchpoint(hit)),
+            None if self.breakpoints.contains(&self.pc) => Some(DebugEvent::Breakpoint(self.pc)),
+            None => None,
+        };
+
+        Step {
+            address,
+            opcode,
+            exception,
+            interrupt,
+            cycles,
+            event: self.event,
+        }
+    }
+
+    //NOTE: Steps until the halt condition holds after an instruction, the cpu halted or a breakpoint or watchpoint
+    //      triggered, returns the amount of executed instructions
+    pub fn run_until<F>(&mut self, mut halt: F) -> u64
+    where
+        F: FnMut(&Cpu, &Step) -> bool,
+    {
+        let mut executed = 0;
+        loop {
+            let step = self.step();
+            executed += 1;
+
+            if halt(self, &step) || self.halted || step.event.is_some() {
+                return executed;
+            }
+        }
+    }
 
-        self.execution_stage(opcode);
+    //NOTE: Runs whole instructions until at least the given amount of cycles elapsed, returns the elapsed cycles
+    //      which can overshoot by the length of the last instruction. Stops early once the cpu halted or a breakpoint
+    //      or watchpoint triggered.
+    pub fn run_for_cycles(&mut self, cycles: u64) -> u64 {
+        let start = self.cycles;
+        while self.cycles - start < cycles && !self.halted {
+            if self.step().event.is_some() {
+                break;
+            }
+        }
+        self.cycles - start
     }
 
     //TODO(Kay): Refactor to the Opcode enum!
-    pub fn execution_stage(&mut self, opcode: Opcode) {
-        opcode.execute(
+    pub fn execution_stage(&mut self, opcode: &Opcode) -> Option<Exception> {
+        let address = self.pc;
+        let next_pc = address.wrapping_add(opcode.length());
+        let branch_taken = match opcode {
+            Opcode::Branch(data) => data.condition.evaluate(&self.status_register),
+            _ => false,
+        };
+        self.cycles += opcode.cycles(branch_taken) as u64;
+
+        let result = opcode.execute(
             &mut self.pc,
             &mut self.register_file,
             &mut self.status_register,
             &mut self.memory,
         );
+
+        match result {
+            Ok(()) => {
+                if !opcode.is_control_flow() {
+                    self.pc = next_pc;
+                }
+                None
+            }
+            Err(exception) => {
+                self.pc = address;
+                self.enter_exception(exception);
+                Some(exception)
+            }
+        }
+    }
+
+    //NOTE: Interrupts are only checked between instructions and only while the InterruptEnable flag is set
+    fn dispatch_interrupt(&mut self) -> Option<u8> {
+        if !self.status_register.is_set(Flags::InterruptEnable) {
+            return None;
+        }
+
+        let line = self.interrupt_controller.acknowledge()?;
+        self.enter_exception(Exception::Interrupt(line));
+        self.status_register.raise(Flags::Interrupt);
+
+        Some(line)
+    }
+
+    //NOTE: Saves the pc of the faulting instruction and the status register on the stack and continues at the
+    //      handler stored inside the jump table. The status register is pushed last so it is popped first.
+    //      Handlers start with interrupts disabled, RTI restores the saved status register.
+    //      A bus error while entering the handler is a double fault and halts the cpu.
+    fn enter_exception(&mut self, exception: Exception) {
+        self.cycles += EXCEPTION_CYCLES as u64;
+
+        let entered = self.push_exception_frame(exception.vector_address());
+        match entered {
+            Ok(handler) => {
+                self.status_register.clear(Flags::InterruptEnable);
+                self.pc = handler;
+            }
+            Err(_) => self.halted = true,
+        }
+    }
+
+    fn push_exception_frame(&mut self, vector_address: u32) -> Result<u32, BusError> {
+        let handler = self.memory.read_dword(vector_address)?;
+
+        push_dword(self.pc, &mut self.register_file, &mut self.memory)?;
+        push_dword(
+            self.status_register.status_bits() as u32,
+            &mut self.register_file,
+            &mut self.memory,
+        )?;
+
+        Ok(handler)
     }
 }
 
 #[cfg(test)]
 mod test {
     use crate::cpu::{
-        addressing_modes::AddressingMode, opcode::MoveOpcode, opcode_size::OpcodeSize,
+        addressing_modes::AddressingMode,
+        condition::Condition,
+        exception::VECTOR_TABLE_START,
+        opcode::{
+            ArithmeticOpcode, ArithmeticOperation, BranchOpcode, InterruptEnableOpcode, JumpOpcode,
+            JumpOperation, LeaOpcode, LogicOpcode, LogicOperation, MoveOpcode, PopOpcode,
+            PushOpcode, RtiOpcode, RtsOpcode,
+        },
+        opcode_size::OpcodeSize,
         register::Register,
     };
 
     use super::*;
+    use crate::bus::{AccessMode, BIOS_END};
+    use crate::MEMORY_SIZE;
 
     static ALL_REGISTERS: [Register; 32] = [
         Register::D0,
@@ -156,6 +592,10 @@ mod test {
         })
     }
 
+    fn flag_is_set(cpu: &Cpu, flag: Flags) -> bool {
+        cpu.status().is_set(flag)
+    }
+
     fn generate_opcode(
         opcode: u32,
         mode: AddressingMode,
@@ -217,18 +657,14 @@ mod test {
                     OpcodeSize::Dword,
                 );
                 let mut cpu = Cpu::new();
-                let src_index: u32 = src_reg.into();
-                let dest_index: u32 = data_reg.into();
-                cpu.register_file.registers[src_index as usize] = 0x7000BA5;
+                cpu.set_register(src_reg, 0x7000BA5);
                 let opcode = cpu.decoder(opcode);
 
-                cpu.memory.bytes[0x7000BA5] = 0xAA;
-                cpu.memory.bytes[0x7000BA6] = 0xBB;
-                cpu.memory.bytes[0x7000BA7] = 0xCC;
-                cpu.memory.bytes[0x7000BA8] = 0xDD;
-                cpu.execution_stage(opcode);
+                cpu.load_bytes(0x7000BA5, &[0xAA, 0xBB, 0xCC, 0xDD])
+                    .unwrap();
+                cpu.execution_stage(&opcode);
 
-                assert_eq!(cpu.register_file.registers[dest_index as usize], 0xAABBCCDD);
+                assert_eq!(cpu.register(data_reg), 0xAABBCCDD);
             }
         }
     }
@@ -246,18 +682,14 @@ mod test {
                     OpcodeSize::Word,
                 );
                 let mut cpu = Cpu::new();
-                let src_index: u32 = src_reg.into();
-                let dest_index: u32 = data_reg.into();
-                cpu.register_file.registers[src_index as usize] = 0x7000BA5;
+                cpu.set_register(src_reg, 0x7000BA5);
                 let opcode = cpu.decoder(opcode);
 
-                cpu.memory.bytes[0x7000BA5] = 0xAA;
-                cpu.memory.bytes[0x7000BA6] = 0xBB;
-                cpu.memory.bytes[0x7000BA7] = 0xCC;
-                cpu.memory.bytes[0x7000BA8] = 0xDD;
-                cpu.execution_stage(opcode);
+                cpu.load_bytes(0x7000BA5, &[0xAA, 0xBB, 0xCC, 0xDD])
+                    .unwrap();
+                cpu.execution_stage(&opcode);
 
-                assert_eq!(cpu.register_file.registers[dest_index as usize], 0x0000AABB);
+                assert_eq!(cpu.register(data_reg), 0x0000AABB);
             }
         }
     }
@@ -276,18 +708,14 @@ mod test {
                 );
 
                 let mut cpu = Cpu::new();
-                let src_index: u32 = src_reg.into();
-                let dest_index: u32 = data_reg.into();
-                cpu.register_file.registers[src_index as usize] = 0x7000BA5;
+                cpu.set_register(src_reg, 0x7000BA5);
                 let opcode = cpu.decoder(opcode);
 
-                cpu.memory.bytes[0x7000BA5] = 0xAA;
-                cpu.memory.bytes[0x7000BA6] = 0xBB;
-                cpu.memory.bytes[0x7000BA7] = 0xCC;
-                cpu.memory.bytes[0x7000BA8] = 0xDD;
-                cpu.execution_stage(opcode);
+                cpu.load_bytes(0x7000BA5, &[0xAA, 0xBB, 0xCC, 0xDD])
+                    .unwrap();
+                cpu.execution_stage(&opcode);
 
-                assert_eq!(cpu.register_file.registers[dest_index as usize], 0x000000AA);
+                assert_eq!(cpu.register(data_reg), 0x000000AA);
             }
         }
     }
@@ -306,16 +734,11 @@ mod test {
                 );
 
                 let mut cpu = Cpu::new();
-                let src_index: u32 = src_reg.into();
-                let dest_index: u32 = dest_reg.into();
-                cpu.register_file.registers[src_index as usize] = 0xAABBCCDD;
+                cpu.set_register(src_reg, 0xAABBCCDD);
                 let opcode = cpu.decoder(opcode);
-                cpu.execution_stage(opcode);
+                cpu.execution_stage(&opcode);
 
-                assert_eq!(
-                    cpu.register_file.registers[dest_index as usize],
-                    cpu.register_file.registers[src_index as usize]
-                );
+                assert_eq!(cpu.register(dest_reg), cpu.register(src_reg));
             }
         }
     }
@@ -334,13 +757,11 @@ mod test {
                 );
 
                 let mut cpu = Cpu::new();
-                let src_index: u32 = src_reg.into();
-                let dest_index: u32 = dest_reg.into();
-                cpu.register_file.registers[src_index as usize] = 0xAABBCCDD;
+                cpu.set_register(src_reg, 0xAABBCCDD);
                 let opcode = cpu.decoder(opcode);
-                cpu.execution_stage(opcode);
+                cpu.execution_stage(&opcode);
 
-                assert_eq!(cpu.register_file.registers[dest_index as usize], 0x0000CCDD);
+                assert_eq!(cpu.register(dest_reg), 0x0000CCDD);
             }
         }
     }
@@ -359,13 +780,11 @@ mod test {
                 );
 
                 let mut cpu = Cpu::new();
-                let src_index: u32 = src_reg.into();
-                let dest_index: u32 = dest_reg.into();
-                cpu.register_file.registers[src_index as usize] = 0xAABBCCDD;
+                cpu.set_register(src_reg, 0xAABBCCDD);
                 let opcode = cpu.decoder(opcode);
-                cpu.execution_stage(opcode);
+                cpu.execution_stage(&opcode);
 
-                assert_eq!(cpu.register_file.registers[dest_index as usize], 0x000000DD);
+                assert_eq!(cpu.register(dest_reg), 0x000000DD);
             }
         }
     }
@@ -384,15 +803,15 @@ mod test {
                 );
 
                 let mut cpu = Cpu::new();
-                let src_index: u32 = src_reg.into();
-                let dest_index: u32 = dest_reg.into();
-                cpu.register_file.registers[src_index as usize] = 0xDEADBEEF;
-                cpu.register_file.registers[dest_index as usize] = 0x05403502;
+                cpu.set_register(src_reg, 0xDEADBEEF);
+                cpu.set_register(dest_reg, 0x05403502);
                 let opcode = cpu.decoder(opcode);
-                cpu.execution_stage(opcode);
+                cpu.execution_stage(&opcode);
 
                 assert_eq!(
-                    cpu.memory.memory_bus_read(&OpcodeSize::Byte, 0x05403502),
+                    cpu.bus()
+                        .memory_bus_read(&OpcodeSize::Byte, 0x05403502)
+                        .unwrap(),
                     0x000000EF
                 );
             }
@@ -413,15 +832,15 @@ mod test {
                 );
 
                 let mut cpu = Cpu::new();
-                let src_index: u32 = src_reg.into();
-                let dest_index: u32 = dest_reg.into();
-                cpu.register_file.registers[src_index as usize] = 0xDEADBEEF;
-                cpu.register_file.registers[dest_index as usize] = 0x05403502;
+                cpu.set_register(src_reg, 0xDEADBEEF);
+                cpu.set_register(dest_reg, 0x05403502);
                 let opcode = cpu.decoder(opcode);
-                cpu.execution_stage(opcode);
+                cpu.execution_stage(&opcode);
 
                 assert_eq!(
-                    cpu.memory.memory_bus_read(&OpcodeSize::Word, 0x05403502),
+                    cpu.bus()
+                        .memory_bus_read(&OpcodeSize::Word, 0x05403502)
+                        .unwrap(),
                     0x0000BEEF
                 );
             }
@@ -442,15 +861,15 @@ mod test {
                 );
 
                 let mut cpu = Cpu::new();
-                let src_index: u32 = src_reg.into();
-                let dest_index: u32 = dest_reg.into();
-                cpu.register_file.registers[src_index as usize] = 0xDEADBEEF;
-                cpu.register_file.registers[dest_index as usize] = 0x05403502;
+                cpu.set_register(src_reg, 0xDEADBEEF);
+                cpu.set_register(dest_reg, 0x05403502);
                 let opcode = cpu.decoder(opcode);
-                cpu.execution_stage(opcode);
+                cpu.execution_stage(&opcode);
 
                 assert_eq!(
-                    cpu.memory.memory_bus_read(&OpcodeSize::Dword, 0x05403502),
+                    cpu.bus()
+                        .memory_bus_read(&OpcodeSize::Dword, 0x05403502)
+                        .unwrap(),
                     0xDEADBEEF
                 );
             }
@@ -470,16 +889,14 @@ mod test {
                     OpcodeSize::Byte,
                 );
                 let mut cpu = Cpu::new();
-                let dest_index: u32 = dest_reg.into();
-                let src_index: u32 = src_reg.into();
-                cpu.memory.write_byte(0x05403502, 0xEF);
-                cpu.register_file.registers[src_index as usize] = 0x05403502;
+                cpu.load_bytes(0x05403502, &[0xEF]).unwrap();
+                cpu.set_register(src_reg, 0x05403502);
 
                 let opcode = cpu.decoder(opcode);
-                cpu.execution_stage(opcode);
+                cpu.execution_stage(&opcode);
 
-                assert_eq!(cpu.register_file.registers[dest_index as usize], 0x000000EF);
-                assert_eq!(cpu.register_file.registers[src_index as usize], 0x05403503)
+                assert_eq!(cpu.register(dest_reg), 0x000000EF);
+                assert_eq!(cpu.register(src_reg), 0x05403503)
             }
         }
     }
@@ -497,16 +914,14 @@ mod test {
                     OpcodeSize::Word,
                 );
                 let mut cpu = Cpu::new();
-                let dest_index: u32 = dest_reg.into();
-                let src_index: u32 = src_reg.into();
-                cpu.memory.write_word(0x05403502, 0xBEEF);
-                cpu.register_file.registers[src_index as usize] = 0x05403502;
+                cpu.bus().write_word(0x05403502, 0xBEEF).unwrap();
+                cpu.set_register(src_reg, 0x05403502);
 
                 let opcode = cpu.decoder(opcode);
-                cpu.execution_stage(opcode);
+                cpu.execution_stage(&opcode);
 
-                assert_eq!(cpu.register_file.registers[dest_index as usize], 0x0000BEEF);
-                assert_eq!(cpu.register_file.registers[src_index as usize], 0x05403504)
+                assert_eq!(cpu.register(dest_reg), 0x0000BEEF);
+                assert_eq!(cpu.register(src_reg), 0x05403504)
             }
         }
     }
@@ -524,16 +939,14 @@ mod test {
                     OpcodeSize::Dword,
                 );
                 let mut cpu = Cpu::new();
-                let dest_index: u32 = dest_reg.into();
-                let src_index: u32 = src_reg.into();
 
-                cpu.memory.write_dword(0x05403502, 0xDEADBEEF);
-                cpu.register_file.registers[src_index as usize] = 0x05403502;
+                cpu.load_words(0x05403502, &[0xDEADBEEF]).unwrap();
+                cpu.set_register(src_reg, 0x05403502);
                 let opcode = cpu.decoder(opcode);
-                cpu.execution_stage(opcode);
+                cpu.execution_stage(&opcode);
 
-                assert_eq!(cpu.register_file.registers[dest_index as usize], 0xDEADBEEF);
-                assert_eq!(cpu.register_file.registers[src_index as usize], 0x05403506)
+                assert_eq!(cpu.register(dest_reg), 0xDEADBEEF);
+                assert_eq!(cpu.register(src_reg), 0x05403506)
             }
         }
     }
@@ -573,37 +986,2508 @@ mod test {
                 OpcodeSize::Dword,
             );
             let mut cpu = Cpu::new();
+            cpu.set_pc(0x54034FE);
+            cpu.load_words(0x05403502, &[0xC0EDCAFE]).unwrap();
+            let opcode = cpu.decoder(opcode);
+            cpu.execution_stage(&opcode);
+
+            assert_eq!(cpu.register(dest_reg), 0xC0EDCAFE);
+            assert_eq!(cpu.pc(), 0x05403506);
+        }
+    }
+    #[test]
+    fn test_decode_push() {
+        let modes = [
+            AddressingMode::Atomic,
+            AddressingMode::Immediate,
+            AddressingMode::MemoryDest,
+            AddressingMode::MemoryDestInc,
+            AddressingMode::MemoryDestDec,
+        ];
+
+        for mode in modes {
+            for src_reg in ALL_REGISTERS {
+                let opcode = generate_opcode(
+                    0x03,
+                    mode,
+                    Register::D0,
+                    Some(src_reg),
+                    0,
+                    OpcodeSize::Dword,
+                );
+                let result = get_decoder_result(opcode);
+                let expected = Opcode::Push(PushOpcode {
+                    addressing_mode: mode,
+                    source: src_reg,
+                    size: OpcodeSize::Dword,
+                });
+
+                assert_eq!(result, expected, "Failed {:?} {:?}", result, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_decode_push_unsupported_addressing_modes() {
+        let modes = [
+            AddressingMode::MemorySrc,
+            AddressingMode::MemorySrcInc,
+            AddressingMode::MemorySrcDec,
+        ];
+
+        for mode in modes {
+            let opcode = generate_opcode(
+                0x03,
+                mode,
+                Register::D0,
+                Some(Register::A0),
+                0,
+                OpcodeSize::Dword,
+            );
+            assert_eq!(get_decoder_result(opcode), Opcode::Unknown);
+        }
+    }
+
+    #[test]
+    fn test_push_dword_register_execution() {
+        for src_reg in ALL_REGISTERS {
+            if src_reg == Register::A15 {
+                continue;
+            }
+
+            let opcode = generate_opcode(
+                0x03,
+                AddressingMode::Atomic,
+                Register::D0,
+                Some(src_reg),
+                0,
+                OpcodeSize::Dword,
+            );
+
+            let mut cpu = Cpu::new();
+            cpu.set_register(Register::A15, 0x05403502);
+            cpu.set_register(src_reg, 0xDEADBEEF);
+            let opcode = cpu.decoder(opcode);
+            cpu.execution_stage(&opcode);
+
+            assert_eq!(cpu.register(Register::A15), 0x054034FE);
+            assert_eq!(
+                cpu.bus()
+                    .memory_bus_read(&OpcodeSize::Dword, 0x054034FE)
+                    .unwrap(),
+                0xDEADBEEF
+            );
+        }
+    }
 
-            let dest_index: u32 = dest_reg.into();
-            cpu.pc = 0x54034FE;
-            cpu.memory.write_dword(0x05403502, 0xC0EDCAFE);
+    #[test]
+    fn test_push_word_register_execution() {
+        for src_reg in ALL_REGISTERS {
+            if src_reg == Register::A15 {
+                continue;
+            }
+
+            let opcode = generate_opcode(
+                0x03,
+                AddressingMode::Atomic,
+                Register::D0,
+                Some(src_reg),
+                0,
+                OpcodeSize::Word,
+            );
+
+            let mut cpu = Cpu::new();
+            cpu.set_register(Register::A15, 0x05403502);
+            cpu.set_register(src_reg, 0xDEADBEEF);
             let opcode = cpu.decoder(opcode);
-            cpu.execution_stage(opcode);
+            cpu.execution_stage(&opcode);
+
+            assert_eq!(cpu.register(Register::A15), 0x05403500);
+            assert_eq!(
+                cpu.bus()
+                    .memory_bus_read(&OpcodeSize::Word, 0x05403500)
+                    .unwrap(),
+                0x0000BEEF
+            );
+        }
+    }
+
+    #[test]
+    fn test_push_byte_register_execution() {
+        for src_reg in ALL_REGISTERS {
+            if src_reg == Register::A15 {
+                continue;
+            }
+
+            let opcode = generate_opcode(
+                0x03,
+                AddressingMode::Atomic,
+                Register::D0,
+                Some(src_reg),
+                0,
+                OpcodeSize::Byte,
+            );
 
-            assert_eq!(cpu.register_file.registers[dest_index as usize], 0xC0EDCAFE);
-            assert_eq!(cpu.pc, 0x05403502);
+            let mut cpu = Cpu::new();
+            cpu.set_register(Register::A15, 0x05403502);
+            cpu.set_register(src_reg, 0xDEADBEEF);
+            let opcode = cpu.decoder(opcode);
+            cpu.execution_stage(&opcode);
+
+            assert_eq!(cpu.register(Register::A15), 0x05403501);
+            assert_eq!(
+                cpu.bus()
+                    .memory_bus_read(&OpcodeSize::Byte, 0x05403501)
+                    .unwrap(),
+                0x000000EF
+            );
         }
     }
+
     #[test]
-    fn test_full_cpu_cycle() {
+    fn test_push_stack_pointer_stores_old_value() {
         let opcode = generate_opcode(
-            0x01,
+            0x03,
             AddressingMode::Atomic,
             Register::D0,
-            Some(Register::D1),
+            Some(Register::A15),
             0,
             OpcodeSize::Dword,
         );
+
         let mut cpu = Cpu::new();
+        cpu.set_register(Register::A15, 0x05403502);
+        let opcode = cpu.decoder(opcode);
+        cpu.execution_stage(&opcode);
+
+        assert_eq!(
+            cpu.bus()
+                .memory_bus_read(&OpcodeSize::Dword, 0x054034FE)
+                .unwrap(),
+            0x05403502
+        );
+    }
 
-        cpu.memory.write_dword(0x00000000, opcode);
-        cpu.register_file.write_value(&Register::D1, 0xDEADBEEF);
+    #[test]
+    fn test_push_with_stack_pointer_at_zero() {
+        let opcode = generate_opcode(
+            0x03,
+            AddressingMode::Atomic,
+            Register::D0,
+            Some(Register::D0),
+            0,
+            OpcodeSize::Dword,
+        );
 
-        cpu.cycle();
+        let mut cpu = Cpu::new();
+        cpu.set_register(Register::A15, 0x00000000);
+        let opcode = cpu.decoder(opcode);
 
+        //NOTE: The stack wraps to the top of the address space where nothing answers
         assert_eq!(
-            cpu.register_file.registers[0],
-            cpu.register_file.registers[1]
+            cpu.execution_stage(&opcode),
+            Some(Exception::BusError(BusError::OutOfBounds(0xFFFFFFFC)))
         );
+        assert_eq!(cpu.register(Register::A15), 0x00000000);
+    }
+
+    #[test]
+    fn test_push_immediate_execution() {
+        let sizes = [
+            (OpcodeSize::Byte, 0x05403501, 0x000000EF),
+            (OpcodeSize::Word, 0x05403500, 0x0000BEEF),
+            (OpcodeSize::Dword, 0x054034FE, 0xDEADBEEF),
+        ];
+
+        for (size, expected_sp, expected_value) in sizes {
+            let opcode =
+                generate_opcode(0x03, AddressingMode::Immediate, Register::D0, None, 0, size);
+
+            let mut cpu = Cpu::new();
+            cpu.set_pc(0x00001000);
+            cpu.load_words(0x00001004, &[0xDEADBEEF]).unwrap();
+            cpu.set_register(Register::A15, 0x05403502);
+            let opcode = cpu.decoder(opcode);
+            cpu.execution_stage(&opcode);
+
+            assert_eq!(cpu.register(Register::A15), expected_sp);
+            assert_eq!(
+                cpu.bus().memory_bus_read(&size, expected_sp).unwrap(),
+                expected_value
+            );
+        }
+    }
+
+    #[test]
+    fn test_push_from_memory_execution() {
+        for src_reg in ADDRESS_REGISTERS {
+            if src_reg == Register::A15 {
+                continue;
+            }
+
+            let opcode = generate_opcode(
+                0x03,
+                AddressingMode::MemoryDest,
+                Register::D0,
+                Some(src_reg),
+                0,
+                OpcodeSize::Dword,
+            );
+
+            let mut cpu = Cpu::new();
+            cpu.set_register(Register::A15, 0x05403502);
+            cpu.set_register(src_reg, 0x00100000);
+            cpu.load_words(0x00100000, &[0xDEADBEEF]).unwrap();
+            let opcode = cpu.decoder(opcode);
+            cpu.execution_stage(&opcode);
+
+            assert_eq!(cpu.register(src_reg), 0x00100000);
+            assert_eq!(cpu.register(Register::A15), 0x054034FE);
+            assert_eq!(
+                cpu.bus()
+                    .memory_bus_read(&OpcodeSize::Dword, 0x054034FE)
+                    .unwrap(),
+                0xDEADBEEF
+            );
+        }
+    }
+
+    #[test]
+    fn test_push_from_memory_inc_execution() {
+        for src_reg in ADDRESS_REGISTERS {
+            if src_reg == Register::A15 {
+                continue;
+            }
+
+            let opcode = generate_opcode(
+                0x03,
+                AddressingMode::MemoryDestInc,
+                Register::D0,
+                Some(src_reg),
+                0,
+                OpcodeSize::Word,
+            );
+
+            let mut cpu = Cpu::new();
+            cpu.set_register(Register::A15, 0x05403502);
+            cpu.set_register(src_reg, 0x00100000);
+            cpu.bus().write_word(0x00100000, 0xBEEF).unwrap();
+            let opcode = cpu.decoder(opcode);
+            cpu.execution_stage(&opcode);
+
+            assert_eq!(cpu.register(src_reg), 0x00100002);
+            assert_eq!(cpu.register(Register::A15), 0x05403500);
+            assert_eq!(
+                cpu.bus()
+                    .memory_bus_read(&OpcodeSize::Word, 0x05403500)
+                    .unwrap(),
+                0x0000BEEF
+            );
+        }
+    }
+
+    #[test]
+    fn test_push_from_memory_dec_execution() {
+        for src_reg in ADDRESS_REGISTERS {
+            if src_reg == Register::A15 {
+                continue;
+            }
+
+            let opcode = generate_opcode(
+                0x03,
+                AddressingMode::MemoryDestDec,
+                Register::D0,
+                Some(src_reg),
+                0,
+                OpcodeSize::Byte,
+            );
+
+            let mut cpu = Cpu::new();
+            cpu.set_register(Register::A15, 0x05403502);
+            cpu.set_register(src_reg, 0x00100000);
+            cpu.load_bytes(0x00100000, &[0xEF]).unwrap();
+            let opcode = cpu.decoder(opcode);
+            cpu.execution_stage(&opcode);
+
+            assert_eq!(cpu.register(src_reg), 0x000FFFFF);
+            assert_eq!(cpu.register(Register::A15), 0x05403501);
+            assert_eq!(
+                cpu.bus()
+                    .memory_bus_read(&OpcodeSize::Byte, 0x05403501)
+                    .unwrap(),
+                0x000000EF
+            );
+        }
+    }
+
+    #[test]
+    fn test_consecutive_pushes_do_not_overwrite() {
+        let push_d0 = generate_opcode(
+            0x03,
+            AddressingMode::Atomic,
+            Register::D0,
+            Some(Register::D0),
+            0,
+            OpcodeSize::Dword,
+        );
+        let push_d1 = generate_opcode(
+            0x03,
+            AddressingMode::Atomic,
+            Register::D0,
+            Some(Register::D1),
+            0,
+            OpcodeSize::Dword,
+        );
+        let pop_d2 = generate_opcode(
+            0x04,
+            AddressingMode::Atomic,
+            Register::D2,
+            None,
+            0,
+            OpcodeSize::Dword,
+        );
+        let pop_d3 = generate_opcode(
+            0x04,
+            AddressingMode::Atomic,
+            Register::D3,
+            None,
+            0,
+            OpcodeSize::Dword,
+        );
+
+        let mut cpu = Cpu::new();
+        cpu.set_register(Register::A15, 0x05403502);
+        cpu.set_register(Register::D0, 0xDEADBEEF);
+        cpu.set_register(Register::D1, 0xC0EDCAFE);
+
+        for pattern in [push_d0, push_d1, pop_d2, pop_d3] {
+            let opcode = cpu.decoder(pattern);
+            cpu.execution_stage(&opcode);
+        }
+
+        assert_eq!(cpu.register(Register::D2), 0xC0EDCAFE);
+        assert_eq!(cpu.register(Register::D3), 0xDEADBEEF);
+        assert_eq!(cpu.register(Register::A15), 0x05403502);
+    }
+
+    #[test]
+    fn test_decode_pop() {
+        let modes = [
+            AddressingMode::Atomic,
+            AddressingMode::MemoryDest,
+            AddressingMode::MemoryDestInc,
+            AddressingMode::MemoryDestDec,
+        ];
+
+        for mode in modes {
+            for dest_reg in ALL_REGISTERS {
+                let opcode = generate_opcode(0x04, mode, dest_reg, None, 0, OpcodeSize::Word);
+                let result = get_decoder_result(opcode);
+                let expected = Opcode::Pop(PopOpcode {
+                    addressing_mode: mode,
+                    destination: dest_reg,
+                    size: OpcodeSize::Word,
+                });
+
+                assert_eq!(result, expected, "Failed {:?} {:?}", result, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_decode_pop_unsupported_addressing_modes() {
+        let modes = [
+            AddressingMode::Immediate,
+            AddressingMode::MemorySrc,
+            AddressingMode::MemorySrcInc,
+            AddressingMode::MemorySrcDec,
+        ];
+
+        for mode in modes {
+            let opcode = generate_opcode(0x04, mode, Register::D0, None, 0, OpcodeSize::Dword);
+            assert_eq!(get_decoder_result(opcode), Opcode::Unknown);
+        }
+    }
+
+    #[test]
+    fn test_pop_dword_register_execution() {
+        for dest_reg in ALL_REGISTERS {
+            if dest_reg == Register::A15 {
+                continue;
+            }
+
+            let opcode = generate_opcode(
+                0x04,
+                AddressingMode::Atomic,
+                dest_reg,
+                None,
+                0,
+                OpcodeSize::Dword,
+            );
+
+            let mut cpu = Cpu::new();
+            cpu.set_register(Register::A15, 0x05403502);
+            cpu.load_words(0x05403502, &[0xDEADBEEF]).unwrap();
+            let opcode = cpu.decoder(opcode);
+            cpu.execution_stage(&opcode);
+
+            assert_eq!(cpu.register(dest_reg), 0xDEADBEEF);
+            assert_eq!(cpu.register(Register::A15), 0x05403506);
+        }
+    }
+
+    #[test]
+    fn test_pop_word_register_execution() {
+        for dest_reg in DATA_REGISTERS {
+            let opcode = generate_opcode(
+                0x04,
+                AddressingMode::Atomic,
+                dest_reg,
+                None,
+                0,
+                OpcodeSize::Word,
+            );
+
+            let mut cpu = Cpu::new();
+            cpu.set_register(Register::A15, 0x05403502);
+            cpu.load_words(0x05403502, &[0xBEEFCAFE]).unwrap();
+            let opcode = cpu.decoder(opcode);
+            cpu.execution_stage(&opcode);
+
+            assert_eq!(cpu.register(dest_reg), 0x0000BEEF);
+            assert_eq!(cpu.register(Register::A15), 0x05403504);
+        }
+    }
+
+    #[test]
+    fn test_pop_byte_register_execution() {
+        for dest_reg in DATA_REGISTERS {
+            let opcode = generate_opcode(
+                0x04,
+                AddressingMode::Atomic,
+                dest_reg,
+                None,
+                0,
+                OpcodeSize::Byte,
+            );
+
+            let mut cpu = Cpu::new();
+            cpu.set_register(Register::A15, 0x05403502);
+            cpu.load_words(0x05403502, &[0xEFBEADDE]).unwrap();
+            let opcode = cpu.decoder(opcode);
+            cpu.execution_stage(&opcode);
+
+            assert_eq!(cpu.register(dest_reg), 0x000000EF);
+            assert_eq!(cpu.register(Register::A15), 0x05403503);
+        }
+    }
+
+    #[test]
+    fn test_pop_into_stack_pointer_keeps_popped_value() {
+        let opcode = generate_opcode(
+            0x04,
+            AddressingMode::Atomic,
+            Register::A15,
+            None,
+            0,
+            OpcodeSize::Dword,
+        );
+
+        let mut cpu = Cpu::new();
+        cpu.set_register(Register::A15, 0x05403502);
+        cpu.load_words(0x05403502, &[0x00001000]).unwrap();
+        let opcode = cpu.decoder(opcode);
+        cpu.execution_stage(&opcode);
+
+        assert_eq!(cpu.register(Register::A15), 0x00001000);
+    }
+
+    #[test]
+    fn test_pop_into_memory_execution() {
+        for dest_reg in ADDRESS_REGISTERS {
+            if dest_reg == Register::A15 {
+                continue;
+            }
+
+            let opcode = generate_opcode(
+                0x04,
+                AddressingMode::MemoryDest,
+                dest_reg,
+                None,
+                0,
+                OpcodeSize::Dword,
+            );
+
+            let mut cpu = Cpu::new();
+            cpu.set_register(Register::A15, 0x05403502);
+            cpu.set_register(dest_reg, 0x00100000);
+            cpu.load_words(0x05403502, &[0xDEADBEEF]).unwrap();
+            let opcode = cpu.decoder(opcode);
+            cpu.execution_stage(&opcode);
+
+            assert_eq!(
+                cpu.bus()
+                    .memory_bus_read(&OpcodeSize::Dword, 0x00100000)
+                    .unwrap(),
+                0xDEADBEEF
+            );
+            assert_eq!(cpu.register(dest_reg), 0x00100000);
+            assert_eq!(cpu.register(Register::A15), 0x05403506);
+        }
+    }
+
+    #[test]
+    fn test_pop_into_memory_inc_execution() {
+        for dest_reg in ADDRESS_REGISTERS {
+            if dest_reg == Register::A15 {
+                continue;
+            }
+
+            let opcode = generate_opcode(
+                0x04,
+                AddressingMode::MemoryDestInc,
+                dest_reg,
+                None,
+                0,
+                OpcodeSize::Word,
+            );
+
+            let mut cpu = Cpu::new();
+            cpu.set_register(Register::A15, 0x05403502);
+            cpu.set_register(dest_reg, 0x00100000);
+            cpu.bus().write_word(0x05403502, 0xBEEF).unwrap();
+            let opcode = cpu.decoder(opcode);
+            cpu.execution_stage(&opcode);
+
+            assert_eq!(
+                cpu.bus()
+                    .memory_bus_read(&OpcodeSize::Word, 0x00100000)
+                    .unwrap(),
+                0x0000BEEF
+            );
+            assert_eq!(cpu.register(dest_reg), 0x00100002);
+            assert_eq!(cpu.register(Register::A15), 0x05403504);
+        }
+    }
+
+    #[test]
+    fn test_pop_into_memory_dec_execution() {
+        for dest_reg in ADDRESS_REGISTERS {
+            if dest_reg == Register::A15 {
+                continue;
+            }
+
+            let opcode = generate_opcode(
+                0x04,
+                AddressingMode::MemoryDestDec,
+                dest_reg,
+                None,
+                0,
+                OpcodeSize::Byte,
+            );
+
+            let mut cpu = Cpu::new();
+            cpu.set_register(Register::A15, 0x05403502);
+            cpu.set_register(dest_reg, 0x00100000);
+            cpu.load_bytes(0x05403502, &[0xEF]).unwrap();
+            let opcode = cpu.decoder(opcode);
+            cpu.execution_stage(&opcode);
+
+            assert_eq!(
+                cpu.bus()
+                    .memory_bus_read(&OpcodeSize::Byte, 0x00100000)
+                    .unwrap(),
+                0x000000EF
+            );
+            assert_eq!(cpu.register(dest_reg), 0x000FFFFF);
+            assert_eq!(cpu.register(Register::A15), 0x05403503);
+        }
+    }
+
+    #[test]
+    fn test_pop_flags() {
+        let opcode = generate_opcode(
+            0x04,
+            AddressingMode::Atomic,
+            Register::D0,
+            None,
+            0,
+            OpcodeSize::Byte,
+        );
+
+        let mut cpu = Cpu::new();
+        cpu.set_register(Register::A15, 0x05403502);
+        cpu.load_bytes(0x05403502, &[0x80]).unwrap();
+        let decoded = cpu.decoder(opcode);
+        cpu.execution_stage(&decoded);
+
+        let negative = 1 << Flags::Negative as u16;
+        let zero = 1 << Flags::Zero as u16;
+        assert_eq!(cpu.status().status_bits() & negative, negative);
+        assert_eq!(cpu.status().status_bits() & zero, 0x00);
+
+        let mut cpu = Cpu::new();
+        cpu.set_register(Register::A15, 0x05403502);
+        cpu.load_bytes(0x05403502, &[0x00]).unwrap();
+        let decoded = cpu.decoder(opcode);
+        cpu.execution_stage(&decoded);
+
+        assert_eq!(cpu.status().status_bits() & zero, zero);
+    }
+
+    #[test]
+    fn test_decode_arithmetic() {
+        let operations = [
+            (0x05, ArithmeticOperation::Add),
+            (0x06, ArithmeticOperation::Sub),
+            (0x07, ArithmeticOperation::Addc),
+            (0x08, ArithmeticOperation::Subc),
+            (0x0A, ArithmeticOperation::Cmp),
+        ];
+
+        for (pattern, operation) in operations {
+            for mode in 0..8 {
+                let mode = AddressingMode::try_from(mode).unwrap();
+                let opcode = generate_opcode(
+                    pattern,
+                    mode,
+                    Register::D1,
+                    Some(Register::A2),
+                    0,
+                    OpcodeSize::Word,
+                );
+                let result = get_decoder_result(opcode);
+                let expected = Opcode::Arithmetic(ArithmeticOpcode {
+                    operation,
+                    addr_mode: mode,
+                    destination: Register::D1,
+                    source: Register::A2,
+                    size: OpcodeSize::Word,
+                });
+
+                assert_eq!(result, expected, "Failed {:?} {:?}", result, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_decode_neg_unsupported_addressing_modes() {
+        let modes = [
+            AddressingMode::Immediate,
+            AddressingMode::MemorySrc,
+            AddressingMode::MemorySrcInc,
+            AddressingMode::MemorySrcDec,
+        ];
+
+        for mode in modes {
+            let opcode = generate_opcode(0x09, mode, Register::D0, None, 0, OpcodeSize::Dword);
+            assert_eq!(get_decoder_result(opcode), Opcode::Unknown);
+        }
+    }
+
+    #[test]
+    fn test_add_registers_execution() {
+        let sizes = [
+            (OpcodeSize::Byte, 0x000000FF, 0x00000001, 0x00000000),
+            (OpcodeSize::Word, 0x0000FFFF, 0x00000001, 0x00000000),
+            (OpcodeSize::Dword, 0xFFFFFFFF, 0x00000001, 0x00000000),
+        ];
+
+        for (size, lhs, rhs, expected) in sizes {
+            for dest_reg in DATA_REGISTERS {
+                let opcode = generate_opcode(
+                    0x05,
+                    AddressingMode::Atomic,
+                    dest_reg,
+                    Some(Register::A0),
+                    0,
+                    size,
+                );
+
+                let mut cpu = Cpu::new();
+                cpu.set_register(dest_reg, lhs);
+                cpu.set_register(Register::A0, rhs);
+                let opcode = cpu.decoder(opcode);
+                cpu.execution_stage(&opcode);
+
+                assert_eq!(cpu.register(dest_reg), expected);
+                assert!(flag_is_set(&cpu, Flags::Carry));
+                assert!(flag_is_set(&cpu, Flags::Zero));
+                assert!(!flag_is_set(&cpu, Flags::Overflow));
+                assert!(!flag_is_set(&cpu, Flags::Negative));
+            }
+        }
+    }
+
+    #[test]
+    fn test_add_immediate_execution() {
+        let opcode = generate_opcode(
+            0x05,
+            AddressingMode::Immediate,
+            Register::D3,
+            None,
+            0,
+            OpcodeSize::Dword,
+        );
+
+        let mut cpu = Cpu::new();
+        cpu.set_pc(0x00001000);
+        cpu.load_words(0x00001004, &[0x7FFFFFFF]).unwrap();
+        cpu.set_register(Register::D3, 0x00000001);
+        let opcode = cpu.decoder(opcode);
+        cpu.execution_stage(&opcode);
+
+        assert_eq!(cpu.register(Register::D3), 0x80000000);
+        assert!(flag_is_set(&cpu, Flags::Overflow));
+        assert!(flag_is_set(&cpu, Flags::Negative));
+        assert!(!flag_is_set(&cpu, Flags::Carry));
+        assert!(!flag_is_set(&cpu, Flags::Parity));
+    }
+
+    #[test]
+    fn test_sub_memory_source_inc_execution() {
+        for src_reg in ADDRESS_REGISTERS {
+            let opcode = generate_opcode(
+                0x06,
+                AddressingMode::MemorySrcInc,
+                Register::D0,
+                Some(src_reg),
+                0,
+                OpcodeSize::Word,
+            );
+
+            let mut cpu = Cpu::new();
+            cpu.set_register(src_reg, 0x00100000);
+            cpu.set_register(Register::D0, 0x00000010);
+            cpu.bus().write_word(0x00100000, 0x0011).unwrap();
+            let opcode = cpu.decoder(opcode);
+            cpu.execution_stage(&opcode);
+
+            assert_eq!(cpu.register(Register::D0), 0x0000FFFF);
+            assert_eq!(cpu.register(src_reg), 0x00100002);
+            assert!(flag_is_set(&cpu, Flags::Carry));
+            assert!(flag_is_set(&cpu, Flags::Negative));
+            assert!(!flag_is_set(&cpu, Flags::Overflow));
+        }
+    }
+
+    #[test]
+    fn test_add_memory_destination_dec_execution() {
+        for dest_reg in ADDRESS_REGISTERS {
+            let opcode = generate_opcode(
+                0x05,
+                AddressingMode::MemoryDestDec,
+                dest_reg,
+                Some(Register::D2),
+                0,
+                OpcodeSize::Byte,
+            );
+
+            let mut cpu = Cpu::new();
+            cpu.set_register(dest_reg, 0x00100000);
+            cpu.set_register(Register::D2, 0x00000003);
+            cpu.load_bytes(0x00100000, &[0x7F]).unwrap();
+            let opcode = cpu.decoder(opcode);
+            cpu.execution_stage(&opcode);
+
+            assert_eq!(
+                cpu.bus()
+                    .memory_bus_read(&OpcodeSize::Byte, 0x00100000)
+                    .unwrap(),
+                0x00000082
+            );
+            assert_eq!(cpu.register(dest_reg), 0x000FFFFF);
+            assert!(flag_is_set(&cpu, Flags::Overflow));
+            assert!(flag_is_set(&cpu, Flags::Negative));
+            assert!(flag_is_set(&cpu, Flags::Parity));
+        }
+    }
+
+    #[test]
+    fn test_addc_and_subc_use_carry() {
+        //NOTE: 64bit addition 0x00000001_FFFFFFFF + 0x00000000_00000001 split over D0:D1 and D2:D3
+        let add_low = generate_opcode(
+            0x05,
+            AddressingMode::Atomic,
+            Register::D1,
+            Some(Register::D3),
+            0,
+            OpcodeSize::Dword,
+        );
+        let add_high = generate_opcode(
+            0x07,
+            AddressingMode::Atomic,
+            Register::D0,
+            Some(Register::D2),
+            0,
+            OpcodeSize::Dword,
+        );
+
+        let mut cpu = Cpu::new();
+        cpu.set_register(Register::D0, 0x00000001);
+        cpu.set_register(Register::D1, 0xFFFFFFFF);
+        cpu.set_register(Register::D3, 0x00000001);
+        for pattern in [add_low, add_high] {
+            let opcode = cpu.decoder(pattern);
+            cpu.execution_stage(&opcode);
+        }
+
+        assert_eq!(cpu.register(Register::D0), 0x00000002);
+        assert_eq!(cpu.register(Register::D1), 0x00000000);
+        assert!(!flag_is_set(&cpu, Flags::Carry));
+
+        let sub_low = generate_opcode(
+            0x06,
+            AddressingMode::Atomic,
+            Register::D1,
+            Some(Register::D3),
+            0,
+            OpcodeSize::Dword,
+        );
+        let sub_high = generate_opcode(
+            0x08,
+            AddressingMode::Atomic,
+            Register::D0,
+            Some(Register::D2),
+            0,
+            OpcodeSize::Dword,
+        );
+        for pattern in [sub_low, sub_high] {
+            let opcode = cpu.decoder(pattern);
+            cpu.execution_stage(&opcode);
+        }
+
+        assert_eq!(cpu.register(Register::D0), 0x00000001);
+        assert_eq!(cpu.register(Register::D1), 0xFFFFFFFF);
+        assert!(!flag_is_set(&cpu, Flags::Carry));
+    }
+
+    #[test]
+    fn test_neg_execution() {
+        let sizes = [
+            (OpcodeSize::Byte, 0x00000001, 0x000000FF),
+            (OpcodeSize::Word, 0x00000001, 0x0000FFFF),
+            (OpcodeSize::Dword, 0x00000001, 0xFFFFFFFF),
+        ];
+
+        for (size, value, expected) in sizes {
+            let opcode = generate_opcode(0x09, AddressingMode::Atomic, Register::D4, None, 0, size);
+
+            let mut cpu = Cpu::new();
+            cpu.set_register(Register::D4, value);
+            let opcode = cpu.decoder(opcode);
+            cpu.execution_stage(&opcode);
+
+            assert_eq!(cpu.register(Register::D4), expected);
+            assert!(flag_is_set(&cpu, Flags::Carry));
+            assert!(flag_is_set(&cpu, Flags::Negative));
+            assert!(!flag_is_set(&cpu, Flags::Overflow));
+        }
+
+        let opcode = generate_opcode(
+            0x09,
+            AddressingMode::Atomic,
+            Register::D4,
+            None,
+            0,
+            OpcodeSize::Byte,
+        );
+        let mut cpu = Cpu::new();
+        cpu.set_register(Register::D4, 0x00000080);
+        let opcode = cpu.decoder(opcode);
+        cpu.execution_stage(&opcode);
+
+        assert_eq!(cpu.register(Register::D4), 0x00000080);
+        assert!(flag_is_set(&cpu, Flags::Overflow));
+    }
+
+    #[test]
+    fn test_cmp_does_not_write_back() {
+        let opcode = generate_opcode(
+            0x0A,
+            AddressingMode::Atomic,
+            Register::D0,
+            Some(Register::D1),
+            0,
+            OpcodeSize::Dword,
+        );
+
+        let mut cpu = Cpu::new();
+        cpu.set_register(Register::D0, 0x00000005);
+        cpu.set_register(Register::D1, 0x00000005);
+        let decoded = cpu.decoder(opcode);
+        cpu.execution_stage(&decoded);
+
+        assert_eq!(cpu.register(Register::D0), 0x00000005);
+        assert!(flag_is_set(&cpu, Flags::Zero));
+        assert!(!flag_is_set(&cpu, Flags::Carry));
+
+        cpu.set_register(Register::D1, 0x00000006);
+        let decoded = cpu.decoder(opcode);
+        cpu.execution_stage(&decoded);
+
+        assert_eq!(cpu.register(Register::D0), 0x00000005);
+        assert!(!flag_is_set(&cpu, Flags::Zero));
+        assert!(flag_is_set(&cpu, Flags::Carry));
+        assert!(flag_is_set(&cpu, Flags::Negative));
+    }
+
+    fn execute_logic(
+        opcode: u32,
+        mode: AddressingMode,
+        offset: u32,
+        size: OpcodeSize,
+        dest_value: u32,
+        src_value: u32,
+    ) -> Cpu {
+        let pattern = generate_opcode(opcode, mode, Register::D0, Some(Register::D1), offset, size);
+
+        let mut cpu = Cpu::new();
+        cpu.set_register(Register::D0, dest_value);
+        cpu.set_register(Register::D1, src_value);
+        let opcode = cpu.decoder(pattern);
+        cpu.execution_stage(&opcode);
+
+        cpu
+    }
+
+    #[test]
+    fn test_decode_logic() {
+        let operations = [
+            (0x0B, LogicOperation::And),
+            (0x0C, LogicOperation::Or),
+            (0x0D, LogicOperation::Xor),
+        ];
+
+        for (pattern, operation) in operations {
+            for mode in 0..8 {
+                let mode = AddressingMode::try_from(mode).unwrap();
+                let opcode = generate_opcode(
+                    pattern,
+                    mode,
+                    Register::A3,
+                    Some(Register::D7),
+                    0,
+                    OpcodeSize::Byte,
+                );
+                let result = get_decoder_result(opcode);
+                let expected = Opcode::Logic(LogicOpcode {
+                    operation,
+                    addr_mode: mode,
+                    destination: Register::A3,
+                    source: Register::D7,
+                    offset: 0,
+                    size: OpcodeSize::Byte,
+                });
+
+                assert_eq!(result, expected, "Failed {:?} {:?}", result, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_decode_shifts() {
+        let operations = [
+            (0x0F, LogicOperation::Lsl),
+            (0x10, LogicOperation::Lsr),
+            (0x11, LogicOperation::Asr),
+            (0x12, LogicOperation::Rol),
+            (0x13, LogicOperation::Ror),
+        ];
+
+        for (pattern, operation) in operations {
+            let opcode = generate_opcode(
+                pattern,
+                AddressingMode::Immediate,
+                Register::D2,
+                None,
+                17,
+                OpcodeSize::Dword,
+            );
+            let result = get_decoder_result(opcode);
+            let expected = Opcode::Logic(LogicOpcode {
+                operation,
+                addr_mode: AddressingMode::Immediate,
+                destination: Register::D2,
+                source: Register::D0,
+                offset: 17,
+                size: OpcodeSize::Dword,
+            });
+            assert_eq!(result, expected, "Failed {:?} {:?}", result, expected);
+
+            for mode in 2..8 {
+                let mode = AddressingMode::try_from(mode).unwrap();
+                let opcode =
+                    generate_opcode(pattern, mode, Register::D2, None, 0, OpcodeSize::Dword);
+                assert_eq!(get_decoder_result(opcode), Opcode::Unknown);
+            }
+        }
+    }
+
+    #[test]
+    fn test_and_or_xor_registers_execution() {
+        let cpu = execute_logic(
+            0x0B,
+            AddressingMode::Atomic,
+            0,
+            OpcodeSize::Dword,
+            0xFF00FF00,
+            0x0FF00FF0,
+        );
+        assert_eq!(cpu.register(Register::D0), 0x0F000F00);
+
+        let cpu = execute_logic(
+            0x0C,
+            AddressingMode::Atomic,
+            0,
+            OpcodeSize::Word,
+            0xFF00FF00,
+            0x0FF00FF0,
+        );
+        assert_eq!(cpu.register(Register::D0), 0x0000FFF0);
+        assert!(flag_is_set(&cpu, Flags::Negative));
+
+        let cpu = execute_logic(
+            0x0D,
+            AddressingMode::Atomic,
+            0,
+            OpcodeSize::Byte,
+            0x000000AA,
+            0x000000AA,
+        );
+        assert_eq!(cpu.register(Register::D0), 0x00000000);
+        assert!(flag_is_set(&cpu, Flags::Zero));
+        assert!(flag_is_set(&cpu, Flags::Parity));
+        assert!(!flag_is_set(&cpu, Flags::Carry));
+    }
+
+    #[test]
+    fn test_and_immediate_execution() {
+        let opcode = generate_opcode(
+            0x0B,
+            AddressingMode::Immediate,
+            Register::D5,
+            None,
+            0,
+            OpcodeSize::Word,
+        );
+
+        let mut cpu = Cpu::new();
+        cpu.set_pc(0x00001000);
+        cpu.load_words(0x00001004, &[0xFFFF00F0]).unwrap();
+        cpu.set_register(Register::D5, 0xABCDABCD);
+        let opcode = cpu.decoder(opcode);
+        cpu.execution_stage(&opcode);
+
+        assert_eq!(cpu.register(Register::D5), 0x000000C0);
+    }
+
+    #[test]
+    fn test_not_memory_inc_execution() {
+        for dest_reg in ADDRESS_REGISTERS {
+            let opcode = generate_opcode(
+                0x0E,
+                AddressingMode::MemoryDestInc,
+                dest_reg,
+                None,
+                0,
+                OpcodeSize::Word,
+            );
+
+            let mut cpu = Cpu::new();
+            cpu.set_register(dest_reg, 0x00100000);
+            cpu.bus().write_word(0x00100000, 0x00FF).unwrap();
+            let opcode = cpu.decoder(opcode);
+            cpu.execution_stage(&opcode);
+
+            assert_eq!(
+                cpu.bus()
+                    .memory_bus_read(&OpcodeSize::Word, 0x00100000)
+                    .unwrap(),
+                0x0000FF00
+            );
+            assert_eq!(cpu.register(dest_reg), 0x00100002);
+            assert!(flag_is_set(&cpu, Flags::Negative));
+        }
+    }
+
+    #[test]
+    fn test_shift_register_count_execution() {
+        let cpu = execute_logic(
+            0x0F,
+            AddressingMode::Atomic,
+            0,
+            OpcodeSize::Byte,
+            0x000000C1,
+            0x00000002,
+        );
+        assert_eq!(cpu.register(Register::D0), 0x00000004);
+        assert!(flag_is_set(&cpu, Flags::Carry));
+
+        let cpu = execute_logic(
+            0x10,
+            AddressingMode::Atomic,
+            0,
+            OpcodeSize::Word,
+            0x00018001,
+            0x00000001,
+        );
+        assert_eq!(cpu.register(Register::D0), 0x00004000);
+        assert!(flag_is_set(&cpu, Flags::Carry));
+
+        let cpu = execute_logic(
+            0x11,
+            AddressingMode::Atomic,
+            0,
+            OpcodeSize::Dword,
+            0x80000000,
+            0x00000004,
+        );
+        assert_eq!(cpu.register(Register::D0), 0xF8000000);
+        assert!(!flag_is_set(&cpu, Flags::Carry));
+        assert!(flag_is_set(&cpu, Flags::Negative));
+    }
+
+    #[test]
+    fn test_shift_immediate_count_execution() {
+        let cpu = execute_logic(
+            0x0F,
+            AddressingMode::Immediate,
+            31,
+            OpcodeSize::Dword,
+            0x00000003,
+            0x00000000,
+        );
+        assert_eq!(cpu.register(Register::D0), 0x80000000);
+        assert!(flag_is_set(&cpu, Flags::Carry));
+        assert_eq!(cpu.pc(), 0x04);
+
+        let cpu = execute_logic(
+            0x12,
+            AddressingMode::Immediate,
+            4,
+            OpcodeSize::Word,
+            0x0000F00F,
+            0x00000000,
+        );
+        assert_eq!(cpu.register(Register::D0), 0x000000FF);
+        assert!(flag_is_set(&cpu, Flags::Carry));
+
+        let cpu = execute_logic(
+            0x13,
+            AddressingMode::Immediate,
+            1,
+            OpcodeSize::Byte,
+            0x00000002,
+            0x00000000,
+        );
+        assert_eq!(cpu.register(Register::D0), 0x00000001);
+        assert!(!flag_is_set(&cpu, Flags::Carry));
+    }
+
+    fn generate_branch(opcode: u32, condition: Condition) -> u32 {
+        let condition: u32 = condition.into();
+        let size: u32 = OpcodeSize::Dword.into();
+        let mode: u32 = AddressingMode::Immediate.into();
+
+        (size << 30) | (condition << 14) | (mode << 8) | opcode
+    }
+
+    #[test]
+    fn test_decode_control_flow() {
+        let opcode = generate_opcode(
+            0x14,
+            AddressingMode::Immediate,
+            Register::D0,
+            None,
+            0,
+            OpcodeSize::Dword,
+        );
+        assert_eq!(
+            get_decoder_result(opcode),
+            Opcode::Jump(JumpOpcode {
+                operation: JumpOperation::Jmp,
+                addr_mode: AddressingMode::Immediate,
+                destination: Register::D0,
+            })
+        );
+
+        let opcode = generate_opcode(
+            0x16,
+            AddressingMode::Atomic,
+            Register::A4,
+            None,
+            0,
+            OpcodeSize::Dword,
+        );
+        assert_eq!(
+            get_decoder_result(opcode),
+            Opcode::Jump(JumpOpcode {
+                operation: JumpOperation::Jsr,
+                addr_mode: AddressingMode::Atomic,
+                destination: Register::A4,
+            })
+        );
+
+        for pattern in 0x00..=0x10 {
+            let condition = Condition::try_from(pattern).unwrap();
+            assert_eq!(
+                get_decoder_result(generate_branch(0x15, condition)),
+                Opcode::Branch(BranchOpcode {
+                    condition,
+                    subroutine: false,
+                })
+            );
+            assert_eq!(
+                get_decoder_result(generate_branch(0x18, condition)),
+                Opcode::Branch(BranchOpcode {
+                    condition,
+                    subroutine: true,
+                })
+            );
+        }
+
+        assert_eq!(get_decoder_result(0x80000017), Opcode::Rts(RtsOpcode));
+    }
+
+    #[test]
+    fn test_decode_invalid_control_flow() {
+        //NOTE: Condition 0x11 does not exist
+        assert_eq!(get_decoder_result(0x80044115), Opcode::Unknown);
+        //NOTE: Branches need their displacement
+        assert_eq!(get_decoder_result(0x80000015), Opcode::Unknown);
+
+        for mode in 2..8 {
+            let mode = AddressingMode::try_from(mode).unwrap();
+            let opcode = generate_opcode(0x14, mode, Register::A0, None, 0, OpcodeSize::Dword);
+            assert_eq!(get_decoder_result(opcode), Opcode::Unknown);
+        }
+    }
+
+    #[test]
+    fn test_jmp_execution() {
+        let opcode = generate_opcode(
+            0x14,
+            AddressingMode::Immediate,
+            Register::D0,
+            None,
+            0,
+            OpcodeSize::Dword,
+        );
+
+        let mut cpu = Cpu::new();
+        cpu.set_pc(0x00001000);
+        cpu.load_words(0x00001004, &[0x00200000]).unwrap();
+        let opcode = cpu.decoder(opcode);
+        cpu.execution_stage(&opcode);
+
+        assert_eq!(cpu.pc(), 0x00200000);
+
+        for dest_reg in ALL_REGISTERS {
+            let opcode = generate_opcode(
+                0x14,
+                AddressingMode::Atomic,
+                dest_reg,
+                None,
+                0,
+                OpcodeSize::Dword,
+            );
+
+            let mut cpu = Cpu::new();
+            cpu.set_pc(0x00001000);
+            cpu.set_register(dest_reg, 0x00300000);
+            let opcode = cpu.decoder(opcode);
+            cpu.execution_stage(&opcode);
+
+            assert_eq!(cpu.pc(), 0x00300000);
+        }
+    }
+
+    #[test]
+    fn test_bra_execution() {
+        let mut cpu = Cpu::new();
+        cpu.set_pc(0x00001000);
+        cpu.load_words(0x00001004, &[0x00000100]).unwrap();
+        let opcode = cpu.decoder(generate_branch(0x15, Condition::Always));
+        cpu.execution_stage(&opcode);
+
+        assert_eq!(cpu.pc(), 0x00001100);
+
+        cpu.load_words(0x00001104, &[(-0x200_i32) as u32]).unwrap();
+        let opcode = cpu.decoder(generate_branch(0x15, Condition::Always));
+        cpu.execution_stage(&opcode);
+
+        assert_eq!(cpu.pc(), 0x00000F00);
+    }
+
+    #[test]
+    fn test_conditional_branch_execution() {
+        let mut cpu = Cpu::new();
+        cpu.set_pc(0x00001000);
+        cpu.load_words(0x00001004, &[0x00000100]).unwrap();
+        cpu.status_register.raise(Flags::Zero);
+
+        let opcode = cpu.decoder(generate_branch(0x15, Condition::NotEqual));
+        cpu.execution_stage(&opcode);
+        assert_eq!(cpu.pc(), 0x00001008);
+
+        cpu.set_pc(0x00001000);
+        let opcode = cpu.decoder(generate_branch(0x15, Condition::Equal));
+        cpu.execution_stage(&opcode);
+        assert_eq!(cpu.pc(), 0x00001100);
+    }
+
+    #[test]
+    fn test_cmp_and_branch() {
+        let cmp = generate_opcode(
+            0x0A,
+            AddressingMode::Atomic,
+            Register::D0,
+            Some(Register::D1),
+            0,
+            OpcodeSize::Dword,
+        );
+
+        //NOTE: (D0, D1, taken conditions, not taken conditions)
+        let cases = [
+            (
+                5,
+                3,
+                vec![Condition::Greater, Condition::Higher, Condition::NotEqual],
+                vec![Condition::Less, Condition::LowerOrSame, Condition::Equal],
+            ),
+            (
+                (-5_i32) as u32,
+                3,
+                vec![Condition::Less, Condition::Higher, Condition::LessOrEqual],
+                vec![Condition::Greater, Condition::LowerOrSame],
+            ),
+            (
+                3,
+                3,
+                vec![
+                    Condition::Equal,
+                    Condition::GreaterOrEqual,
+                    Condition::LessOrEqual,
+                ],
+                vec![Condition::Greater, Condition::Less, Condition::Higher],
+            ),
+        ];
+
+        for (lhs, rhs, taken, not_taken) in cases {
+            for (condition, expected_pc) in taken
+                .iter()
+                .map(|c| (c, 0x00001100))
+                .chain(not_taken.iter().map(|c| (c, 0x00001008)))
+            {
+                let mut cpu = Cpu::new();
+                cpu.set_register(Register::D0, lhs);
+                cpu.set_register(Register::D1, rhs);
+                let opcode = cpu.decoder(cmp);
+                cpu.execution_stage(&opcode);
+
+                cpu.set_pc(0x00001000);
+                cpu.load_words(0x00001004, &[0x00000100]).unwrap();
+                let opcode = cpu.decoder(generate_branch(0x15, *condition));
+                cpu.execution_stage(&opcode);
+
+                assert_eq!(cpu.pc(), expected_pc, "{:?} {:?} {:?}", lhs, rhs, condition);
+            }
+        }
+    }
+
+    #[test]
+    fn test_jsr_and_rts_execution() {
+        let jsr = generate_opcode(
+            0x16,
+            AddressingMode::Immediate,
+            Register::D0,
+            None,
+            0,
+            OpcodeSize::Dword,
+        );
+
+        let mut cpu = Cpu::new();
+        cpu.set_pc(0x00001000);
+        cpu.set_register(Register::A15, 0x00100000);
+        cpu.load_words(0x00001004, &[0x00002000]).unwrap();
+        let opcode = cpu.decoder(jsr);
+        cpu.execution_stage(&opcode);
+
+        assert_eq!(cpu.pc(), 0x00002000);
+        assert_eq!(cpu.register(Register::A15), 0x000FFFFC);
+        assert_eq!(cpu.bus().read_dword(0x000FFFFC).unwrap(), 0x00001008);
+
+        let opcode = cpu.decoder(0x80000017);
+        cpu.execution_stage(&opcode);
+
+        assert_eq!(cpu.pc(), 0x00001008);
+        assert_eq!(cpu.register(Register::A15), 0x00100000);
+    }
+
+    #[test]
+    fn test_jsr_register_execution() {
+        let jsr = generate_opcode(
+            0x16,
+            AddressingMode::Atomic,
+            Register::A0,
+            None,
+            0,
+            OpcodeSize::Dword,
+        );
+
+        let mut cpu = Cpu::new();
+        cpu.set_pc(0x00001000);
+        cpu.set_register(Register::A15, 0x00100000);
+        cpu.set_register(Register::A0, 0x00002000);
+        let opcode = cpu.decoder(jsr);
+        cpu.execution_stage(&opcode);
+
+        assert_eq!(cpu.pc(), 0x00002000);
+        assert_eq!(cpu.bus().read_dword(0x000FFFFC).unwrap(), 0x00001004);
+    }
+
+    #[test]
+    fn test_bsr_execution() {
+        let mut cpu = Cpu::new();
+        cpu.set_pc(0x00001000);
+        cpu.set_register(Register::A15, 0x00100000);
+        cpu.load_words(0x00001004, &[(-0x800_i32) as u32]).unwrap();
+        let opcode = cpu.decoder(generate_branch(0x18, Condition::Always));
+        cpu.execution_stage(&opcode);
+
+        assert_eq!(cpu.pc(), 0x00000800);
+        assert_eq!(cpu.bus().read_dword(0x000FFFFC).unwrap(), 0x00001008);
+
+        let mut cpu = Cpu::new();
+        cpu.set_pc(0x00001000);
+        cpu.set_register(Register::A15, 0x00100000);
+        let opcode = cpu.decoder(generate_branch(0x18, Condition::CarrySet));
+        cpu.execution_stage(&opcode);
+
+        assert_eq!(cpu.pc(), 0x00001008);
+        assert_eq!(cpu.register(Register::A15), 0x00100000);
+    }
+
+    #[test]
+    fn test_full_cpu_cycle() {
+        let opcode = generate_opcode(
+            0x01,
+            AddressingMode::Atomic,
+            Register::D0,
+            Some(Register::D1),
+            0,
+            OpcodeSize::Dword,
+        );
+        let mut cpu = Cpu::new();
+
+        cpu.load_words(0x00000000, &[opcode]).unwrap();
+        cpu.set_register(Register::D1, 0xDEADBEEF);
+
+        let step = cpu.step();
+
+        assert_eq!(cpu.register(Register::D0), cpu.register(Register::D1));
+        assert_eq!(step.address, 0x00000000);
+        assert!(matches!(step.opcode, Opcode::Move(_)));
+        assert_eq!(cpu.pc(), 0x00000004);
+    }
+
+    #[test]
+    fn test_step_skips_extension_words() {
+        let mut cpu = Cpu::new();
+        let program = [
+            generate_opcode(
+                0x01,
+                AddressingMode::Immediate,
+                Register::D0,
+                None,
+                0,
+                OpcodeSize::Dword,
+            ),
+            0x00000005,
+            generate_opcode(
+                0x05,
+                AddressingMode::Immediate,
+                Register::D0,
+                None,
+                0,
+                OpcodeSize::Dword,
+            ),
+            0x00000003,
+            generate_opcode(
+                0x0F,
+                AddressingMode::Immediate,
+                Register::D0,
+                None,
+                1,
+                OpcodeSize::Dword,
+            ),
+        ];
+        for (index, word) in program.iter().enumerate() {
+            cpu.load_words(index as u32 * 4, &[*word]).unwrap();
+        }
+
+        cpu.step();
+        assert_eq!(cpu.pc(), 0x00000008);
+        cpu.step();
+        assert_eq!(cpu.pc(), 0x00000010);
+        cpu.step();
+        assert_eq!(cpu.pc(), 0x00000014);
+
+        assert_eq!(cpu.register(Register::D0), 0x00000010);
+    }
+
+    #[test]
+    fn test_run_until_counting_loop() {
+        //NOTE: D0 = 10; loop: D1 += 2; D0 -= 1; bne loop
+        let mut cpu = Cpu::new();
+        let program = [
+            generate_opcode(
+                0x01,
+                AddressingMode::Immediate,
+                Register::D0,
+                None,
+                0,
+                OpcodeSize::Dword,
+            ),
+            0x0000000A,
+            generate_opcode(
+                0x05,
+                AddressingMode::Immediate,
+                Register::D1,
+                None,
+                0,
+                OpcodeSize::Dword,
+            ),
+            0x00000002,
+            generate_opcode(
+                0x06,
+                AddressingMode::Immediate,
+                Register::D0,
+                None,
+                0,
+                OpcodeSize::Dword,
+            ),
+            0x00000001,
+            generate_branch(0x15, Condition::NotEqual),
+            (-16i32) as u32,
+        ];
+        for (index, word) in program.iter().enumerate() {
+            cpu.load_words(index as u32 * 4, &[*word]).unwrap();
+        }
+
+        let executed = cpu.run_until(|cpu, _| cpu.pc() == 0x00000020);
+
+        assert_eq!(executed, 1 + 10 * 3);
+        assert_eq!(cpu.register(Register::D0), 0x00000000);
+        assert_eq!(cpu.register(Register::D1), 0x00000014);
+    }
+
+    #[test]
+    fn test_run_until_step_condition() {
+        let mut cpu = Cpu::new();
+        cpu.load_words(0x00000000, &[0x80000114]).unwrap();
+        cpu.load_words(0x00000004, &[0x00000000]).unwrap();
+
+        let executed =
+            cpu.run_until(|_, step| step.address == 0x00000000 && step.opcode.is_control_flow());
+
+        assert_eq!(executed, 1);
+        assert_eq!(cpu.pc(), 0x00000000);
+    }
+
+    #[test]
+    fn test_illegal_instruction_exception() {
+        let mut cpu = Cpu::new();
+        cpu.set_pc(0x00001000);
+        cpu.set_register(Register::A15, 0x00100000);
+        cpu.status_register.raise(Flags::Carry);
+        cpu.load_words(VECTOR_TABLE_START + 0x02 * 4, &[0x00004000])
+            .unwrap();
+        cpu.load_words(0x00001000, &[0x000000FF]).unwrap();
+
+        let step = cpu.step();
+
+        assert_eq!(step.address, 0x00001000);
+        assert_eq!(step.opcode, Opcode::Unknown);
+        assert_eq!(step.exception, Some(Exception::IllegalInstruction));
+        assert_eq!(cpu.pc(), 0x00004000);
+        assert_eq!(cpu.register(Register::A15), 0x000FFFF8);
+        assert_eq!(cpu.bus().read_dword(0x000FFFFC).unwrap(), 0x00001000);
+        assert_eq!(
+            cpu.bus().read_dword(0x000FFFF8).unwrap(),
+            cpu.status().status_bits() as u32
+        );
+        assert!(flag_is_set(&cpu, Flags::Carry));
+    }
+
+    #[test]
+    fn test_illegal_lea_destination() {
+        let mut cpu = Cpu::new();
+        let opcode = generate_opcode(
+            0x02,
+            AddressingMode::Immediate,
+            Register::D3,
+            None,
+            0,
+            OpcodeSize::Dword,
+        );
+        assert_eq!(cpu.decoder(opcode), Opcode::Unknown);
+
+        cpu.set_register(Register::A15, 0x00100000);
+        cpu.load_words(VECTOR_TABLE_START + 0x02 * 4, &[0x00004000])
+            .unwrap();
+        let exception = cpu.execution_stage(&Opcode::Lea(LeaOpcode {
+            destination: Register::D3,
+        }));
+
+        assert_eq!(exception, Some(Exception::IllegalInstruction));
+        assert_eq!(cpu.pc(), 0x00004000);
+        assert_eq!(cpu.register(Register::D3), 0x00000000);
+    }
+
+    #[test]
+    fn test_illegal_addressing_modes_do_not_panic() {
+        //NOTE: POP from an immediate and a branch without a displacement aren't part of the ISA
+        let patterns = [
+            generate_opcode(
+                0x04,
+                AddressingMode::Immediate,
+                Register::D0,
+                None,
+                0,
+                OpcodeSize::Dword,
+            ),
+            generate_opcode(
+                0x15,
+                AddressingMode::Atomic,
+                Register::D0,
+                None,
+                0,
+                OpcodeSize::Dword,
+            ),
+        ];
+
+        for pattern in patterns {
+            let mut cpu = Cpu::new();
+            cpu.set_register(Register::A15, 0x00100000);
+            cpu.load_words(0x00000000, &[pattern]).unwrap();
+
+            let step = cpu.step();
+
+            assert_eq!(step.exception, Some(Exception::IllegalInstruction));
+            assert_eq!(cpu.bus().read_dword(0x000FFFFC).unwrap(), 0x00000000);
+        }
+    }
+
+    #[test]
+    fn test_decode_interrupt_opcodes() {
+        let cpu = Cpu::new();
+
+        assert_eq!(cpu.decoder(0x80000019), Opcode::Rti(RtiOpcode));
+        assert_eq!(
+            cpu.decoder(0x8000001A),
+            Opcode::InterruptEnable(InterruptEnableOpcode { enable: true })
+        );
+        assert_eq!(
+            cpu.decoder(0x8000001B),
+            Opcode::InterruptEnable(InterruptEnableOpcode { enable: false })
+        );
+    }
+
+    #[test]
+    fn test_hand_built_opcodes_with_invalid_modes() {
+        let mut cpu = Cpu::new();
+        cpu.set_register(Register::A15, 0x00100000);
+        cpu.load_words(VECTOR_TABLE_START + 8, &[0x00004000])
+            .unwrap();
+        let opcodes = [
+            Opcode::Push(PushOpcode {
+                addressing_mode: AddressingMode::MemorySrc,
+                source: Register::A0,
+                size: OpcodeSize::Dword,
+            }),
+            Opcode::Pop(PopOpcode {
+                addressing_mode: AddressingMode::Immediate,
+                destination: Register::D0,
+                size: OpcodeSize::Dword,
+            }),
+            Opcode::Jump(JumpOpcode {
+                operation: JumpOperation::Jsr,
+                addr_mode: AddressingMode::MemoryDest,
+                destination: Register::A0,
+            }),
+        ];
+
+        //NOTE: Only the exception frame touches the stack
+        for opcode in opcodes {
+            cpu.set_pc(0x00001000);
+            cpu.set_register(Register::A15, 0x00100000);
+            assert_eq!(
+                cpu.execution_stage(&opcode),
+                Some(Exception::IllegalInstruction)
+            );
+            assert_eq!(cpu.pc(), 0x00004000);
+            assert_eq!(cpu.register(Register::A15), 0x00100000 - 8);
+            assert_eq!(cpu.bus().read_dword(0x00100000 - 4).unwrap(), 0x00001000);
+        }
+    }
+
+    #[test]
+    fn test_decode_nop() {
+        assert_eq!(get_decoder_result(0x00000000), Opcode::Nop);
+        for opcode in [0xFFFFFF00, 0x0004C100, 0x80000000, 0x00000100] {
+            assert_eq!(get_decoder_result(opcode), Opcode::Unknown);
+        }
+    }
+
+    #[test]
+    fn test_nop() {
+        let mut cpu = Cpu::new();
+        cpu.set_register(Register::A15, 0x00100000);
+        cpu.set_pc(0x00001000);
+        let status = cpu.status().status_bits();
+
+        let step = cpu.step();
+        assert_eq!(step.opcode, Opcode::Nop);
+        assert_eq!(step.exception, None);
+        assert_eq!(step.cycles, 1);
+        assert_eq!(cpu.pc(), 0x00001004);
+        assert_eq!(cpu.status().status_bits(), status);
+        assert_eq!(cpu.register(Register::A15), 0x00100000);
+        for register in ALL_REGISTERS.iter().filter(|r| **r != Register::A15) {
+            assert_eq!(cpu.register(*register), 0x00000000);
+        }
+    }
+
+    #[test]
+    fn test_ei_di() {
+        let mut cpu = Cpu::new();
+
+        cpu.execution_stage(&cpu.decoder(0x8000001A));
+        assert!(flag_is_set(&cpu, Flags::InterruptEnable));
+        assert_eq!(cpu.pc(), 0x00000004);
+
+        cpu.execution_stage(&cpu.decoder(0x8000001B));
+        assert!(!flag_is_set(&cpu, Flags::InterruptEnable));
+        assert_eq!(cpu.pc(), 0x00000008);
+    }
+
+    #[test]
+    fn test_interrupts_are_masked() {
+        let mut cpu = Cpu::new();
+        cpu.set_register(Register::A15, 0x00100000);
+        cpu.load_words(0x00000000, &[0x8000001B]).unwrap();
+        cpu.raise_interrupt(2);
+
+        let step = cpu.step();
+
+        assert_eq!(step.interrupt, None);
+        assert_eq!(step.address, 0x00000000);
+        assert!(cpu.interrupt_controller().is_pending(2));
+        assert_eq!(cpu.register(Register::A15), 0x00100000);
+    }
+
+    #[test]
+    fn test_invalid_interrupt_lines_are_ignored() {
+        let mut cpu = Cpu::new();
+        cpu.raise_interrupt(IRQ_LINES);
+        cpu.raise_interrupt(0xFF);
+
+        assert_eq!(cpu.interrupt_controller().highest_pending(), None);
+    }
+
+    #[test]
+    fn test_interrupt_dispatch_and_rti() {
+        let mut cpu = Cpu::new();
+        cpu.set_register(Register::A15, 0x00100000);
+        cpu.load_words(0x00002040 + 3 * 4, &[0x00004000]).unwrap();
+        cpu.load_words(0x00002040 + 5 * 4, &[0x00005000]).unwrap();
+
+        //NOTE: ei; rti
+        cpu.load_words(0x00000000, &[0x8000001A]).unwrap();
+        cpu.load_words(0x00000004, &[0x80000019]).unwrap();
+        //NOTE: The handler only returns
+        cpu.load_words(0x00004000, &[0x80000019]).unwrap();
+
+        cpu.step();
+        cpu.status_register.raise(Flags::Carry);
+        let saved_flags = cpu.status().status_bits();
+
+        cpu.raise_interrupt(5);
+        cpu.raise_interrupt(3);
+
+        let step = cpu.step();
+        assert_eq!(step.interrupt, Some(3));
+        assert_eq!(step.address, 0x00004000);
+        assert_eq!(step.opcode, Opcode::Rti(RtiOpcode));
+        assert_eq!(cpu.bus().read_dword(0x000FFFFC).unwrap(), 0x00000004);
+        assert_eq!(
+            cpu.bus().read_dword(0x000FFFF8).unwrap(),
+            saved_flags as u32
+        );
+
+        //NOTE: RTI already ran, we are back with the saved flags and interrupts enabled again
+        assert_eq!(cpu.pc(), 0x00000004);
+        assert_eq!(cpu.status().status_bits(), saved_flags);
+        assert_eq!(cpu.register(Register::A15), 0x00100000);
+        assert!(!cpu.interrupt_controller().is_pending(3));
+        assert!(cpu.interrupt_controller().is_pending(5));
+
+        let step = cpu.step();
+        assert_eq!(step.interrupt, Some(5));
+        assert_eq!(step.address, 0x00005000);
+        assert!(flag_is_set(&cpu, Flags::Interrupt));
+        assert!(!flag_is_set(&cpu, Flags::InterruptEnable));
+    }
+
+    #[test]
+    fn test_exceptions_disable_interrupts() {
+        let mut cpu = Cpu::new();
+        cpu.set_register(Register::A15, 0x00100000);
+        cpu.status_register.raise(Flags::InterruptEnable);
+        cpu.load_words(0x00000000, &[0x000000FF]).unwrap();
+        cpu.load_words(0x00002008, &[0x00004000]).unwrap();
+
+        cpu.step();
+
+        assert_eq!(cpu.pc(), 0x00004000);
+        assert!(!flag_is_set(&cpu, Flags::InterruptEnable));
+        assert!(
+            cpu.bus().read_dword(0x000FFFF8).unwrap() & (1 << Flags::InterruptEnable as u32) != 0
+        );
+    }
+
+    #[test]
+    fn test_instruction_cycles() {
+        let cases = [
+            (
+                generate_opcode(
+                    0x01,
+                    AddressingMode::Atomic,
+                    Register::D0,
+                    Some(Register::D1),
+                    0,
+                    OpcodeSize::Dword,
+                ),
+                1,
+            ),
+            (
+                generate_opcode(
+                    0x01,
+                    AddressingMode::Immediate,
+                    Register::D0,
+                    None,
+                    0,
+                    OpcodeSize::Dword,
+                ),
+                2,
+            ),
+            (
+                generate_opcode(
+                    0x01,
+                    AddressingMode::MemorySrc,
+                    Register::D0,
+                    Some(Register::A1),
+                    0,
+                    OpcodeSize::Word,
+                ),
+                2,
+            ),
+            (
+                generate_opcode(
+                    0x05,
+                    AddressingMode::MemoryDestInc,
+                    Register::A0,
+                    Some(Register::D1),
+                    0,
+                    OpcodeSize::Dword,
+                ),
+                5,
+            ),
+            (
+                generate_opcode(
+                    0x0A,
+                    AddressingMode::MemoryDest,
+                    Register::A0,
+                    Some(Register::D1),
+                    0,
+                    OpcodeSize::Byte,
+                ),
+                2,
+            ),
+            (
+                generate_opcode(
+                    0x03,
+                    AddressingMode::MemoryDest,
+                    Register::D0,
+                    Some(Register::A1),
+                    0,
+                    OpcodeSize::Dword,
+                ),
+                5,
+            ),
+            (
+                generate_opcode(
+                    0x04,
+                    AddressingMode::Atomic,
+                    Register::D0,
+                    None,
+                    0,
+                    OpcodeSize::Byte,
+                ),
+                2,
+            ),
+            (0x80000116, 4),
+            (0x80000017, 3),
+            (0x80000019, 5),
+            (0x8000001A, 1),
+        ];
+
+        let cpu = Cpu::new();
+        for (pattern, cycles) in cases {
+            let opcode = cpu.decoder(pattern);
+            assert_eq!(opcode.cycles(false), cycles, "{:?}", opcode);
+        }
+
+        let branch = cpu.decoder(generate_branch(0x15, Condition::Equal));
+        assert_eq!(branch.cycles(false), 2);
+        assert_eq!(branch.cycles(true), 3);
+
+        let subroutine = cpu.decoder(generate_branch(0x18, Condition::Always));
+        assert_eq!(subroutine.cycles(true), 5);
+    }
+
+    #[test]
+    fn test_cycle_counter() {
+        let mut cpu = Cpu::new();
+        cpu.set_register(Register::A15, 0x00100000);
+        cpu.load_words(0x00000000, &[0x8000001A]).unwrap();
+        cpu.load_words(0x00000004, &[generate_branch(0x15, Condition::Equal)])
+            .unwrap();
+        cpu.load_words(0x00000008, &[0x00000010]).unwrap();
+        cpu.load_words(0x0000000C, &[0x000000FF]).unwrap();
+        cpu.load_words(0x00002008, &[0x00000000]).unwrap();
+
+        assert_eq!(cpu.step().cycles, 1);
+        assert_eq!(cpu.step().cycles, 2);
+
+        let step = cpu.step();
+        assert_eq!(step.exception, Some(Exception::IllegalInstruction));
+        assert_eq!(step.cycles, 1 + EXCEPTION_CYCLES);
+
+        cpu.raise_interrupt(0);
+        cpu.load_words(0x00002040, &[0x00000004]).unwrap();
+        cpu.status_register.raise(Flags::InterruptEnable);
+        cpu.status_register.raise(Flags::Zero);
+
+        let step = cpu.step();
+        assert_eq!(step.interrupt, Some(0));
+        assert_eq!(step.cycles, EXCEPTION_CYCLES + 3);
+
+        assert_eq!(
+            cpu.cycles(),
+            1 + 2 + 1 + EXCEPTION_CYCLES as u64 + EXCEPTION_CYCLES as u64 + 3
+        );
+    }
+
+    #[test]
+    fn test_run_for_cycles() {
+        //NOTE: An endless loop of a move (1 cycle) and a taken jump (2 cycles)
+        let mut cpu = Cpu::new();
+        cpu.load_words(
+            0x00000000,
+            &[generate_opcode(
+                0x01,
+                AddressingMode::Atomic,
+                Register::D0,
+                Some(Register::D1),
+                0,
+                OpcodeSize::Dword,
+            )],
+        )
+        .unwrap();
+        cpu.load_words(0x00000004, &[0x80000114]).unwrap();
+        cpu.load_words(0x00000008, &[0x00000000]).unwrap();
+
+        assert_eq!(cpu.run_for_cycles(9), 9);
+        assert_eq!(cpu.pc(), 0x00000000);
+
+        assert_eq!(cpu.run_for_cycles(2), 3);
+        assert_eq!(cpu.pc(), 0x00000000);
+        assert_eq!(cpu.cycles(), 12);
+    }
+
+    #[test]
+    fn test_opcodes_talk_to_mapped_devices() {
+        //NOTE: A single byte register that counts how often it was read
+        struct CounterDevice {
+            value: u8,
+        }
+
+        impl Device for CounterDevice {
+            fn read_byte(&mut self, _offset: u32) -> Result<u8, BusError> {
+                self.value = self.value.wrapping_add(1);
+                Ok(self.value)
+            }
+
+            fn write_byte(&mut self, _offset: u32, value: u8) -> Result<(), BusError> {
+                self.value = value;
+                Ok(())
+            }
+        }
+
+        let mut cpu = Cpu::new();
+        cpu.map_device(0x00090000, 0x00090001, Box::new(CounterDevice { value: 0 }))
+            .unwrap();
+        cpu.set_register(Register::A0, 0x00090000);
+        cpu.set_register(Register::D1, 0x00000041);
+
+        let store = generate_opcode(
+            0x01,
+            AddressingMode::MemoryDest,
+            Register::A0,
+            Some(Register::D1),
+            0,
+            OpcodeSize::Byte,
+        );
+        cpu.execution_stage(&cpu.decoder(store));
+
+        let load = generate_opcode(
+            0x01,
+            AddressingMode::MemorySrc,
+            Register::D2,
+            Some(Register::A0),
+            0,
+            OpcodeSize::Byte,
+        );
+        cpu.execution_stage(&cpu.decoder(load));
+        assert_eq!(cpu.register(Register::D2), 0x00000042);
+
+        cpu.execution_stage(&cpu.decoder(load));
+        assert_eq!(cpu.register(Register::D2), 0x00000043);
+
+        //NOTE: The neighbouring byte is still plain ram
+        assert_eq!(cpu.bus().read_byte(0x00090001).unwrap(), 0x00);
+    }
+
+    #[test]
+    fn test_device_raised_interrupt() {
+        //NOTE: Raises IRQ 1 once after the given amount of cycles
+        struct TimerDevice {
+            remaining: u32,
+        }
+
+        impl Device for TimerDevice {
+            fn read_byte(&mut self, _offset: u32) -> Result<u8, BusError> {
+                Ok(0x00)
+            }
+
+            fn write_byte(&mut self, _offset: u32, _value: u8) -> Result<(), BusError> {
+                Ok(())
+            }
+
+            fn tick(&mut self, cycles: u32) -> u16 {
+                if self.remaining == 0 {
+                    return 0x00;
+                }
+                self.remaining = self.remaining.saturating_sub(cycles);
+                if self.remaining == 0 {
+                    1 << 1
+                } else {
+                    0x00
+                }
+            }
+        }
+
+        let mut cpu = Cpu::new();
+        cpu.map_device(
+            0x00090000,
+            0x00090001,
+            Box::new(TimerDevice { remaining: 3 }),
+        )
+        .unwrap();
+        cpu.set_register(Register::A15, 0x00100000);
+        cpu.load_words(0x00002040 + 4, &[0x00004000]).unwrap();
+        //NOTE: ei; add.d #1, d0 forever
+        cpu.load_words(0x00000000, &[0x8000001A]).unwrap();
+        cpu.load_words(0x00000004, &[0x80000105, 0x1, 0x80000114, 0x4])
+            .unwrap();
+
+        let step = cpu.step();
+        assert_eq!(step.interrupt, None);
+        assert!(!cpu.interrupt_controller().is_pending(1));
+
+        let step = cpu.step();
+        assert_eq!(step.interrupt, None);
+        assert!(cpu.interrupt_controller().is_pending(1));
+
+        let step = cpu.step();
+        assert_eq!(step.interrupt, Some(1));
+        assert_eq!(step.address, 0x00004000);
+        assert_eq!(cpu.bus().read_dword(0x000FFFFC).unwrap(), 0x0000000C);
+        assert!(!cpu.interrupt_controller().is_pending(1));
+    }
+
+    #[test]
+    fn test_bus_error_exception() {
+        let mut cpu = Cpu::new();
+        cpu.set_pc(0x00001000);
+        cpu.set_register(Register::A15, 0x00100000);
+        cpu.set_register(Register::A0, 0x10000000);
+        cpu.load_words(VECTOR_TABLE_START + 4, &[0x00004000])
+            .unwrap();
+        let load = generate_opcode(
+            0x01,
+            AddressingMode::MemorySrc,
+            Register::D0,
+            Some(Register::A0),
+            0,
+            OpcodeSize::Dword,
+        );
+        cpu.load_words(0x00001000, &[load]).unwrap();
+
+        let step = cpu.step();
+
+        assert_eq!(
+            step.exception,
+            Some(Exception::BusError(BusError::OutOfBounds(0x10000000)))
+        );
+        assert_eq!(cpu.pc(), 0x00004000);
+        assert_eq!(cpu.bus().read_dword(0x000FFFFC).unwrap(), 0x00001000);
+        assert_eq!(cpu.register(Register::D0), 0x00000000);
+    }
+
+    #[test]
+    fn test_address_register_updates_wrap() {
+        let mut cpu = Cpu::new();
+        cpu.set_register(Register::A15, 0x00100000);
+        cpu.load_words(VECTOR_TABLE_START + 4, &[0x00004000])
+            .unwrap();
+        cpu.set_register(Register::D0, 0x000000AB);
+
+        //NOTE: Address 0 is fine, only the register wraps around
+        let decrement = cpu.decoder(generate_opcode(
+            0x01,
+            AddressingMode::MemoryDestDec,
+            Register::A0,
+            Some(Register::D0),
+            0,
+            OpcodeSize::Byte,
+        ));
+        cpu.set_register(Register::A0, 0x00000000);
+        assert_eq!(cpu.execution_stage(&decrement), None);
+        assert_eq!(cpu.register(Register::A0), 0xFFFFFFFF);
+        assert_eq!(cpu.bus().read_byte(0x00000000).unwrap(), 0xAB);
+
+        //NOTE: Nothing answers at the top of the address space, the access faults instead of overflowing
+        let increment = cpu.decoder(generate_opcode(
+            0x01,
+            AddressingMode::MemoryDestInc,
+            Register::A0,
+            Some(Register::D0),
+            0,
+            OpcodeSize::Byte,
+        ));
+        assert_eq!(
+            cpu.execution_stage(&increment),
+            Some(Exception::BusError(BusError::OutOfBounds(0xFFFFFFFF)))
+        );
+        assert_eq!(cpu.register(Register::A0), 0xFFFFFFFF);
+
+        let add = cpu.decoder(generate_opcode(
+            0x05,
+            AddressingMode::MemorySrcInc,
+            Register::D0,
+            Some(Register::A0),
+            0,
+            OpcodeSize::Byte,
+        ));
+        assert_eq!(
+            cpu.execution_stage(&add),
+            Some(Exception::BusError(BusError::OutOfBounds(0xFFFFFFFF)))
+        );
+        assert_eq!(cpu.register(Register::A0), 0xFFFFFFFF);
+    }
+
+    #[test]
+    fn test_faulting_stack_accesses_keep_registers() {
+        let mut cpu = Cpu::new();
+        cpu.load_words(VECTOR_TABLE_START + 4, &[0x00004000])
+            .unwrap();
+
+        //NOTE: The popped byte can't be stored, only the exception frame moves the stack pointer
+        let pop = cpu.decoder(generate_opcode(
+            0x04,
+            AddressingMode::MemoryDestInc,
+            Register::A0,
+            None,
+            0,
+            OpcodeSize::Byte,
+        ));
+        cpu.set_register(Register::A15, 0x00100000);
+        cpu.set_register(Register::A0, 0xFFFFFFFF);
+        assert_eq!(
+            cpu.execution_stage(&pop),
+            Some(Exception::BusError(BusError::OutOfBounds(0xFFFFFFFF)))
+        );
+        assert_eq!(cpu.register(Register::A15), 0x00100000 - 8);
+        assert_eq!(cpu.register(Register::A0), 0xFFFFFFFF);
+
+        //NOTE: The second dword of the frame is out of bounds, the status register stays as it was
+        let rti = cpu.decoder(generate_opcode(
+            0x19,
+            AddressingMode::Atomic,
+            Register::D0,
+            None,
+            0,
+            OpcodeSize::Dword,
+        ));
+        cpu.set_register(Register::A15, MEMORY_SIZE as u32 - 4);
+        cpu.status_register.raise(Flags::Carry);
+        assert_eq!(
+            cpu.execution_stage(&rti),
+            Some(Exception::BusError(BusError::OutOfBounds(
+                MEMORY_SIZE as u32
+            )))
+        );
+        assert_eq!(cpu.register(Register::A15), MEMORY_SIZE as u32 - 4 - 8);
+        assert!(cpu.status_register.is_set(Flags::Carry));
+
+        //NOTE: The stack is full, the source register isn't advanced and the double fault halts the cpu
+        let push = cpu.decoder(generate_opcode(
+            0x03,
+            AddressingMode::MemoryDestInc,
+            Register::D0,
+            Some(Register::A0),
+            0,
+            OpcodeSize::Byte,
+        ));
+        cpu.set_register(Register::A15, 0x00000000);
+        cpu.set_register(Register::A0, 0x00001000);
+        assert_eq!(
+            cpu.execution_stage(&push),
+            Some(Exception::BusError(BusError::OutOfBounds(0xFFFFFFFF)))
+        );
+        assert_eq!(cpu.register(Register::A15), 0x00000000);
+        assert_eq!(cpu.register(Register::A0), 0x00001000);
+    }
+
+    #[test]
+    fn test_misaligned_accesses() {
+        let mut cpu = Cpu::new();
+        cpu.set_register(Register::A15, 0x00100000);
+        cpu.set_register(Register::A0, 0x00001001);
+        cpu.load_words(VECTOR_TABLE_START + 4, &[0x00004000])
+            .unwrap();
+        let load = cpu.decoder(generate_opcode(
+            0x01,
+            AddressingMode::MemorySrc,
+            Register::D0,
+            Some(Register::A0),
+            0,
+            OpcodeSize::Word,
+        ));
+
+        assert_eq!(cpu.execution_stage(&load), None);
+
+        cpu.bus().set_access_mode(AccessMode::Strict);
+        cpu.set_pc(0x00000000);
+        assert_eq!(
+            cpu.execution_stage(&load),
+            Some(Exception::BusError(BusError::Misaligned(0x00001001)))
+        );
+        assert_eq!(cpu.pc(), 0x00004000);
+
+        //NOTE: Instruction fetches have to be aligned as well
+        cpu.set_pc(0x00004002);
+        let step = cpu.step();
+        assert_eq!(step.opcode, Opcode::Unknown);
+        assert_eq!(
+            step.exception,
+            Some(Exception::BusError(BusError::Misaligned(0x00004002)))
+        );
+    }
+
+    #[test]
+    fn test_double_fault_halts() {
+        let mut cpu = Cpu::new();
+        cpu.set_pc(0x00001000);
+        //NOTE: The exception frame would be pushed below address 0
+        cpu.set_register(Register::A15, 0x00000000);
+        cpu.load_words(0x00001000, &[0x000000FF]).unwrap();
+
+        let step = cpu.step();
+        assert_eq!(step.exception, Some(Exception::IllegalInstruction));
+        assert!(cpu.is_halted());
+        assert_eq!(cpu.register(Register::A15), 0x00000000);
+
+        let step = cpu.step();
+        assert_eq!(step.address, 0x00001000);
+        assert_eq!(step.cycles, 0);
+        assert_eq!(cpu.run_until(|_, _| false), 1);
+        assert_eq!(cpu.run_for_cycles(100), 0);
+    }
+
+    fn bios_image(program: &[u32]) -> Vec<u8> {
+        program
+            .iter()
+            .flat_map(|dword| dword.to_be_bytes())
+            .collect()
+    }
+
+    #[test]
+    fn test_start_at_bios_reset_vector() {
+        let add = generate_opcode(
+            0x05,
+            AddressingMode::Immediate,
+            Register::D0,
+            None,
+            0,
+            OpcodeSize::Dword,
+        );
+        let bios =
+            Bios::from_bytes(bios_image(&[0x00000008, 0x00000000, add, 0x00000010])).unwrap();
+
+        let mut cpu = Cpu::with_bios(bios);
+        assert_eq!(cpu.pc(), 0x00000008);
+
+        cpu.step();
+        assert_eq!(cpu.register(Register::D0), 0x00000010);
+
+        cpu.set_pc(0x00000100);
+        cpu.reset();
+        assert_eq!(cpu.pc(), 0x00000008);
+        assert_eq!(cpu.register(Register::D0), 0x00000000);
+    }
+
+    #[test]
+    fn test_bios_is_read_only() {
+        let store = generate_opcode(
+            0x01,
+            AddressingMode::MemoryDest,
+            Register::A0,
+            Some(Register::D0),
+            0,
+            OpcodeSize::Dword,
+        );
+        let bios = Bios::from_bytes(bios_image(&[0x00000004, store])).unwrap();
+        let mut cpu = Cpu::with_bios(bios);
+        cpu.set_register(Register::A15, 0x00100000);
+        cpu.set_register(Register::D0, 0xFFFFFFFF);
+        cpu.load_words(VECTOR_TABLE_START + 4, &[0x00004000])
+            .unwrap();
+
+        let step = cpu.step();
+
+        assert_eq!(
+            step.exception,
+            Some(Exception::BusError(BusError::ReadOnly(0x00000000)))
+        );
+        assert_eq!(cpu.pc(), 0x00004000);
+        assert_eq!(cpu.bus().read_dword(0x00000000).unwrap(), 0x00000004);
+        //NOTE: Everything past the bios region is still ram
+        cpu.load_words(BIOS_END, &[0xDEADBEEF]).unwrap();
+        assert_eq!(cpu.bus().read_dword(BIOS_END).unwrap(), 0xDEADBEEF);
+    }
+
+    #[test]
+    fn test_bios_file() {
+        let path =
+            std::env::temp_dir().join(format!("dielectric_cpu_bios_{}.bin", std::process::id()));
+        std::fs::write(&path, bios_image(&[0x00000040])).unwrap();
+
+        let cpu = Cpu::with_bios_file(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(cpu.unwrap().pc, 0x00000040);
+        assert!(matches!(Cpu::with_bios_file(&path), Err(BiosError::Io(_))));
+    }
+
+    #[test]
+    fn test_program_loading() {
+        let mut cpu = Cpu::new();
+        cpu.load_words(0x00001000, &[0xDEADBEEF, 0x00C0FFEE])
+            .unwrap();
+        cpu.load_bytes(0x00001008, &[0x12, 0x34]).unwrap();
+
+        assert_eq!(cpu.bus().read_dword(0x00001004).unwrap(), 0x00C0FFEE);
+        assert_eq!(cpu.bus().read_word(0x00001008).unwrap(), 0x1234);
+
+        let mut cpu = Cpu::with_bios(Bios::from_bytes(vec![0x00; 4]).unwrap());
+        assert_eq!(
+            cpu.load_words(BIOS_END - 4, &[0x00000000]),
+            Err(BusError::ReadOnly(BIOS_END - 4))
+        );
+        assert_eq!(
+            cpu.load_bytes(0xFFFFFFFF, &[0x00, 0x00]),
+            Err(BusError::OutOfBounds(0xFFFFFFFF))
+        );
+    }
+
+    //NOTE: add.dw D0, #1 / move.dw (A0), D0 / jmp #0
+    fn counting_store_loop() -> Cpu {
+        let store = generate_opcode(
+            0x01,
+            AddressingMode::MemoryDest,
+            Register::A0,
+            Some(Register::D0),
+            0,
+            OpcodeSize::Dword,
+        );
+        let mut cpu = Cpu::new();
+        cpu.load_words(
+            0x00000000,
+            &[0x80000105, 0x00000001, store, 0x80000114, 0x00000000],
+        )
+        .unwrap();
+        cpu.set_register(Register::A0, 0x00001000);
+        cpu
+    }
+
+    #[test]
+    fn test_breakpoints() {
+        let mut cpu = counting_store_loop();
+        assert!(cpu.add_breakpoint(0x00000008));
+        assert!(!cpu.add_breakpoint(0x00000008));
+
+        let executed = cpu.run_until(|_, _| false);
+        assert_eq!(executed, 1);
+        assert_eq!(cpu.pc(), 0x00000008);
+        assert_eq!(cpu.debug_event(), Some(DebugEvent::Breakpoint(0x00000008)));
+
+        //NOTE: Continuing from the breakpoint executes the instruction at it
+        let executed = cpu.run_until(|_, _| false);
+        assert_eq!(executed, 3);
+        assert_eq!(cpu.register(Register::D0), 2);
+
+        assert!(cpu.remove_breakpoint(0x00000008));
+        assert_eq!(cpu.breakpoints().count(), 0);
+        assert_eq!(cpu.run_until(|_, step| step.address == 0x0000000C), 2);
+        assert_eq!(cpu.debug_event(), None);
+    }
+
+    #[test]
+    fn test_watchpoints() {
+        let mut cpu = counting_store_loop();
+        cpu.add_watchpoint(0x00001000..0x00001004, WatchKind::Write);
+        //NOTE: Reading the program itself doesn't count as an access
+        cpu.add_watchpoint(0x00000000..0x00000010, WatchKind::Read);
+
+        let elapsed = cpu.run_for_cycles(1000);
+
+        assert_eq!(elapsed, cpu.cycles());
+        assert_eq!(cpu.pc(), 0x0000000C);
+        assert_eq!(
+            cpu.debug_event(),
+            Some(DebugEvent::Watchpoint(WatchpointHit {
+                address: 0x00001000,
+                access: crate::bus::Access::Write
+            }))
+        );
+
+        assert!(cpu.remove_watchpoints(0x00001000));
+        cpu.run_for_cycles(20);
+        assert_eq!(cpu.debug_event(), None);
     }
 }
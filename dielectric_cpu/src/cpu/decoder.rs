@@ -1,7 +1,13 @@
 use std::fmt::{Binary, Display, LowerHex};
 
 use super::{
-    opcode::{LeaOpcode, MoveOpcode, Opcode},
+    addressing_modes::AddressingMode,
+    condition::Condition,
+    opcode::{
+        ArithmeticOpcode, ArithmeticOperation, BranchOpcode, InterruptEnableOpcode, JumpOpcode,
+        JumpOperation, LeaOpcode, LogicOpcode, LogicOperation, MoveOpcode, Opcode, PopOpcode,
+        PushOpcode, RtiOpcode, RtsOpcode,
+    },
     opcode_size::OpcodeSize,
     register::Register,
 };
@@ -67,19 +73,152 @@ impl LowerHex for BitPattern {
 
 impl From<BitPattern> for Opcode {
     fn from(value: BitPattern) -> Self {
+        //NOTE: Every bit pattern that isn't part of the ISA decodes to Unknown which raises an illegal instruction
+        let Ok(addr_mode) = AddressingMode::try_from(value.addr_mode) else {
+            return Opcode::Unknown;
+        };
+
         match value.opcode {
+            //NOTE: NOP is only the all zeros word, see docs/ISA.txt
+            0x00 if value.pattern == 0x00000000 => Opcode::Nop,
             0x01 => Opcode::Move(MoveOpcode {
-                addr_mode: value.addr_mode.into(),
+                addr_mode,
                 destination: Register::new(value.dest_reg),
                 source: Register::new(value.src_reg),
                 offset: value.offset,
                 size: OpcodeSize::new(value.size),
             }),
-            0x02 => Opcode::Lea(LeaOpcode {
-                destination: Register::new(value.dest_reg),
-            }),
+            //NOTE: LEA can only load an address into the registers A0 to A15
+            0x02 => match Register::new(value.dest_reg) {
+                destination if destination >= Register::A0 => {
+                    Opcode::Lea(LeaOpcode { destination })
+                }
+                _ => Opcode::Unknown,
+            },
+            0x03 => match addr_mode {
+                addressing_mode @ (AddressingMode::Atomic
+                | AddressingMode::Immediate
+                | AddressingMode::MemoryDest
+                | AddressingMode::MemoryDestInc
+                | AddressingMode::MemoryDestDec) => Opcode::Push(PushOpcode {
+                    addressing_mode,
+                    source: Register::new(value.src_reg),
+                    size: OpcodeSize::new(value.size),
+                }),
+                _ => Opcode::Unknown,
+            },
+            0x04 => match addr_mode {
+                addressing_mode @ (AddressingMode::Atomic
+                | AddressingMode::MemoryDest
+                | AddressingMode::MemoryDestInc
+                | AddressingMode::MemoryDestDec) => Opcode::Pop(PopOpcode {
+                    addressing_mode,
+                    destination: Register::new(value.dest_reg),
+                    size: OpcodeSize::new(value.size),
+                }),
+                _ => Opcode::Unknown,
+            },
+            0x05 => arithmetic_opcode(ArithmeticOperation::Add, addr_mode, value),
+            0x06 => arithmetic_opcode(ArithmeticOperation::Sub, addr_mode, value),
+            0x07 => arithmetic_opcode(ArithmeticOperation::Addc, addr_mode, value),
+            0x08 => arithmetic_opcode(ArithmeticOperation::Subc, addr_mode, value),
+            0x09 => match addr_mode {
+                //NOTE: NEG only has a single operand, it works on a register or on memory
+                AddressingMode::Atomic
+                | AddressingMode::MemoryDest
+                | AddressingMode::MemoryDestInc
+                | AddressingMode::MemoryDestDec => {
+                    arithmetic_opcode(ArithmeticOperation::Neg, addr_mode, value)
+                }
+                _ => Opcode::Unknown,
+            },
+            0x0A => arithmetic_opcode(ArithmeticOperation::Cmp, addr_mode, value),
+            0x0B => logic_opcode(LogicOperation::And, addr_mode, value),
+            0x0C => logic_opcode(LogicOperation::Or, addr_mode, value),
+            0x0D => logic_opcode(LogicOperation::Xor, addr_mode, value),
+            0x0E => match addr_mode {
+                //NOTE: NOT only has a single operand, it works on a register or on memory
+                AddressingMode::Atomic
+                | AddressingMode::MemoryDest
+                | AddressingMode::MemoryDestInc
+                | AddressingMode::MemoryDestDec => {
+                    logic_opcode(LogicOperation::Not, addr_mode, value)
+                }
+                _ => Opcode::Unknown,
+            },
+            0x0F => shift_opcode(LogicOperation::Lsl, addr_mode, value),
+            0x10 => shift_opcode(LogicOperation::Lsr, addr_mode, value),
+            0x11 => shift_opcode(LogicOperation::Asr, addr_mode, value),
+            0x12 => shift_opcode(LogicOperation::Rol, addr_mode, value),
+            0x13 => shift_opcode(LogicOperation::Ror, addr_mode, value),
+            0x14 => jump_opcode(JumpOperation::Jmp, addr_mode, value),
+            0x15 => branch_opcode(false, addr_mode, value),
+            0x16 => jump_opcode(JumpOperation::Jsr, addr_mode, value),
+            0x17 => Opcode::Rts(RtsOpcode),
+            0x18 => branch_opcode(true, addr_mode, value),
+            0x19 => Opcode::Rti(RtiOpcode),
+            0x1A => Opcode::InterruptEnable(InterruptEnableOpcode { enable: true }),
+            0x1B => Opcode::InterruptEnable(InterruptEnableOpcode { enable: false }),
 
             _ => Opcode::Unknown,
         }
     }
 }
+
+fn arithmetic_opcode(
+    operation: ArithmeticOperation,
+    addr_mode: AddressingMode,
+    value: BitPattern,
+) -> Opcode {
+    Opcode::Arithmetic(ArithmeticOpcode {
+        operation,
+        addr_mode,
+        destination: Register::new(value.dest_reg),
+        source: Register::new(value.src_reg),
+        size: OpcodeSize::new(value.size),
+    })
+}
+
+fn logic_opcode(operation: LogicOperation, addr_mode: AddressingMode, value: BitPattern) -> Opcode {
+    Opcode::Logic(LogicOpcode {
+        operation,
+        addr_mode,
+        destination: Register::new(value.dest_reg),
+        source: Register::new(value.src_reg),
+        offset: value.offset,
+        size: OpcodeSize::new(value.size),
+    })
+}
+
+//NOTE: Shifts and rotates only exist with the count in a register (Atomic) or in the offset field (Immediate)
+fn shift_opcode(operation: LogicOperation, addr_mode: AddressingMode, value: BitPattern) -> Opcode {
+    match addr_mode {
+        AddressingMode::Atomic | AddressingMode::Immediate => {
+            logic_opcode(operation, addr_mode, value)
+        }
+        _ => Opcode::Unknown,
+    }
+}
+
+//NOTE: Jumps either go to the immediate dword following the opcode or to the address inside the destination register
+fn jump_opcode(operation: JumpOperation, addr_mode: AddressingMode, value: BitPattern) -> Opcode {
+    match addr_mode {
+        AddressingMode::Atomic | AddressingMode::Immediate => Opcode::Jump(JumpOpcode {
+            operation,
+            addr_mode,
+            destination: Register::new(value.dest_reg),
+        }),
+        _ => Opcode::Unknown,
+    }
+}
+
+//NOTE: Branches always carry their displacement in the dword after the opcode and the condition in the destination field
+fn branch_opcode(subroutine: bool, addr_mode: AddressingMode, value: BitPattern) -> Opcode {
+    match (addr_mode, Condition::try_from(value.dest_reg & 0x1F)) {
+        (AddressingMode::Immediate, Ok(condition)) => Opcode::Branch(BranchOpcode {
+            condition,
+            subroutine,
+        }),
+        _ => Opcode::Unknown,
+    }
+}
@@ -0,0 +1,464 @@
+use std::fmt::Display;
+
+use super::{
+    addressing_modes::AddressingMode,
+    condition::Condition,
+    decoder::BitPattern,
+    opcode::{
+        ArithmeticOpcode, ArithmeticOperation, BranchOpcode, JumpOpcode, LogicOpcode,
+        LogicOperation, MoveOpcode, Opcode, PopOpcode, PushOpcode,
+    },
+    opcode_size::OpcodeSize,
+    register::Register,
+};
+use crate::bus::{Bus, BusError};
+
+//NOTE: Renders an opcode in the syntax of the assembler, the destination comes first like in docs/ISA.txt. Values
+//      taken from the extension word are printed as $hex, shift counts as #decimal and branch displacements as the
+//      absolute target address so the output can be fed back into the assembler.
+pub struct Disassembly<'a> {
+    opcode: &'a Opcode,
+    address: u32,
+    extension_word: Option<u32>,
+}
+
+impl Opcode {
+    //NOTE: The address is needed to resolve the branch displacements
+    pub fn disassemble(&self, address: u32, extension_word: Option<u32>) -> Disassembly<'_> {
+        Disassembly {
+            opcode: self,
+            address,
+            extension_word,
+        }
+    }
+}
+
+//NOTE: Without the extension word immediates show up as the placeholders used in docs/ISA.txt
+impl Display for Opcode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.disassemble(0x00000000, None))
+    }
+}
+
+impl Disassembly<'_> {
+    fn immediate(&self, size: &OpcodeSize) -> String {
+        match (self.extension_word, size) {
+            (Some(value), _) => format!("${:08X}", value),
+            (None, OpcodeSize::Byte) => "#Imm8".to_string(),
+            (None, OpcodeSize::Word) => "#Imm16".to_string(),
+            (None, OpcodeSize::Dword) => "#Imm32".to_string(),
+        }
+    }
+
+    fn address(&self) -> String {
+        match self.extension_word {
+            Some(address) => format!("${:08X}", address),
+            None => "#Address".to_string(),
+        }
+    }
+
+    fn branch_target(&self) -> String {
+        match self.extension_word {
+            Some(displacement) => format!("${:08X}", self.address.wrapping_add(displacement)),
+            None => "#Displacement".to_string(),
+        }
+    }
+
+    //NOTE: Operands of the two operand instructions, memory destinations are addressed by the destination register
+    //      and memory sources by the source register
+    fn operands(
+        &self,
+        addr_mode: &AddressingMode,
+        destination: &Register,
+        source: &Register,
+        size: &OpcodeSize,
+    ) -> String {
+        match addr_mode {
+            AddressingMode::Atomic => format!("{},{}", destination, source),
+            AddressingMode::Immediate => format!("{},{}", destination, self.immediate(size)),
+            AddressingMode::MemoryDest
+            | AddressingMode::MemoryDestInc
+            | AddressingMode::MemoryDestDec => {
+                format!("{},{}", memory_operand(addr_mode, destination), source)
+            }
+            AddressingMode::MemorySrc
+            | AddressingMode::MemorySrcInc
+            | AddressingMode::MemorySrcDec => {
+                format!("{},{}", destination, memory_operand(addr_mode, source))
+            }
+        }
+    }
+
+    //NOTE: Operand of the single operand instructions, the register itself or the memory it points to
+    fn operand(
+        &self,
+        addr_mode: &AddressingMode,
+        register: &Register,
+        size: &OpcodeSize,
+    ) -> String {
+        match addr_mode {
+            AddressingMode::Atomic => register.to_string(),
+            AddressingMode::Immediate => self.immediate(size),
+            _ => memory_operand(addr_mode, register),
+        }
+    }
+}
+
+fn memory_operand(addr_mode: &AddressingMode, register: &Register) -> String {
+    match addr_mode {
+        AddressingMode::MemoryDestInc | AddressingMode::MemorySrcInc => format!("({})+", register),
+        AddressingMode::MemoryDestDec | AddressingMode::MemorySrcDec => format!("({})-", register),
+        _ => format!("({})", register),
+    }
+}
+
+impl Display for Disassembly<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.opcode {
+            Opcode::Move(MoveOpcode {
+                addr_mode,
+                destination,
+                source,
+                offset: _,
+                size,
+            }) => write!(
+                f,
+                "MOVE.{} {}",
+                size,
+                self.operands(addr_mode, destination, source, size)
+            ),
+            Opcode::Lea(data) => write!(f, "LEA.DW {},{}", data.destination, self.address()),
+            Opcode::Push(PushOpcode {
+                addressing_mode,
+                source,
+                size,
+            }) => write!(
+                f,
+                "PUSH.{} {}",
+                size,
+                self.operand(addressing_mode, source, size)
+            ),
+            Opcode::Pop(PopOpcode {
+                addressing_mode,
+                destination,
+                size,
+            }) => write!(
+                f,
+                "POP.{} {}",
+                size,
+                self.operand(addressing_mode, destination, size)
+            ),
+            Opcode::Arithmetic(ArithmeticOpcode {
+                operation: ArithmeticOperation::Neg,
+                addr_mode,
+                destination,
+                source: _,
+                size,
+            }) => write!(
+                f,
+                "NEG.{} {}",
+                size,
+                self.operand(addr_mode, destination, size)
+            ),
+            Opcode::Arithmetic(ArithmeticOpcode {
+                operation,
+                addr_mode,
+                destination,
+                source,
+                size,
+            }) => write!(
+                f,
+                "{}.{} {}",
+                operation,
+                size,
+                self.operands(addr_mode, destination, source, size)
+            ),
+            Opcode::Logic(LogicOpcode {
+                operation: LogicOperation::Not,
+                addr_mode,
+                destination,
+                source: _,
+                offset: _,
+                size,
+            }) => write!(
+                f,
+                "NOT.{} {}",
+                size,
+                self.operand(addr_mode, destination, size)
+            ),
+            //NOTE: The immediate shift count lives in the offset field and not in an extension word
+            Opcode::Logic(LogicOpcode {
+                operation,
+                addr_mode: AddressingMode::Immediate,
+                destination,
+                source: _,
+                offset,
+                size,
+            }) if operation.is_shift() => {
+                write!(f, "{}.{} {},#{}", operation, size, destination, offset)
+            }
+            Opcode::Logic(LogicOpcode {
+                operation,
+                addr_mode,
+                destination,
+                source,
+                offset: _,
+                size,
+            }) => write!(
+                f,
+                "{}.{} {}",
+                operation,
+                size,
+                self.operands(addr_mode, destination, source, size)
+            ),
+            Opcode::Jump(JumpOpcode {
+                operation,
+                addr_mode: AddressingMode::Immediate,
+                destination: _,
+            }) => write!(f, "{} {}", operation, self.address()),
+            Opcode::Jump(JumpOpcode {
+                operation,
+                addr_mode: _,
+                destination,
+            }) => write!(f, "{} {}", operation, destination),
+            //NOTE: Conditional subroutine calls get the condition appended like BSREQ, BSR alone always calls
+            Opcode::Branch(BranchOpcode {
+                condition: Condition::Always,
+                subroutine: true,
+            }) => write!(f, "BSR {}", self.branch_target()),
+            Opcode::Branch(BranchOpcode {
+                condition,
+                subroutine: true,
+            }) => write!(f, "BSR{} {}", condition, self.branch_target()),
+            Opcode::Branch(BranchOpcode {
+                condition,
+                subroutine: false,
+            }) => write!(f, "B{} {}", condition, self.branch_target()),
+            Opcode::Nop => write!(f, "NOP"),
+            Opcode::Rts(_) => write!(f, "RTS"),
+            Opcode::Rti(_) => write!(f, "RTI"),
+            Opcode::InterruptEnable(data) if data.enable => write!(f, "EI"),
+            Opcode::InterruptEnable(_) => write!(f, "DI"),
+            Opcode::Unknown => write!(f, "ILLEGAL"),
+        }
+    }
+}
+
+//NOTE: A decoded instruction together with the words it was decoded from
+#[derive(Debug, PartialEq, Eq)]
+pub struct Instruction {
+    pub address: u32,
+    pub word: u32,
+    pub extension_word: Option<u32>,
+    pub opcode: Opcode,
+}
+
+impl Instruction {
+    //NOTE: The extension word is only kept if the opcode actually has one
+    pub fn new(address: u32, word: u32, extension_word: Option<u32>) -> Self {
+        let opcode: Opcode = BitPattern::new(word).into();
+        let extension_word = if opcode.length() == 8 {
+            extension_word
+        } else {
+            None
+        };
+
+        Self {
+            address,
+            word,
+            extension_word,
+            opcode,
+        }
+    }
+
+    //NOTE: Only the opcode has to be readable, an unreadable extension word is shown as placeholder
+    pub fn read(bus: &mut dyn Bus, address: u32) -> Result<Self, BusError> {
+        let word = bus.read_dword(address)?;
+        let extension_word = address
+            .checked_add(4)
+            .and_then(|address| bus.read_dword(address).ok());
+
+        Ok(Self::new(address, word, extension_word))
+    }
+
+    //NOTE: Size in bytes the instruction occupies, an instruction cut off at the end of an image still advances by
+    //      its full length
+    pub fn length(&self) -> u32 {
+        self.opcode.length()
+    }
+}
+
+impl Display for Instruction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            self.opcode.disassemble(self.address, self.extension_word)
+        )
+    }
+}
+
+//NOTE: Disassembles a raw image of big endian words that is loaded at the given address
+pub fn disassemble(words: &[u32], address: u32) -> Vec<Instruction> {
+    let mut instructions = vec![];
+    let mut index = 0;
+
+    while let Some(&word) = words.get(index) {
+        let instruction = Instruction::new(
+            address.wrapping_add(index as u32 * 4),
+            word,
+            words.get(index + 1).copied(),
+        );
+        index += instruction.length() as usize / 4;
+        instructions.push(instruction);
+    }
+
+    instructions
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn text(words: &[u32]) -> String {
+        Instruction::new(0x00001000, words[0], words.get(1).copied()).to_string()
+    }
+
+    #[test]
+    fn test_moves() {
+        assert_eq!(text(&[0x80000001 | (0x11 << 19)]), "MOVE.DW D0,A1");
+        assert_eq!(text(&[0x80008101, 0xAABBCCDD]), "MOVE.DW D2,$AABBCCDD");
+        assert_eq!(text(&[0x00000201 | (0x11 << 14)]), "MOVE.B (A1),D0");
+        assert_eq!(
+            text(&[0x40000501 | (0x12 << 19) | (0x03 << 14)]),
+            "MOVE.W D3,(A2)+"
+        );
+        assert_eq!(text(&[0x80000401 | (0x11 << 14)]), "MOVE.DW (A1)+,D0");
+        assert_eq!(text(&[0x80000601 | (0x11 << 14)]), "MOVE.DW (A1)-,D0");
+        assert_eq!(
+            text(&[0x80000002 | (0x13 << 14), 0x00002000]),
+            "LEA.DW A3,$00002000"
+        );
+    }
+
+    #[test]
+    fn test_stack() {
+        assert_eq!(text(&[0x80000103, 0x12345678]), "PUSH.DW $12345678");
+        assert_eq!(text(&[0x40000003 | (0x04 << 19)]), "PUSH.W D4");
+        assert_eq!(text(&[0x00000403 | (0x10 << 19)]), "PUSH.B (A0)+");
+        assert_eq!(text(&[0x80000004 | (0x05 << 14)]), "POP.DW D5");
+        assert_eq!(text(&[0x80000604 | (0x1F << 14)]), "POP.DW (A15)-");
+    }
+
+    #[test]
+    fn test_arithmetic_and_logic() {
+        assert_eq!(text(&[0x80000105, 0x00000001]), "ADD.DW D0,$00000001");
+        assert_eq!(
+            text(&[0x00000006 | (0x01 << 19) | (0x02 << 14)]),
+            "SUB.B D2,D1"
+        );
+        assert_eq!(
+            text(&[0x4000020A | (0x10 << 14) | (0x01 << 19)]),
+            "CMP.W (A0),D1"
+        );
+        assert_eq!(text(&[0x80000209 | (0x10 << 14)]), "NEG.DW (A0)");
+        assert_eq!(text(&[0x8000000E | (0x07 << 14)]), "NOT.DW D7");
+        assert_eq!(
+            text(&[0x8000030B | (0x10 << 19) | (0x01 << 14)]),
+            "AND.DW D1,(A0)"
+        );
+        assert_eq!(
+            text(&[0x8000010F | (0x05 << 24) | (0x02 << 14)]),
+            "LSL.DW D2,#5"
+        );
+        assert_eq!(
+            text(&[0x80000013 | (0x03 << 19) | (0x02 << 14)]),
+            "ROR.DW D2,D3"
+        );
+    }
+
+    #[test]
+    fn test_control_flow() {
+        assert_eq!(text(&[0x80000114, 0x00002000]), "JMP $00002000");
+        assert_eq!(text(&[0x80000014 | (0x13 << 14)]), "JMP A3");
+        assert_eq!(text(&[0x80000116, 0x00000010]), "JSR $00000010");
+        assert_eq!(text(&[0x80008115, (-8i32) as u32]), "BNE $00000FF8");
+        assert_eq!(text(&[0x80000115, 0x00000010]), "BRA $00001010");
+        assert_eq!(text(&[0x80000118, 0x00000008]), "BSR $00001008");
+        assert_eq!(text(&[0x80004118, 0x00000008]), "BSREQ $00001008");
+        assert_eq!(text(&[0x80000017]), "RTS");
+        assert_eq!(text(&[0x80000019]), "RTI");
+        assert_eq!(text(&[0x8000001A]), "EI");
+        assert_eq!(text(&[0x8000001B]), "DI");
+        assert_eq!(text(&[0x00000000]), "NOP");
+        assert_eq!(text(&[0x000000FF]), "ILLEGAL");
+    }
+
+    #[test]
+    fn test_branches_round_trip_through_the_assembler() {
+        //NOTE: Every condition as branch and as subroutine call, jumping back and forth
+        let mut words = vec![];
+        for subroutine in [false, true] {
+            for condition in 0x00..=0x10u32 {
+                let opcode = if subroutine { 0x18 } else { 0x15 };
+                let displacement = if condition % 2 == 0 {
+                    0x40
+                } else {
+                    -0x18i32 as u32
+                };
+                words.extend([0x80000100 | (condition << 14) | opcode, displacement]);
+            }
+        }
+
+        //NOTE: The assembler places the program at address 0
+        let source: Vec<String> = disassemble(&words, 0x00000000)
+            .iter()
+            .map(|instruction| instruction.to_string())
+            .collect();
+        let mut assembler = assembler::Assembler::new();
+
+        assert_eq!(assembler.assemble(&source.join("\n")), words);
+    }
+
+    #[test]
+    fn test_placeholders_without_extension_word() {
+        let opcode: Opcode = BitPattern::new(0x80000101).into();
+        assert_eq!(opcode.to_string(), "MOVE.DW D0,#Imm32");
+
+        let opcode: Opcode = BitPattern::new(0x80008115).into();
+        assert_eq!(opcode.to_string(), "BNE #Displacement");
+    }
+
+    #[test]
+    fn test_disassemble_image() {
+        let instructions = disassemble(
+            &[
+                0x80000105, 0x00000001, 0x80000114, 0x00001000, 0x80000017, 0x80000105,
+            ],
+            0x1000,
+        );
+        let listing: Vec<(u32, String)> = instructions
+            .iter()
+            .map(|instruction| (instruction.address, instruction.to_string()))
+            .collect();
+
+        assert_eq!(
+            listing,
+            vec![
+                (0x00001000, "ADD.DW D0,$00000001".to_string()),
+                (0x00001008, "JMP $00001000".to_string()),
+                (0x00001010, "RTS".to_string()),
+                (0x00001014, "ADD.DW D0,#Imm32".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_assembler_output() {
+        //NOTE: Words produced by the assembler tests, see assembler/src/lib.rs
+        let instructions = disassemble(&[0x80008101, 0xAABBCCDD, 0x80008115, (-8i32) as u32], 0x0);
+
+        assert_eq!(instructions[0].to_string(), "MOVE.DW D2,$AABBCCDD");
+        assert_eq!(instructions[1].to_string(), "BNE $00000000");
+    }
+}
@@ -0,0 +1,69 @@
+use std::fmt::Display;
+
+use crate::bus::BusError;
+
+//NOTE: Start of the interrupt jump table, see docs/Memory.md. Every entry is the dword address of a handler.
+pub const VECTOR_TABLE_START: u32 = 0x00002000;
+pub const VECTOR_TABLE_END: u32 = 0x00002400;
+pub const INTERRUPT_VECTOR_START: u32 = 0x10;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Exception {
+    //NOTE: A memory access or instruction fetch faulted on the bus
+    BusError(BusError),
+    //NOTE: The opcode, addressing mode or operands of the instruction are not part of the ISA
+    IllegalInstruction,
+    //NOTE: A device raised the given IRQ line while interrupts were enabled
+    Interrupt(u8),
+}
+
+impl Exception {
+    pub fn vector(&self) -> u32 {
+        match self {
+            Exception::BusError(_) => 0x01,
+            Exception::IllegalInstruction => 0x02,
+            Exception::Interrupt(line) => INTERRUPT_VECTOR_START + *line as u32,
+        }
+    }
+
+    pub fn vector_address(&self) -> u32 {
+        VECTOR_TABLE_START + self.vector() * 4
+    }
+}
+
+impl From<BusError> for Exception {
+    fn from(error: BusError) -> Self {
+        Exception::BusError(error)
+    }
+}
+
+impl Display for Exception {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Exception::BusError(error) => write!(f, "bus error, {}", error),
+            Exception::IllegalInstruction => write!(f, "illegal instruction"),
+            Exception::Interrupt(line) => write!(f, "interrupt request {}", line),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::cpu::interrupt::IRQ_LINES;
+
+    #[test]
+    fn test_vector_addresses_are_inside_the_table() {
+        let address = Exception::IllegalInstruction.vector_address();
+
+        assert_eq!(address, 0x00002008);
+        assert!((VECTOR_TABLE_START..VECTOR_TABLE_END).contains(&address));
+
+        for line in 0..IRQ_LINES {
+            let address = Exception::Interrupt(line).vector_address();
+
+            assert_eq!(address, 0x00002040 + line as u32 * 4);
+            assert!((VECTOR_TABLE_START..VECTOR_TABLE_END).contains(&address));
+        }
+    }
+}
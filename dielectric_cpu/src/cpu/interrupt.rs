@@ -0,0 +1,96 @@
+pub const IRQ_LINES: u8 = 16;
+
+//NOTE: Devices raise one of the IRQ lines and the line stays pending until the cpu dispatches it. Line 0 has the
+//      highest priority, if several lines are pending the lowest one is handled first.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct InterruptController {
+    pending: u16,
+}
+
+impl InterruptController {
+    pub fn new() -> Self {
+        Self { pending: 0x00 }
+    }
+
+    pub fn raise(&mut self, line: u8) {
+        assert!(line < IRQ_LINES, "there are only {} IRQ lines", IRQ_LINES);
+        self.pending |= 1 << line;
+    }
+
+    pub fn clear(&mut self, line: u8) {
+        assert!(line < IRQ_LINES, "there are only {} IRQ lines", IRQ_LINES);
+        self.pending &= !(1 << line);
+    }
+
+    pub fn is_pending(&self, line: u8) -> bool {
+        line < IRQ_LINES && self.pending & (1 << line) != 0
+    }
+
+    pub fn highest_pending(&self) -> Option<u8> {
+        if self.pending == 0 {
+            None
+        } else {
+            Some(self.pending.trailing_zeros() as u8)
+        }
+    }
+
+    //NOTE: Raises every line whose bit is set, used for the lines devices raise while ticking
+    pub(crate) fn raise_lines(&mut self, lines: u16) {
+        self.pending |= lines;
+    }
+
+    //NOTE: One bit per pending line, used by snapshots
+    pub(crate) fn pending_lines(&self) -> u16 {
+        self.pending
+    }
+
+    pub(crate) fn with_pending_lines(pending: u16) -> Self {
+        Self { pending }
+    }
+
+    //NOTE: Called by the cpu once it dispatches the interrupt, the line is no longer pending afterwards
+    pub(crate) fn acknowledge(&mut self) -> Option<u8> {
+        let line = self.highest_pending()?;
+        self.clear(line);
+        Some(line)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_raise_and_clear() {
+        let mut controller = InterruptController::new();
+        assert_eq!(controller.highest_pending(), None);
+
+        controller.raise(3);
+        assert!(controller.is_pending(3));
+        assert!(!controller.is_pending(2));
+
+        controller.clear(3);
+        assert!(!controller.is_pending(3));
+        assert_eq!(controller.highest_pending(), None);
+    }
+
+    #[test]
+    fn test_priority() {
+        let mut controller = InterruptController::new();
+        controller.raise(9);
+        controller.raise(15);
+        controller.raise(4);
+
+        assert_eq!(controller.acknowledge(), Some(4));
+        assert_eq!(controller.acknowledge(), Some(9));
+        assert_eq!(controller.acknowledge(), Some(15));
+        assert_eq!(controller.acknowledge(), None);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_invalid_line() {
+        let mut controller = InterruptController::new();
+        controller.raise(IRQ_LINES);
+    }
+}
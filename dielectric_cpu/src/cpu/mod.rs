@@ -1,6 +1,11 @@
 pub mod addressing_modes;
+mod alu;
+pub mod condition;
 pub mod core;
 pub mod decoder;
+pub mod disassembler;
+pub mod exception;
+pub mod interrupt;
 pub mod opcode;
 pub mod opcode_size;
 pub mod register;
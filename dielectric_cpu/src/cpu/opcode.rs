@@ -1,34 +1,191 @@
+use std::fmt::Display;
+
 use super::{
     addressing_modes::AddressingMode,
+    alu,
+    condition::Condition,
+    exception::Exception,
     opcode_size::OpcodeSize,
     register::Register,
     status_register::{Flags, StatusRegister},
 };
-use crate::{Memory, RegisterFile};
+use crate::{
+    bus::{Bus, BusError},
+    RegisterFile,
+};
 
 #[derive(Debug, PartialEq, Eq)]
 pub enum Opcode {
+    Nop,
     Move(MoveOpcode),
     Lea(LeaOpcode),
     Push(PushOpcode),
     Pop(PopOpcode),
+    Arithmetic(ArithmeticOpcode),
+    Logic(LogicOpcode),
+    Jump(JumpOpcode),
+    Branch(BranchOpcode),
+    Rts(RtsOpcode),
+    Rti(RtiOpcode),
+    InterruptEnable(InterruptEnableOpcode),
     Unknown,
 }
 
+//NOTE: Timing model, every instruction takes a cycle to fetch and execute and another one for its extension word.
+//      Memory accesses cost OpcodeSize::access_cycles on top, pushing or popping the pc is a dword access.
+pub const BASE_CYCLES: u32 = 1;
+pub const EXTENSION_WORD_CYCLES: u32 = 1;
+pub const BRANCH_TAKEN_CYCLES: u32 = 1;
+//NOTE: Reading the vector and pushing the pc and the status register
+pub const EXCEPTION_CYCLES: u32 = 6;
+const STACK_DWORD_CYCLES: u32 = 2;
+
+fn is_memory_dest(addr_mode: &AddressingMode) -> bool {
+    matches!(
+        addr_mode,
+        AddressingMode::MemoryDest | AddressingMode::MemoryDestInc | AddressingMode::MemoryDestDec
+    )
+}
+
+fn is_memory_src(addr_mode: &AddressingMode) -> bool {
+    matches!(
+        addr_mode,
+        AddressingMode::MemorySrc | AddressingMode::MemorySrcInc | AddressingMode::MemorySrcDec
+    )
+}
+
+impl Opcode {
+    //NOTE: Size of the instruction in bytes, the opcode itself plus the immediate dword that follows it
+    pub fn length(&self) -> u32 {
+        let has_extension_word = match self {
+            Opcode::Nop => false,
+            Opcode::Move(data) => data.addr_mode == AddressingMode::Immediate,
+            Opcode::Lea(_) => true,
+            Opcode::Push(data) => data.addressing_mode == AddressingMode::Immediate,
+            Opcode::Pop(_) => false,
+            Opcode::Arithmetic(data) => data.addr_mode == AddressingMode::Immediate,
+            //NOTE: Shifts and rotates keep their immediate count inside the offset field
+            Opcode::Logic(data) => {
+                data.addr_mode == AddressingMode::Immediate && !data.operation.is_shift()
+            }
+            Opcode::Jump(data) => data.addr_mode == AddressingMode::Immediate,
+            Opcode::Branch(_) => true,
+            Opcode::Rts(_) => false,
+            Opcode::Rti(_) => false,
+            Opcode::InterruptEnable(_) => false,
+            Opcode::Unknown => false,
+        };
+
+        if has_extension_word {
+            8
+        } else {
+            4
+        }
+    }
+
+    //NOTE: Cycles the instruction takes, branch_taken is only relevant for Bcc and BSR
+    pub fn cycles(&self, branch_taken: bool) -> u32 {
+        let extension_word = if self.length() == 8 {
+            EXTENSION_WORD_CYCLES
+        } else {
+            0
+        };
+
+        let memory = match self {
+            Opcode::Move(data) => {
+                if is_memory_dest(&data.addr_mode) || is_memory_src(&data.addr_mode) {
+                    data.size.access_cycles()
+                } else {
+                    0
+                }
+            }
+            Opcode::Lea(_) => 0,
+            Opcode::Push(data) => {
+                let source = if is_memory_dest(&data.addressing_mode) {
+                    data.size.access_cycles()
+                } else {
+                    0
+                };
+                source + data.size.access_cycles()
+            }
+            Opcode::Pop(data) => {
+                let destination = if is_memory_dest(&data.addressing_mode) {
+                    data.size.access_cycles()
+                } else {
+                    0
+                };
+                data.size.access_cycles() + destination
+            }
+            //NOTE: Memory destinations are read and written back, CMP only reads them
+            Opcode::Arithmetic(data) => {
+                if is_memory_dest(&data.addr_mode) && data.operation != ArithmeticOperation::Cmp {
+                    2 * data.size.access_cycles()
+                } else if is_memory_dest(&data.addr_mode) || is_memory_src(&data.addr_mode) {
+                    data.size.access_cycles()
+                } else {
+                    0
+                }
+            }
+            Opcode::Logic(data) => {
+                if is_memory_dest(&data.addr_mode) {
+                    2 * data.size.access_cycles()
+                } else if is_memory_src(&data.addr_mode) {
+                    data.size.access_cycles()
+                } else {
+                    0
+                }
+            }
+            Opcode::Jump(data) => match data.operation {
+                JumpOperation::Jmp => 0,
+                JumpOperation::Jsr => STACK_DWORD_CYCLES,
+            },
+            Opcode::Branch(data) => match (branch_taken, data.subroutine) {
+                (false, _) => 0,
+                (true, false) => BRANCH_TAKEN_CYCLES,
+                (true, true) => BRANCH_TAKEN_CYCLES + STACK_DWORD_CYCLES,
+            },
+            Opcode::Rts(_) => STACK_DWORD_CYCLES,
+            Opcode::Rti(_) => 2 * STACK_DWORD_CYCLES,
+            Opcode::InterruptEnable(_) => 0,
+            Opcode::Nop | Opcode::Unknown => 0,
+        };
+
+        BASE_CYCLES + extension_word + memory
+    }
+
+    //NOTE: These opcodes set the pc themselves, for everything else the cpu advances it by the length
+    pub fn is_control_flow(&self) -> bool {
+        matches!(
+            self,
+            Opcode::Jump(_) | Opcode::Branch(_) | Opcode::Rts(_) | Opcode::Rti(_)
+        )
+    }
+}
+
 impl Execute for Opcode {
     fn execute(
         &self,
         pc: &mut u32,
         register_file: &mut RegisterFile,
         status_register: &mut StatusRegister,
-        memory: &mut Memory,
-    ) {
+        memory: &mut dyn Bus,
+    ) -> Result<(), Exception> {
         match self {
+            Opcode::Nop => Ok(()),
             Opcode::Move(data) => data.execute(pc, register_file, status_register, memory),
             Opcode::Lea(data) => data.execute(pc, register_file, status_register, memory),
             Opcode::Push(data) => data.execute(pc, register_file, status_register, memory),
             Opcode::Pop(data) => data.execute(pc, register_file, status_register, memory),
-            Opcode::Unknown => todo!(),
+            Opcode::Arithmetic(data) => data.execute(pc, register_file, status_register, memory),
+            Opcode::Logic(data) => data.execute(pc, register_file, status_register, memory),
+            Opcode::Jump(data) => data.execute(pc, register_file, status_register, memory),
+            Opcode::Branch(data) => data.execute(pc, register_file, status_register, memory),
+            Opcode::Rts(data) => data.execute(pc, register_file, status_register, memory),
+            Opcode::Rti(data) => data.execute(pc, register_file, status_register, memory),
+            Opcode::InterruptEnable(data) => {
+                data.execute(pc, register_file, status_register, memory)
+            }
+            Opcode::Unknown => Err(Exception::IllegalInstruction),
         }
     }
 }
@@ -49,8 +206,8 @@ impl Execute for MoveOpcode {
         pc: &mut u32,
         register_file: &mut RegisterFile,
         status_register: &mut StatusRegister,
-        memory: &mut Memory,
-    ) {
+        memory: &mut dyn Bus,
+    ) -> Result<(), Exception> {
         match self {
             MoveOpcode {
                 addr_mode: AddressingMode::Atomic,
@@ -71,8 +228,7 @@ impl Execute for MoveOpcode {
                 offset: _,
                 size: _,
             } => {
-                *pc += 4;
-                let value = memory.read_dword(*pc);
+                let value = memory.fetch_dword(pc.wrapping_add(4))?;
                 register_file.write_value(destination, value);
             }
             MoveOpcode {
@@ -88,7 +244,7 @@ impl Execute for MoveOpcode {
                 let command =
                     size.memory_write_command(register_file.read_value(destination), data_to_write);
 
-                memory.memory_bus_write(command);
+                memory.memory_bus_write(command)?;
             }
             MoveOpcode {
                 addr_mode: AddressingMode::MemorySrc,
@@ -98,7 +254,7 @@ impl Execute for MoveOpcode {
                 size,
             } => {
                 let address = register_file.read_value(source);
-                let data_to_write = memory.memory_bus_read(size, address);
+                let data_to_write = memory.memory_bus_read(size, address)?;
 
                 register_file.write_value(destination, data_to_write);
             }
@@ -113,9 +269,9 @@ impl Execute for MoveOpcode {
                 let address = register_file.read_value(destination);
                 let command = size.memory_write_command(address, data_to_write);
 
-                memory.memory_bus_write(command);
+                memory.memory_bus_write(command)?;
 
-                register_file.write_value(destination, address + size.size_in_bytes())
+                register_file.write_value(destination, address.wrapping_add(size.size_in_bytes()))
             }
             MoveOpcode {
                 addr_mode: AddressingMode::MemoryDestDec,
@@ -128,9 +284,9 @@ impl Execute for MoveOpcode {
                 let address = register_file.read_value(destination);
                 let command = size.memory_write_command(address, data_to_write);
 
-                memory.memory_bus_write(command);
+                memory.memory_bus_write(command)?;
 
-                register_file.write_value(destination, address - size.size_in_bytes());
+                register_file.write_value(destination, address.wrapping_sub(size.size_in_bytes()));
             }
             MoveOpcode {
                 addr_mode: AddressingMode::MemorySrcInc,
@@ -140,10 +296,10 @@ impl Execute for MoveOpcode {
                 size,
             } => {
                 let address = register_file.read_value(source);
-                let data_to_write = memory.memory_bus_read(size, address);
+                let data_to_write = memory.memory_bus_read(size, address)?;
 
                 register_file.write_value(destination, data_to_write);
-                register_file.write_value(source, address + size.size_in_bytes());
+                register_file.write_value(source, address.wrapping_add(size.size_in_bytes()));
             }
             MoveOpcode {
                 addr_mode: AddressingMode::MemorySrcDec,
@@ -153,10 +309,10 @@ impl Execute for MoveOpcode {
                 size,
             } => {
                 let address = register_file.read_value(source);
-                let data_to_write = memory.memory_bus_read(size, address);
+                let data_to_write = memory.memory_bus_read(size, address)?;
 
                 register_file.write_value(destination, data_to_write);
-                register_file.write_value(source, address - size.size_in_bytes());
+                register_file.write_value(source, address.wrapping_sub(size.size_in_bytes()));
             }
         }
 
@@ -174,6 +330,8 @@ impl Execute for MoveOpcode {
         } else {
             status_register.clear(Flags::Negative);
         }
+
+        Ok(())
     }
 }
 
@@ -187,18 +345,17 @@ impl Execute for LeaOpcode {
         &self,
         pc: &mut u32,
         register_file: &mut RegisterFile,
-        status_register: &mut StatusRegister,
-        memory: &mut Memory,
-    ) {
+        _status_register: &mut StatusRegister,
+        memory: &mut dyn Bus,
+    ) -> Result<(), Exception> {
         if !(self.destination >= Register::A0 && self.destination <= Register::A15) {
-            unreachable!("Lea opcode can only be used with the Registers A0 to A15")
+            return Err(Exception::IllegalInstruction);
         }
 
-        let dest_index: u32 = self.destination.into();
-        *pc += 4;
+        let address = memory.fetch_dword(pc.wrapping_add(4))?;
+        register_file.write_value(&self.destination, address);
 
-        let address = memory.read_dword(*pc);
-        register_file.registers[dest_index as usize] = address;
+        Ok(())
     }
 }
 
@@ -208,15 +365,15 @@ pub(crate) trait Execute {
         pc: &mut u32,
         register_file: &mut RegisterFile,
         status_register: &mut StatusRegister,
-        memory: &mut Memory,
-    );
+        memory: &mut dyn Bus,
+    ) -> Result<(), Exception>;
 }
 
 #[derive(Debug, PartialEq, Eq)]
 pub struct PushOpcode {
-    addressing_mode: AddressingMode,
-    source: Register,
-    size: OpcodeSize,
+    pub addressing_mode: AddressingMode,
+    pub source: Register,
+    pub size: OpcodeSize,
 }
 
 impl Execute for PushOpcode {
@@ -224,37 +381,33 @@ impl Execute for PushOpcode {
         &self,
         pc: &mut u32,
         register_file: &mut RegisterFile,
-        status_register: &mut StatusRegister,
-        memory: &mut Memory,
-    ) {
+        _status_register: &mut StatusRegister,
+        memory: &mut dyn Bus,
+    ) -> Result<(), Exception> {
         //TODO: For now we assume that SP is the A15 Register but the user should be able to move the sp?!
         let stack_pointer = Register::A15;
 
-        match self {
+        //NOTE: Registers only change once every access succeeded, the update of the source register is kept until then
+        let (data_to_write, source_update) = match self {
             PushOpcode {
                 addressing_mode: AddressingMode::Atomic,
                 source,
                 size,
             } => {
                 let raw_value: u32 = register_file.read_value(source);
-                let data_to_write = size.retrieve_data(raw_value);
-                let write_command = size
-                    .memory_write_command(register_file.read_value(&stack_pointer), data_to_write);
-
-                memory.memory_bus_write(write_command);
+                (size.retrieve_data(raw_value), None)
             }
             PushOpcode {
                 addressing_mode: AddressingMode::Immediate,
                 source: _,
                 size,
             } => {
-                *pc += 4;
-                let data_to_write = memory.memory_bus_read(size, *pc);
-
-                let write_command = size
-                    .memory_write_command(register_file.read_value(&stack_pointer), data_to_write);
-
-                memory.memory_bus_write(write_command);
+                //NOTE: Immediates always occupy a full dword after the opcode, smaller sizes are
+                //      stored in the lower bits of it.
+                (
+                    size.retrieve_data(memory.fetch_dword(pc.wrapping_add(4))?),
+                    None,
+                )
             }
             PushOpcode {
                 addressing_mode: AddressingMode::MemoryDest,
@@ -262,12 +415,7 @@ impl Execute for PushOpcode {
                 size,
             } => {
                 let address = register_file.read_value(source);
-                let value = memory.memory_bus_read(size, address);
-
-                let write_command =
-                    size.memory_write_command(register_file.read_value(&stack_pointer), value);
-
-                memory.memory_bus_write(write_command);
+                (memory.memory_bus_read(size, address)?, None)
             }
             PushOpcode {
                 addressing_mode: AddressingMode::MemoryDestInc,
@@ -275,13 +423,12 @@ impl Execute for PushOpcode {
                 size,
             } => {
                 let address = register_file.read_value(source);
-                let value = memory.memory_bus_read(size, address);
-
-                let write_command =
-                    size.memory_write_command(register_file.read_value(&stack_pointer), value);
+                let value = memory.memory_bus_read(size, address)?;
 
-                memory.memory_bus_write(write_command);
-                register_file.write_value(source, address + size.size_in_bytes());
+                (
+                    value,
+                    Some((*source, address.wrapping_add(size.size_in_bytes()))),
+                )
             }
             PushOpcode {
                 addressing_mode: AddressingMode::MemoryDestDec,
@@ -289,64 +436,603 @@ impl Execute for PushOpcode {
                 size,
             } => {
                 let address = register_file.read_value(source);
-                let value = memory.memory_bus_read(size, address);
+                let value = memory.memory_bus_read(size, address)?;
 
-                let write_command =
-                    size.memory_write_command(register_file.read_value(&stack_pointer), value);
-
-                memory.memory_bus_write(write_command);
-                register_file.write_value(source, address - size.size_in_bytes());
+                (
+                    value,
+                    Some((*source, address.wrapping_sub(size.size_in_bytes()))),
+                )
             }
-            _ => unreachable!(),
+            //NOTE: The decoder never hands us any other addressing mode for PUSH, hand built opcodes might
+            _ => return Err(Exception::IllegalInstruction),
+        };
+
+        //NOTE: The stack grows downwards, so we make room for the value first and then store it. Pushing through the
+        //      stack pointer itself pushes relative to its updated value.
+        let stack_base = match source_update {
+            Some((register, address)) if register == stack_pointer => address,
+            _ => register_file.read_value(&stack_pointer),
+        };
+        let top_of_stack = stack_base.wrapping_sub(self.size.size_in_bytes());
+        let write_command = self.size.memory_write_command(top_of_stack, data_to_write);
+
+        memory.memory_bus_write(write_command)?;
+        if let Some((register, address)) = source_update {
+            register_file.write_value(&register, address);
         }
+        register_file.write_value(&stack_pointer, top_of_stack);
+
+        Ok(())
     }
 }
 
 #[derive(Debug, PartialEq, Eq)]
 pub struct PopOpcode {
-    addressing_mode: AddressingMode,
-    destination: Register,
-    size: OpcodeSize,
+    pub addressing_mode: AddressingMode,
+    pub destination: Register,
+    pub size: OpcodeSize,
 }
 
 impl Execute for PopOpcode {
     fn execute(
         &self,
-        pc: &mut u32,
+        _pc: &mut u32,
         register_file: &mut RegisterFile,
         status_register: &mut StatusRegister,
-        memory: &mut Memory,
-    ) {
-        match self {
+        memory: &mut dyn Bus,
+    ) -> Result<(), Exception> {
+        //TODO: For now we assume that SP is the A15 Register but the user should be able to move the sp?!
+        let stack_pointer = Register::A15;
+
+        //NOTE: Registers only change once every access succeeded, a memory destination addressed through the stack
+        //      pointer sees its popped value
+        let top_of_stack = register_file.read_value(&stack_pointer);
+        let value = memory.memory_bus_read(&self.size, top_of_stack)?;
+        let popped = top_of_stack.wrapping_add(self.size.size_in_bytes());
+        let destination_address = |register: &Register, register_file: &RegisterFile| {
+            if *register == stack_pointer {
+                popped
+            } else {
+                register_file.read_value(register)
+            }
+        };
+
+        let destination_update = match self {
             PopOpcode {
                 addressing_mode: AddressingMode::Atomic,
                 destination,
-                size,
-            } => {
-                todo!()
-            }
+                size: _,
+            } => Some((*destination, value)),
             PopOpcode {
                 addressing_mode: AddressingMode::MemoryDest,
                 destination,
                 size,
             } => {
-                todo!()
+                let address = destination_address(destination, register_file);
+                memory.memory_bus_write(size.memory_write_command(address, value))?;
+                None
             }
             PopOpcode {
                 addressing_mode: AddressingMode::MemoryDestInc,
                 destination,
                 size,
             } => {
-                todo!()
+                let address = destination_address(destination, register_file);
+                memory.memory_bus_write(size.memory_write_command(address, value))?;
+                Some((*destination, address.wrapping_add(size.size_in_bytes())))
             }
             PopOpcode {
                 addressing_mode: AddressingMode::MemoryDestDec,
                 destination,
                 size,
             } => {
-                todo!()
+                let address = destination_address(destination, register_file);
+                memory.memory_bus_write(size.memory_write_command(address, value))?;
+                Some((*destination, address.wrapping_sub(size.size_in_bytes())))
+            }
+            //NOTE: The decoder never hands us any other addressing mode for POP, hand built opcodes might
+            _ => return Err(Exception::IllegalInstruction),
+        };
+
+        register_file.write_value(&stack_pointer, popped);
+        if let Some((register, new_value)) = destination_update {
+            register_file.write_value(&register, new_value);
+        }
+
+        if value == 0x00 {
+            status_register.raise(Flags::Zero);
+        } else {
+            status_register.clear(Flags::Zero);
+        }
+
+        if value & self.size.sign_bit() != 0x00 {
+            status_register.raise(Flags::Negative);
+        } else {
+            status_register.clear(Flags::Negative);
+        }
+
+        Ok(())
+    }
+}
+
+//NOTE: Where the result of an operation with a destination and a source operand ends up
+enum Target {
+    Register(Register),
+    Memory(u32),
+}
+
+struct Operands {
+    destination: u32,
+    source: u32,
+    target: Target,
+}
+
+//NOTE: Shared operand fetching for the two operand instructions (ALU, Logic, ...), memory destination modes read
+//      the destination from the address in the destination register and write the result back to it.
+fn fetch_operands(
+    addr_mode: &AddressingMode,
+    destination: &Register,
+    source: &Register,
+    size: &OpcodeSize,
+    pc: u32,
+    register_file: &RegisterFile,
+    memory: &mut dyn Bus,
+) -> Result<Operands, BusError> {
+    let operands = match addr_mode {
+        AddressingMode::Atomic => Operands {
+            destination: register_file.read_value(destination),
+            source: register_file.read_value(source),
+            target: Target::Register(*destination),
+        },
+        AddressingMode::Immediate => Operands {
+            destination: register_file.read_value(destination),
+            source: size.retrieve_data(memory.fetch_dword(pc.wrapping_add(4))?),
+            target: Target::Register(*destination),
+        },
+        AddressingMode::MemorySrc | AddressingMode::MemorySrcInc | AddressingMode::MemorySrcDec => {
+            Operands {
+                destination: register_file.read_value(destination),
+                source: memory.memory_bus_read(size, register_file.read_value(source))?,
+                target: Target::Register(*destination),
+            }
+        }
+        AddressingMode::MemoryDest
+        | AddressingMode::MemoryDestInc
+        | AddressingMode::MemoryDestDec => {
+            let address = register_file.read_value(destination);
+            Operands {
+                destination: memory.memory_bus_read(size, address)?,
+                source: register_file.read_value(source),
+                target: Target::Memory(address),
+            }
+        }
+    };
+    Ok(operands)
+}
+
+fn write_back(
+    target: &Target,
+    size: &OpcodeSize,
+    value: u32,
+    register_file: &mut RegisterFile,
+    memory: &mut dyn Bus,
+) -> Result<(), BusError> {
+    match target {
+        Target::Register(register) => {
+            register_file.write_value(register, size.retrieve_data(value));
+            Ok(())
+        }
+        Target::Memory(address) => {
+            memory.memory_bus_write(size.memory_write_command(*address, value))
+        }
+    }
+}
+
+fn update_address_register(
+    addr_mode: &AddressingMode,
+    destination: &Register,
+    source: &Register,
+    size: &OpcodeSize,
+    register_file: &mut RegisterFile,
+) {
+    let (register, increment) = match addr_mode {
+        AddressingMode::MemoryDestInc => (destination, true),
+        AddressingMode::MemoryDestDec => (destination, false),
+        AddressingMode::MemorySrcInc => (source, true),
+        AddressingMode::MemorySrcDec => (source, false),
+        _ => return,
+    };
+
+    let address = register_file.read_value(register);
+    if increment {
+        register_file.write_value(register, address.wrapping_add(size.size_in_bytes()));
+    } else {
+        register_file.write_value(register, address.wrapping_sub(size.size_in_bytes()));
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ArithmeticOperation {
+    Add,
+    Sub,
+    Addc,
+    Subc,
+    Neg,
+    Cmp,
+}
+
+impl Display for ArithmeticOperation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ArithmeticOperation::Add => write!(f, "ADD"),
+            ArithmeticOperation::Sub => write!(f, "SUB"),
+            ArithmeticOperation::Addc => write!(f, "ADDC"),
+            ArithmeticOperation::Subc => write!(f, "SUBC"),
+            ArithmeticOperation::Neg => write!(f, "NEG"),
+            ArithmeticOperation::Cmp => write!(f, "CMP"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ArithmeticOpcode {
+    pub operation: ArithmeticOperation,
+    pub addr_mode: AddressingMode,
+    pub destination: Register,
+    pub source: Register,
+    pub size: OpcodeSize,
+}
+
+impl Execute for ArithmeticOpcode {
+    fn execute(
+        &self,
+        pc: &mut u32,
+        register_file: &mut RegisterFile,
+        status_register: &mut StatusRegister,
+        memory: &mut dyn Bus,
+    ) -> Result<(), Exception> {
+        let operands = fetch_operands(
+            &self.addr_mode,
+            &self.destination,
+            &self.source,
+            &self.size,
+            *pc,
+            register_file,
+            memory,
+        )?;
+
+        let carry_in = status_register.is_set(Flags::Carry);
+        let result = match self.operation {
+            ArithmeticOperation::Add => {
+                alu::add(&self.size, operands.destination, operands.source, false)
+            }
+            ArithmeticOperation::Addc => {
+                alu::add(&self.size, operands.destination, operands.source, carry_in)
+            }
+            ArithmeticOperation::Sub | ArithmeticOperation::Cmp => {
+                alu::sub(&self.size, operands.destination, operands.source, false)
+            }
+            ArithmeticOperation::Subc => {
+                alu::sub(&self.size, operands.destination, operands.source, carry_in)
+            }
+            ArithmeticOperation::Neg => alu::sub(&self.size, 0x00, operands.destination, false),
+        };
+
+        //NOTE: CMP only sets the flags the result is thrown away
+        if self.operation != ArithmeticOperation::Cmp {
+            write_back(
+                &operands.target,
+                &self.size,
+                result.value,
+                register_file,
+                memory,
+            )?;
+        }
+
+        update_address_register(
+            &self.addr_mode,
+            &self.destination,
+            &self.source,
+            &self.size,
+            register_file,
+        );
+
+        status_register.update_from_alu(&self.size, &result);
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum LogicOperation {
+    And,
+    Or,
+    Xor,
+    Not,
+    Lsl,
+    Lsr,
+    Asr,
+    Rol,
+    Ror,
+}
+
+impl LogicOperation {
+    pub fn is_shift(&self) -> bool {
+        matches!(
+            self,
+            LogicOperation::Lsl
+                | LogicOperation::Lsr
+                | LogicOperation::Asr
+                | LogicOperation::Rol
+                | LogicOperation::Ror
+        )
+    }
+}
+
+impl Display for LogicOperation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LogicOperation::And => write!(f, "AND"),
+            LogicOperation::Or => write!(f, "OR"),
+            LogicOperation::Xor => write!(f, "XOR"),
+            LogicOperation::Not => write!(f, "NOT"),
+            LogicOperation::Lsl => write!(f, "LSL"),
+            LogicOperation::Lsr => write!(f, "LSR"),
+            LogicOperation::Asr => write!(f, "ASR"),
+            LogicOperation::Rol => write!(f, "ROL"),
+            LogicOperation::Ror => write!(f, "ROR"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct LogicOpcode {
+    pub operation: LogicOperation,
+    pub addr_mode: AddressingMode,
+    pub destination: Register,
+    pub source: Register,
+    pub offset: u32,
+    pub size: OpcodeSize,
+}
+
+impl Execute for LogicOpcode {
+    fn execute(
+        &self,
+        pc: &mut u32,
+        register_file: &mut RegisterFile,
+        status_register: &mut StatusRegister,
+        memory: &mut dyn Bus,
+    ) -> Result<(), Exception> {
+        //NOTE: The immediate form of the shifts and rotates carries the count inside the offset field, so
+        //      they never need an extra dword after the opcode.
+        let operands = if self.operation.is_shift() && self.addr_mode == AddressingMode::Immediate {
+            Operands {
+                destination: register_file.read_value(&self.destination),
+                source: self.offset,
+                target: Target::Register(self.destination),
             }
-            _ => unreachable!(),
+        } else {
+            fetch_operands(
+                &self.addr_mode,
+                &self.destination,
+                &self.source,
+                &self.size,
+                *pc,
+                register_file,
+                memory,
+            )?
+        };
+
+        let value = operands.destination;
+        let count = operands.source & 0x3F;
+        let result = match self.operation {
+            LogicOperation::And => alu::logic(&self.size, value & operands.source),
+            LogicOperation::Or => alu::logic(&self.size, value | operands.source),
+            LogicOperation::Xor => alu::logic(&self.size, value ^ operands.source),
+            LogicOperation::Not => alu::logic(&self.size, !value),
+            LogicOperation::Lsl => alu::shift_left(&self.size, value, count),
+            LogicOperation::Lsr => alu::shift_right(&self.size, value, count),
+            LogicOperation::Asr => alu::arithmetic_shift_right(&self.size, value, count),
+            LogicOperation::Rol => alu::rotate_left(&self.size, value, count),
+            LogicOperation::Ror => alu::rotate_right(&self.size, value, count),
+        };
+
+        write_back(
+            &operands.target,
+            &self.size,
+            result.value,
+            register_file,
+            memory,
+        )?;
+
+        update_address_register(
+            &self.addr_mode,
+            &self.destination,
+            &self.source,
+            &self.size,
+            register_file,
+        );
+
+        status_register.update_from_alu(&self.size, &result);
+
+        Ok(())
+    }
+}
+
+//TODO: For now we assume that SP is the A15 Register but the user should be able to move the sp?!
+//NOTE: The stack pointer only moves once the access succeeded
+pub(crate) fn push_dword(
+    value: u32,
+    register_file: &mut RegisterFile,
+    memory: &mut dyn Bus,
+) -> Result<(), BusError> {
+    let top_of_stack = register_file.read_value(&Register::A15).wrapping_sub(4);
+
+    memory.write_dword(top_of_stack, value)?;
+    register_file.write_value(&Register::A15, top_of_stack);
+    Ok(())
+}
+
+pub(crate) fn pop_dword(
+    register_file: &mut RegisterFile,
+    memory: &mut dyn Bus,
+) -> Result<u32, BusError> {
+    let top_of_stack = register_file.read_value(&Register::A15);
+    let value = memory.read_dword(top_of_stack)?;
+
+    register_file.write_value(&Register::A15, top_of_stack.wrapping_add(4));
+    Ok(value)
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum JumpOperation {
+    Jmp,
+    Jsr,
+}
+
+impl Display for JumpOperation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            JumpOperation::Jmp => write!(f, "JMP"),
+            JumpOperation::Jsr => write!(f, "JSR"),
         }
     }
 }
+
+//NOTE: Absolute jumps, the target is either the immediate dword after the opcode or the value of the destination register
+#[derive(Debug, PartialEq, Eq)]
+pub struct JumpOpcode {
+    pub operation: JumpOperation,
+    pub addr_mode: AddressingMode,
+    pub destination: Register,
+}
+
+impl Execute for JumpOpcode {
+    fn execute(
+        &self,
+        pc: &mut u32,
+        register_file: &mut RegisterFile,
+        _status_register: &mut StatusRegister,
+        memory: &mut dyn Bus,
+    ) -> Result<(), Exception> {
+        let (target, return_address) = match self.addr_mode {
+            AddressingMode::Immediate => {
+                (memory.fetch_dword(pc.wrapping_add(4))?, pc.wrapping_add(8))
+            }
+            AddressingMode::Atomic => (
+                register_file.read_value(&self.destination),
+                pc.wrapping_add(4),
+            ),
+            //NOTE: The decoder never hands us any other addressing mode for jumps, hand built opcodes might
+            _ => return Err(Exception::IllegalInstruction),
+        };
+
+        if self.operation == JumpOperation::Jsr {
+            push_dword(return_address, register_file, memory)?;
+        }
+
+        *pc = target;
+
+        Ok(())
+    }
+}
+
+//NOTE: PC relative branches, the signed displacement in the dword after the opcode is relative to the
+//      address of the branch itself. A branch to a subroutine pushes the address after the displacement.
+#[derive(Debug, PartialEq, Eq)]
+pub struct BranchOpcode {
+    pub condition: Condition,
+    pub subroutine: bool,
+}
+
+impl Execute for BranchOpcode {
+    fn execute(
+        &self,
+        pc: &mut u32,
+        register_file: &mut RegisterFile,
+        status_register: &mut StatusRegister,
+        memory: &mut dyn Bus,
+    ) -> Result<(), Exception> {
+        let return_address = pc.wrapping_add(8);
+
+        if !self.condition.evaluate(status_register) {
+            *pc = return_address;
+            return Ok(());
+        }
+
+        let displacement = memory.fetch_dword(pc.wrapping_add(4))?;
+        if self.subroutine {
+            push_dword(return_address, register_file, memory)?;
+        }
+
+        *pc = pc.wrapping_add(displacement);
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct RtsOpcode;
+
+impl Execute for RtsOpcode {
+    fn execute(
+        &self,
+        pc: &mut u32,
+        register_file: &mut RegisterFile,
+        _status_register: &mut StatusRegister,
+        memory: &mut dyn Bus,
+    ) -> Result<(), Exception> {
+        *pc = pop_dword(register_file, memory)?;
+
+        Ok(())
+    }
+}
+
+//NOTE: Returns from an exception or interrupt handler, the status register was pushed last so it is popped first
+#[derive(Debug, PartialEq, Eq)]
+pub struct RtiOpcode;
+
+impl Execute for RtiOpcode {
+    fn execute(
+        &self,
+        pc: &mut u32,
+        register_file: &mut RegisterFile,
+        status_register: &mut StatusRegister,
+        memory: &mut dyn Bus,
+    ) -> Result<(), Exception> {
+        //NOTE: Both dwords are read before anything changes so a faulting RTI can be retried
+        let top_of_stack = register_file.read_value(&Register::A15);
+        let flags = memory.read_dword(top_of_stack)?;
+        let return_address = memory.read_dword(top_of_stack.wrapping_add(4))?;
+
+        register_file.write_value(&Register::A15, top_of_stack.wrapping_add(8));
+        status_register.restore(flags as u16);
+        *pc = return_address;
+
+        Ok(())
+    }
+}
+
+//NOTE: EI and DI, interrupts are only dispatched while the InterruptEnable flag is set
+#[derive(Debug, PartialEq, Eq)]
+pub struct InterruptEnableOpcode {
+    pub enable: bool,
+}
+
+impl Execute for InterruptEnableOpcode {
+    fn execute(
+        &self,
+        _pc: &mut u32,
+        _register_file: &mut RegisterFile,
+        status_register: &mut StatusRegister,
+        _memory: &mut dyn Bus,
+    ) -> Result<(), Exception> {
+        if self.enable {
+            status_register.raise(Flags::InterruptEnable);
+        } else {
+            status_register.clear(Flags::InterruptEnable);
+        }
+
+        Ok(())
+    }
+}
@@ -57,6 +57,22 @@ impl OpcodeSize {
         }
     }
 
+    //NOTE: The data bus is 16 bits wide, a dword needs two accesses
+    pub fn access_cycles(&self) -> u32 {
+        match self {
+            OpcodeSize::Byte | OpcodeSize::Word => 1,
+            OpcodeSize::Dword => 2,
+        }
+    }
+
+    pub fn sign_bit(&self) -> u32 {
+        match self {
+            OpcodeSize::Byte => 0x00000080,
+            OpcodeSize::Word => 0x00008000,
+            OpcodeSize::Dword => 0x80000000,
+        }
+    }
+
     pub(crate) fn memory_write_command(&self, address: u32, value: u32) -> MemoryWrite {
         match self {
             OpcodeSize::Byte => MemoryWrite::Byte {
@@ -1,4 +1,7 @@
-pub(crate) enum Flags {
+use super::{alu::AluResult, opcode_size::OpcodeSize};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Flags {
     Negative = 15,       //The value is negative
     Overflow = 14,       //A Over/Underflow occured while doing the operation
     Carry = 13,          //The value did not fit and it carried a one into the status register
@@ -10,7 +13,25 @@ pub(crate) enum Flags {
                  //...
 }
 
-pub(crate) struct StatusRegister {
+impl Flags {
+    pub const ALL: [Flags; 8] = [
+        Flags::Negative,
+        Flags::Overflow,
+        Flags::Carry,
+        Flags::Parity,
+        Flags::Zero,
+        Flags::Interrupt,
+        Flags::InterruptEnable,
+        Flags::Trap,
+    ];
+
+    fn mask(&self) -> u16 {
+        1 << *self as u16
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct StatusRegister {
     flags: u16,
 }
 
@@ -20,18 +41,154 @@ impl StatusRegister {
     }
 
     pub(crate) fn raise(&mut self, flag: Flags) {
-        let target_flag = flag as usize;
-        self.flags |= 1 << target_flag;
+        self.flags |= flag.mask();
     }
 
     pub(crate) fn clear(&mut self, flag: Flags) {
-        let target_flag = flag as usize;
-        self.flags &= 1 << target_flag;
+        self.flags &= !flag.mask();
     }
 
-    pub(crate) fn status_bits(&self) -> u16 {
+    pub(crate) fn set_to(&mut self, flag: Flags, value: bool) {
+        if value {
+            self.raise(flag);
+        } else {
+            self.clear(flag);
+        }
+    }
+
+    pub fn is_set(&self, flag: Flags) -> bool {
+        self.flags & flag.mask() != 0
+    }
+
+    pub fn status_bits(&self) -> u16 {
         self.flags
     }
+
+    //NOTE: Sets Zero, Negative, Carry, Overflow and Parity from the result of an alu operation of the given size
+    pub(crate) fn update_from_alu(&mut self, size: &OpcodeSize, result: &AluResult) {
+        self.set_to(Flags::Zero, result.value == 0x00);
+        self.set_to(Flags::Negative, result.value & size.sign_bit() != 0x00);
+        self.set_to(Flags::Carry, result.carry);
+        self.set_to(Flags::Overflow, result.overflow);
+        self.set_to(Flags::Parity, result.value.count_ones().is_multiple_of(2));
+    }
+
+    //NOTE: Used to restore a status register that was saved on the stack
+    pub(crate) fn restore(&mut self, flags: u16) {
+        self.flags = flags;
+    }
 }
 
-//TODO(Kay): Testing!
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::cpu::alu;
+
+    #[test]
+    fn test_new_status_register_is_clear() {
+        let status_register = StatusRegister::new();
+
+        assert_eq!(status_register.status_bits(), 0x00);
+        for flag in Flags::ALL {
+            assert!(!status_register.is_set(flag), "{:?}", flag);
+        }
+    }
+
+    #[test]
+    fn test_raise_only_touches_one_flag() {
+        for flag in Flags::ALL {
+            let mut status_register = StatusRegister::new();
+            status_register.raise(flag);
+
+            assert_eq!(status_register.status_bits(), 1 << flag as u16);
+            for other in Flags::ALL {
+                assert_eq!(status_register.is_set(other), other == flag, "{:?}", other);
+            }
+
+            status_register.raise(flag);
+            assert_eq!(status_register.status_bits(), 1 << flag as u16);
+        }
+    }
+
+    #[test]
+    fn test_clear_only_touches_one_flag() {
+        for flag in Flags::ALL {
+            let mut status_register = StatusRegister::new();
+            for other in Flags::ALL {
+                status_register.raise(other);
+            }
+
+            status_register.clear(flag);
+
+            for other in Flags::ALL {
+                assert_eq!(status_register.is_set(other), other != flag, "{:?}", other);
+            }
+
+            status_register.clear(flag);
+            assert!(!status_register.is_set(flag));
+        }
+    }
+
+    #[test]
+    fn test_set_to() {
+        for flag in Flags::ALL {
+            let mut status_register = StatusRegister::new();
+            status_register.raise(Flags::Zero);
+
+            status_register.set_to(flag, true);
+            assert!(status_register.is_set(flag));
+
+            status_register.set_to(flag, false);
+            assert!(!status_register.is_set(flag));
+            assert_eq!(status_register.is_set(Flags::Zero), flag != Flags::Zero);
+        }
+    }
+
+    #[test]
+    fn test_restore() {
+        let mut status_register = StatusRegister::new();
+        status_register.restore(0xFF00);
+
+        for flag in Flags::ALL {
+            assert!(status_register.is_set(flag), "{:?}", flag);
+        }
+    }
+
+    #[test]
+    fn test_update_from_alu() {
+        let mut status_register = StatusRegister::new();
+        status_register.raise(Flags::InterruptEnable);
+        status_register.raise(Flags::Trap);
+
+        status_register.update_from_alu(
+            &OpcodeSize::Byte,
+            &alu::add(&OpcodeSize::Byte, 0x80, 0x80, false),
+        );
+        assert!(status_register.is_set(Flags::Zero));
+        assert!(!status_register.is_set(Flags::Negative));
+        assert!(status_register.is_set(Flags::Carry));
+        assert!(status_register.is_set(Flags::Overflow));
+        assert!(status_register.is_set(Flags::Parity));
+
+        status_register.update_from_alu(
+            &OpcodeSize::Word,
+            &alu::add(&OpcodeSize::Word, 0x8000, 0x0001, false),
+        );
+        assert!(!status_register.is_set(Flags::Zero));
+        assert!(status_register.is_set(Flags::Negative));
+        assert!(!status_register.is_set(Flags::Carry));
+        assert!(!status_register.is_set(Flags::Overflow));
+        assert!(status_register.is_set(Flags::Parity));
+
+        status_register.update_from_alu(
+            &OpcodeSize::Dword,
+            &alu::add(&OpcodeSize::Dword, 0x01, 0x01, false),
+        );
+        assert!(!status_register.is_set(Flags::Parity));
+
+        //NOTE: The alu never touches the interrupt and trap flags
+        assert!(status_register.is_set(Flags::InterruptEnable));
+        assert!(status_register.is_set(Flags::Trap));
+        assert!(!status_register.is_set(Flags::Interrupt));
+    }
+}
@@ -1,88 +1,113 @@
+pub mod bios;
+pub mod bus;
 pub mod cpu;
+pub mod machine;
+pub mod snapshot;
+pub mod trace;
+pub mod video;
 
-use cpu::{opcode_size::OpcodeSize, register::Register};
+use bus::BusError;
+use cpu::register::Register;
 
 const MEMORY_SIZE: usize = 128 * (1024 * 1024);
+const PAGE_SIZE: usize = 4 * 1024;
 
-enum MemoryRead {
-    Byte { address: u32 },
-    Word { address: u32 },
-    Dword { address: u32 },
-}
-
-enum MemoryWrite {
+pub enum MemoryWrite {
     Byte { address: u32, value: u8 },
     Word { address: u32, value: u16 },
     Dword { address: u32, value: u32 },
 }
 
+//NOTE: Ram is split into pages that are only allocated once something other than zero is written to them, untouched
+//      pages read as 0x00. Creating a cpu stays cheap no matter how large ram is.
 struct Memory {
-    bytes: Box<[u8; MEMORY_SIZE]>,
+    pages: Vec<Option<Box<[u8; PAGE_SIZE]>>>,
+    size: u32,
 }
 
 impl Memory {
-    fn new() -> Self {
+    fn new(size: u32) -> Self {
         Self {
-            bytes: vec![0; MEMORY_SIZE].into_boxed_slice().try_into().unwrap(),
+            pages: vec![None; (size as usize).div_ceil(PAGE_SIZE)],
+            size,
         }
     }
 
-    fn memory_bus_read(&self, size: &OpcodeSize, address: u32) -> u32 {
-        match size {
-            OpcodeSize::Byte => self.read_byte(address),
-            OpcodeSize::Word => self.read_word(address),
-            OpcodeSize::Dword => self.read_dword(address),
-        }
+    fn contains(&self, address: u32) -> bool {
+        address < self.size
     }
 
-    fn memory_bus_write(&mut self, command: MemoryWrite) {
-        match command {
-            MemoryWrite::Byte { address, value } => self.write_byte(address, value),
-            MemoryWrite::Word { address, value } => self.write_word(address, value),
-            MemoryWrite::Dword { address, value } => self.write_dword(address, value),
+    fn write_byte(&mut self, address: u32, value: u8) -> Result<(), BusError> {
+        if !self.contains(address) {
+            return Err(BusError::OutOfBounds(address));
         }
-    }
 
-    fn write_byte(&mut self, address: u32, value: u8) {
-        self.bytes[address as usize] = value;
+        let (page, offset) = Self::split(address);
+        match &mut self.pages[page] {
+            Some(bytes) => bytes[offset] = value,
+            //NOTE: Writing a zero to a page that was never allocated doesn't change what it reads as
+            None if value == 0x00 => {}
+            page => {
+                let mut bytes = Box::new([0x00; PAGE_SIZE]);
+                bytes[offset] = value;
+                *page = Some(bytes);
+            }
+        }
+        Ok(())
     }
 
-    fn write_word(&mut self, address: u32, value: u16) {
-        let byte_hi = ((value >> 8) & 0xFF) as u8;
-        let byte_lo = (value & 0xFF) as u8;
+    fn read_byte(&self, address: u32) -> Result<u8, BusError> {
+        if !self.contains(address) {
+            return Err(BusError::OutOfBounds(address));
+        }
 
-        self.bytes[address as usize] = byte_hi;
-        self.bytes[(address + 1) as usize] = byte_lo;
+        let (page, offset) = Self::split(address);
+        Ok(self.pages[page]
+            .as_ref()
+            .map_or(0x00, |bytes| bytes[offset]))
     }
 
-    fn write_dword(&mut self, address: u32, value: u32) {
-        let byte_a = ((value >> 24) & 0xFF) as u8;
-        let byte_b = ((value >> 16) & 0xFF) as u8;
-        let byte_c = ((value >> 8) & 0xFF) as u8;
-        let byte_d = (value & 0xFF) as u8;
+    fn split(address: u32) -> (usize, usize) {
+        let address = address as usize;
+        (address / PAGE_SIZE, address % PAGE_SIZE)
+    }
 
-        self.bytes[address as usize] = byte_a;
-        self.bytes[(address + 1) as usize] = byte_b;
-        self.bytes[(address + 2) as usize] = byte_c;
-        self.bytes[(address + 3) as usize] = byte_d;
+    fn size(&self) -> u32 {
+        self.size
     }
 
-    fn read_byte(&self, address: u32) -> u32 {
-        let address = address as usize;
-        self.bytes[address] as u32
+    //NOTE: The pages holding anything but zeros together with their start address, a last page that is only partly
+    //      backed by ram is cut off at the end of ram
+    fn used_pages(&self) -> impl Iterator<Item = (u32, &[u8])> {
+        self.pages
+            .iter()
+            .enumerate()
+            .filter_map(|(index, page)| page.as_ref().map(|bytes| (index, bytes)))
+            .filter(|(_, bytes)| bytes.iter().any(|byte| *byte != 0x00))
+            .map(|(index, bytes)| {
+                let start = index * PAGE_SIZE;
+                let length = PAGE_SIZE.min(self.size as usize - start);
+                (start as u32, &bytes[..length])
+            })
     }
 
-    fn read_word(&self, address: u32) -> u32 {
-        (self.bytes[address as usize] as u32) << 8 | (self.bytes[(address + 1) as usize] as u32)
+    fn clear(&mut self) {
+        self.pages.fill(None);
     }
 
-    fn read_dword(&self, address: u32) -> u32 {
-        println!("{}", self.bytes.len());
-        //NOTE: For now we use big endian !
-        (self.bytes[address as usize] as u32) << 24
-            | (self.bytes[(address + 1) as usize] as u32) << 16
-            | (self.bytes[(address + 2) as usize] as u32) << 8
-            | (self.bytes[(address + 3) as usize] as u32)
+    //NOTE: Nothing is written if the bytes don't fit
+    fn write_bytes(&mut self, address: u32, bytes: &[u8]) -> Result<(), BusError> {
+        let fits = (address as usize)
+            .checked_add(bytes.len())
+            .is_some_and(|end| end <= self.size as usize);
+        if !fits {
+            return Err(BusError::OutOfBounds(address));
+        }
+
+        for (offset, byte) in bytes.iter().enumerate() {
+            self.write_byte(address + offset as u32, *byte)?;
+        }
+        Ok(())
     }
 }
 
@@ -90,6 +115,8 @@ struct RegisterFile {
     //D0..D15 & A0..A15
     registers: [u32; 32],
     last_written: u32,
+    //NOTE: One bit per register written since the last take_written, used for tracing
+    written: u32,
 }
 
 impl RegisterFile {
@@ -97,6 +124,7 @@ impl RegisterFile {
         Self {
             registers: [0; 32],
             last_written: 0x00,
+            written: 0x00,
         }
     }
 
@@ -106,6 +134,7 @@ impl RegisterFile {
 
         self.registers[dest_index as usize] = value;
         self.last_written = value;
+        self.written |= 1 << dest_index;
     }
 
     fn read_value(&self, src: &Register) -> u32 {
@@ -116,6 +145,85 @@ impl RegisterFile {
     }
 
     fn last_written_value(&self) -> u32 {
-        return self.last_written;
+        self.last_written
+    }
+
+    //NOTE: The registers written since the last call in register order together with their current value
+    fn take_written(&mut self) -> Vec<(Register, u32)> {
+        let written = std::mem::take(&mut self.written);
+        (0..32)
+            .filter(|index| written & (1 << index) != 0)
+            .map(|index| (Register::new(index), self.registers[index as usize]))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_pages_are_allocated_on_write() {
+        let mut memory = Memory::new(MEMORY_SIZE as u32);
+        assert_eq!(memory.read_byte(0x1234), Ok(0x00));
+
+        memory.write_byte(0x1234, 0x00).unwrap();
+        assert!(memory.pages.iter().all(Option::is_none));
+
+        memory.write_byte(0x1234, 0xAB).unwrap();
+        assert_eq!(memory.read_byte(0x1234), Ok(0xAB));
+        assert_eq!(memory.read_byte(0x1235), Ok(0x00));
+        assert_eq!(memory.pages.iter().filter(|page| page.is_some()).count(), 1);
+    }
+
+    #[test]
+    fn test_bounds() {
+        let mut memory = Memory::new(MEMORY_SIZE as u32);
+        let end = MEMORY_SIZE as u32;
+
+        memory.write_byte(end - 1, 0xFF).unwrap();
+        assert_eq!(memory.read_byte(end - 1), Ok(0xFF));
+        assert_eq!(memory.read_byte(end), Err(BusError::OutOfBounds(end)));
+        assert_eq!(
+            memory.write_byte(end, 0xFF),
+            Err(BusError::OutOfBounds(end))
+        );
+    }
+
+    #[test]
+    fn test_bytes_across_pages() {
+        let mut memory = Memory::new(MEMORY_SIZE as u32);
+        let address = (PAGE_SIZE - 2) as u32;
+
+        memory.write_bytes(address, &[1, 2, 3, 4]).unwrap();
+        let pages: Vec<u32> = memory.used_pages().map(|(start, _)| start).collect();
+        assert_eq!(pages, vec![0x0000, PAGE_SIZE as u32]);
+        assert_eq!(memory.read_byte(address + 3), Ok(4));
+
+        let end = MEMORY_SIZE as u32;
+        assert_eq!(
+            memory.write_bytes(end - 2, &[1, 2, 3]),
+            Err(BusError::OutOfBounds(end - 2))
+        );
+        assert_eq!(memory.read_byte(end - 2), Ok(0x00));
+
+        memory.clear();
+        assert_eq!(memory.used_pages().count(), 0);
+    }
+
+    #[test]
+    fn test_ram_that_ends_inside_a_page() {
+        let mut memory = Memory::new(0x1800);
+
+        memory.write_byte(0x17FF, 0xFF).unwrap();
+        assert_eq!(
+            memory.write_byte(0x1800, 0xFF),
+            Err(BusError::OutOfBounds(0x1800))
+        );
+        let pages: Vec<(u32, usize)> = memory
+            .used_pages()
+            .map(|(start, bytes)| (start, bytes.len()))
+            .collect();
+        assert_eq!(pages, vec![(0x1000, 0x800)]);
     }
 }
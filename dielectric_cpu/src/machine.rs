@@ -0,0 +1,235 @@
+use std::fmt::Display;
+
+use crate::{
+    bios::{Bios, RESET_VECTOR},
+    bus::{
+        BusError, Device, MapError, SystemBus, BIOS_END, BIOS_START, VIDEO_MEMORY_END,
+        VIDEO_MEMORY_START,
+    },
+    video::Video,
+    MEMORY_SIZE,
+};
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum MachineError {
+    Map(MapError),
+    //NOTE: The image doesn't fit into the region start..end, contains the size of the image
+    RomTooLarge { start: u32, end: u32, size: usize },
+}
+
+impl Display for MachineError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MachineError::Map(error) => write!(f, "{}", error),
+            MachineError::RomTooLarge { start, end, size } => write!(
+                f,
+                "the rom image is {} bytes but {:#010x}..{:#010x} only holds {} bytes",
+                size,
+                start,
+                end,
+                end.saturating_sub(*start)
+            ),
+        }
+    }
+}
+
+impl From<MapError> for MachineError {
+    fn from(error: MapError) -> Self {
+        MachineError::Map(error)
+    }
+}
+
+enum Window {
+    Rom(Vec<u8>),
+    Device(Box<dyn Device>),
+}
+
+struct MappedWindow {
+    start: u32,
+    end: u32,
+    window: Window,
+}
+
+//NOTE: Describes the machine a cpu is built into, consumed by Cpu::with_config. The defaults are the console from
+//      docs/Memory.md without a bios: 128MB of ram and the reset vector at 0x00000000. Smaller targets shrink the
+//      ram, everything mapped on top of it shadows the ram underneath like Cpu::map_device does.
+pub struct MachineConfig {
+    ram_size: u32,
+    windows: Vec<MappedWindow>,
+    reset_vector: u32,
+}
+
+impl Default for MachineConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MachineConfig {
+    pub fn new() -> Self {
+        Self {
+            ram_size: MEMORY_SIZE as u32,
+            windows: vec![],
+            reset_vector: RESET_VECTOR,
+        }
+    }
+
+    //NOTE: Ram covers the addresses 0..size
+    pub fn ram_size(mut self, size: u32) -> Self {
+        self.ram_size = size;
+        self
+    }
+
+    //NOTE: Maps the image read only to start..end, the part of the region the image doesn't cover reads as 0x00
+    pub fn rom(mut self, start: u32, end: u32, image: Vec<u8>) -> Self {
+        self.windows.push(MappedWindow {
+            start,
+            end,
+            window: Window::Rom(image),
+        });
+        self
+    }
+
+    //NOTE: The bios region of the console, see docs/Memory.md
+    pub fn bios(self, bios: Bios) -> Self {
+        self.device(BIOS_START, BIOS_END, Box::new(bios))
+    }
+
+    //NOTE: The video memory region of the console, keep a clone of the video around to render frames
+    pub fn video(self, video: Video) -> Self {
+        self.device(VIDEO_MEMORY_START, VIDEO_MEMORY_END, Box::new(video))
+    }
+
+    pub fn device(mut self, start: u32, end: u32, device: Box<dyn Device>) -> Self {
+        self.windows.push(MappedWindow {
+            start,
+            end,
+            window: Window::Device(device),
+        });
+        self
+    }
+
+    //NOTE: The address of the dword the cpu reads its start address from after a reset
+    pub fn reset_vector(mut self, address: u32) -> Self {
+        self.reset_vector = address;
+        self
+    }
+
+    //NOTE: Builds the bus the config describes together with the reset vector, windows are mapped in the order they
+    //      were added
+    pub(crate) fn into_bus(self) -> Result<(SystemBus, u32), MachineError> {
+        let mut bus = SystemBus::with_ram_size(self.ram_size);
+        for MappedWindow { start, end, window } in self.windows {
+            let device: Box<dyn Device> = match window {
+                Window::Rom(image) if image.len() as u64 > end.saturating_sub(start) as u64 => {
+                    return Err(MachineError::RomTooLarge {
+                        start,
+                        end,
+                        size: image.len(),
+                    })
+                }
+                Window::Rom(image) => Box::new(Rom::new(image)),
+                Window::Device(device) => device,
+            };
+            bus.map_device(start, end, device)?;
+        }
+        Ok((bus, self.reset_vector))
+    }
+}
+
+//NOTE: Read only memory holding an image, the part of the region the image doesn't cover reads as 0x00. Writes fault
+//      with a bus error. Backs rom windows and the bios.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub(crate) struct Rom {
+    image: Vec<u8>,
+}
+
+impl Rom {
+    pub(crate) fn new(image: Vec<u8>) -> Self {
+        Self { image }
+    }
+
+    pub(crate) fn image(&self) -> &[u8] {
+        &self.image
+    }
+}
+
+impl Device for Rom {
+    fn read_byte(&mut self, offset: u32) -> Result<u8, BusError> {
+        Ok(self.image.get(offset as usize).copied().unwrap_or(0x00))
+    }
+
+    fn write_byte(&mut self, offset: u32, _value: u8) -> Result<(), BusError> {
+        Err(BusError::ReadOnly(offset))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::bus::Bus;
+    use crate::cpu::{core::Cpu, register::Register};
+
+    #[test]
+    fn test_defaults_match_the_console() {
+        let mut cpu = Cpu::with_config(MachineConfig::new()).unwrap();
+
+        assert_eq!(cpu.pc(), 0x00000000);
+        assert_eq!(cpu.bus().write_byte(0x07FF_FFFF, 0xFF), Ok(()));
+        assert_eq!(
+            cpu.bus().write_byte(0x0800_0000, 0xFF),
+            Err(BusError::OutOfBounds(0x0800_0000))
+        );
+    }
+
+    #[test]
+    fn test_small_target() {
+        //NOTE: 64KB of ram with a boot rom far above it that holds the reset vector at its end
+        let mut rom = vec![0x00; 0x100];
+        rom[0xFC..].copy_from_slice(&0x00001000u32.to_be_bytes());
+        let config = MachineConfig::new()
+            .ram_size(0x10000)
+            .rom(0x8000_0000, 0x8000_0100, rom)
+            .reset_vector(0x8000_00FC);
+        let mut cpu = Cpu::with_config(config).unwrap();
+
+        assert_eq!(cpu.pc(), 0x00001000);
+        assert_eq!(
+            cpu.bus().read_byte(0x10000),
+            Err(BusError::OutOfBounds(0x10000))
+        );
+        assert_eq!(
+            cpu.bus().write_byte(0x8000_0000, 0xFF),
+            Err(BusError::ReadOnly(0x8000_0000))
+        );
+
+        cpu.load_words(0x1000, &[0x80000105, 0x1]).unwrap();
+        cpu.step();
+        assert_eq!(cpu.register(Register::D0), 1);
+        assert_eq!(cpu.snapshot().memory_size, 0x10000);
+    }
+
+    #[test]
+    fn test_invalid_configs() {
+        let config = MachineConfig::new().rom(0x1000, 0x1004, vec![0x00; 8]);
+        assert_eq!(
+            Cpu::with_config(config).err(),
+            Some(MachineError::RomTooLarge {
+                start: 0x1000,
+                end: 0x1004,
+                size: 8
+            })
+        );
+
+        let config = MachineConfig::new()
+            .rom(0x1000, 0x2000, vec![])
+            .rom(0x1800, 0x1900, vec![]);
+        assert_eq!(
+            Cpu::with_config(config).err(),
+            Some(MachineError::Map(MapError::Overlap {
+                start: 0x1800,
+                end: 0x1900
+            }))
+        );
+    }
+}
@@ -0,0 +1,307 @@
+use std::{
+    fmt::Display,
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+//NOTE: Snapshots start with the magic and the version, everything after that is stored big endian as:
+//          pc: u32, status: u16, cycles: u64, halted: u8, pending interrupts: u16, registers: 32 * u32,
+//          memory size: u32, page count: u32, (address: u32, length: u32, bytes)*,
+//          device count: u32, (start address: u32, length: u32, state)*
+//      Only pages of ram that aren't all zeros are stored, everything else is zero after a restore.
+pub const SNAPSHOT_MAGIC: [u8; 4] = *b"DSNP";
+pub const SNAPSHOT_VERSION: u8 = 2;
+
+#[derive(Debug)]
+pub enum SnapshotError {
+    Io(io::Error),
+    //NOTE: The file doesn't start with the snapshot magic
+    NotASnapshot,
+    UnsupportedVersion(u8),
+    //NOTE: The snapshot was taken with a different amount of ram, contains the size stored in the snapshot
+    MemorySize(u32),
+    //NOTE: A page doesn't fit into ram, contains the address of the page
+    InvalidPage(u32),
+    //NOTE: The devices mapped into the cpu don't match the ones in the snapshot or refused their state, contains the
+    //      start address of the first device that didn't fit
+    DeviceState(u32),
+}
+
+impl Display for SnapshotError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SnapshotError::Io(error) => write!(f, "failed to access the snapshot, {}", error),
+            SnapshotError::NotASnapshot => write!(f, "the file is not a snapshot"),
+            SnapshotError::UnsupportedVersion(version) => {
+                write!(f, "unsupported snapshot version {}", version)
+            }
+            SnapshotError::MemorySize(size) => write!(
+                f,
+                "the snapshot was taken with {} bytes of ram which doesn't match the cpu",
+                size
+            ),
+            SnapshotError::InvalidPage(address) => {
+                write!(f, "the page at {:#010x} doesn't fit into ram", address)
+            }
+            SnapshotError::DeviceState(address) => write!(
+                f,
+                "the device state at {:#010x} doesn't match the mapped devices",
+                address
+            ),
+        }
+    }
+}
+
+impl From<io::Error> for SnapshotError {
+    fn from(error: io::Error) -> Self {
+        SnapshotError::Io(error)
+    }
+}
+
+//NOTE: The complete state of a cpu, its ram and the devices mapped into it, taken with Cpu::snapshot and put back with
+//      Cpu::restore. The devices themselves aren't stored, only their state, so the cpu restoring the snapshot has to
+//      have the same devices mapped at the same addresses. Breakpoints, watchpoints and the tracer are not part of it.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Snapshot {
+    pub pc: u32,
+    pub status: u16,
+    pub cycles: u64,
+    pub halted: bool,
+    //NOTE: One bit per pending IRQ line
+    pub pending_interrupts: u16,
+    //NOTE: D0..D15 & A0..A15
+    pub registers: [u32; 32],
+    pub memory_size: u32,
+    //NOTE: The start address and the contents of every page that isn't all zeros
+    pub pages: Vec<(u32, Vec<u8>)>,
+    //NOTE: The start address and the state of every mapped device that has one, stateless devices like the bios are
+    //      left out
+    pub devices: Vec<(u32, Vec<u8>)>,
+}
+
+impl Snapshot {
+    pub fn write_to<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(&SNAPSHOT_MAGIC)?;
+        writer.write_all(&[SNAPSHOT_VERSION])?;
+        writer.write_all(&self.pc.to_be_bytes())?;
+        writer.write_all(&self.status.to_be_bytes())?;
+        writer.write_all(&self.cycles.to_be_bytes())?;
+        writer.write_all(&[self.halted as u8])?;
+        writer.write_all(&self.pending_interrupts.to_be_bytes())?;
+        for register in self.registers {
+            writer.write_all(&register.to_be_bytes())?;
+        }
+
+        writer.write_all(&self.memory_size.to_be_bytes())?;
+        writer.write_all(&(self.pages.len() as u32).to_be_bytes())?;
+        for (address, bytes) in &self.pages {
+            writer.write_all(&address.to_be_bytes())?;
+            writer.write_all(&(bytes.len() as u32).to_be_bytes())?;
+            writer.write_all(bytes)?;
+        }
+
+        writer.write_all(&(self.devices.len() as u32).to_be_bytes())?;
+        for (start, state) in &self.devices {
+            writer.write_all(&start.to_be_bytes())?;
+            writer.write_all(&(state.len() as u32).to_be_bytes())?;
+            writer.write_all(state)?;
+        }
+        writer.flush()
+    }
+
+    pub fn read_from<R: Read>(mut reader: R) -> Result<Self, SnapshotError> {
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        if magic != SNAPSHOT_MAGIC {
+            return Err(SnapshotError::NotASnapshot);
+        }
+        let version = read_array::<_, 1>(&mut reader)?[0];
+        if version != SNAPSHOT_VERSION {
+            return Err(SnapshotError::UnsupportedVersion(version));
+        }
+
+        let pc = u32::from_be_bytes(read_array(&mut reader)?);
+        let status = u16::from_be_bytes(read_array(&mut reader)?);
+        let cycles = u64::from_be_bytes(read_array(&mut reader)?);
+        let halted = read_array::<_, 1>(&mut reader)?[0] != 0;
+        let pending_interrupts = u16::from_be_bytes(read_array(&mut reader)?);
+        let mut registers = [0; 32];
+        for register in registers.iter_mut() {
+            *register = u32::from_be_bytes(read_array(&mut reader)?);
+        }
+
+        let memory_size = u32::from_be_bytes(read_array(&mut reader)?);
+        let page_count = u32::from_be_bytes(read_array(&mut reader)?);
+        let mut pages = Vec::new();
+        for _ in 0..page_count {
+            let address = u32::from_be_bytes(read_array(&mut reader)?);
+            let length = u32::from_be_bytes(read_array(&mut reader)?);
+            //NOTE: Checked before allocating so a corrupt length can't ask for gigabytes
+            if address as u64 + length as u64 > memory_size as u64 {
+                return Err(SnapshotError::InvalidPage(address));
+            }
+            let mut bytes = vec![0; length as usize];
+            reader.read_exact(&mut bytes)?;
+            pages.push((address, bytes));
+        }
+
+        let device_count = u32::from_be_bytes(read_array(&mut reader)?);
+        let mut devices = Vec::new();
+        for _ in 0..device_count {
+            let start = u32::from_be_bytes(read_array(&mut reader)?);
+            let length = u32::from_be_bytes(read_array(&mut reader)?);
+            //NOTE: There's no upper bound for a device state, so it only grows as far as the file actually goes
+            let mut state = Vec::new();
+            (&mut reader).take(length as u64).read_to_end(&mut state)?;
+            if state.len() != length as usize {
+                return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+            }
+            devices.push((start, state));
+        }
+
+        Ok(Self {
+            pc,
+            status,
+            cycles,
+            halted,
+            pending_interrupts,
+            registers,
+            memory_size,
+            pages,
+            devices,
+        })
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        self.write_to(BufWriter::new(File::create(path)?))
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, SnapshotError> {
+        Self::read_from(BufReader::new(File::open(path)?))
+    }
+}
+
+fn read_array<R: Read, const N: usize>(reader: &mut R) -> io::Result<[u8; N]> {
+    let mut bytes = [0; N];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::bus::{Bus, VIDEO_MEMORY_START};
+    use crate::cpu::{core::Cpu, register::Register};
+    use crate::machine::MachineConfig;
+    use crate::video::Video;
+
+    //NOTE: Counts D0 up forever
+    fn counting_cpu() -> Cpu {
+        let mut cpu = Cpu::new();
+        cpu.load_words(0x1000, &[0x80000105, 0x1, 0x80000114, 0x1000])
+            .unwrap();
+        cpu.set_pc(0x1000);
+        cpu
+    }
+
+    #[test]
+    fn test_only_used_pages_are_stored() {
+        let mut cpu = counting_cpu();
+        cpu.bus().write_dword(0x10_0000, 0xDEADBEEF).unwrap();
+
+        let snapshot = cpu.snapshot();
+        let addresses: Vec<u32> = snapshot.pages.iter().map(|(address, _)| *address).collect();
+        assert_eq!(addresses, vec![0x1000, 0x10_0000]);
+    }
+
+    #[test]
+    fn test_file_round_trip() {
+        let mut cpu = counting_cpu();
+        cpu.run_until(|cpu, _| cpu.register(Register::D0) == 5);
+        cpu.raise_interrupt(3);
+        let snapshot = cpu.snapshot();
+
+        let mut bytes = Vec::new();
+        snapshot.write_to(&mut bytes).unwrap();
+        assert_eq!(&bytes[..4], &SNAPSHOT_MAGIC);
+        assert_eq!(Snapshot::read_from(bytes.as_slice()).unwrap(), snapshot);
+    }
+
+    #[test]
+    fn test_restore_continues_where_the_snapshot_was_taken() {
+        let mut cpu = counting_cpu();
+        cpu.run_until(|cpu, _| cpu.register(Register::D0) == 5);
+        let snapshot = cpu.snapshot();
+        cpu.run_for_cycles(100);
+        let expected = (cpu.pc(), cpu.register(Register::D0), cpu.cycles());
+
+        let mut restored = Cpu::new();
+        restored.bus().write_dword(0x20_0000, 0x1234).unwrap();
+        restored.restore(&snapshot).unwrap();
+        assert_eq!(restored.register(Register::D0), 5);
+        assert_eq!(restored.cycles(), snapshot.cycles);
+        assert_eq!(restored.bus().read_dword(0x20_0000), Ok(0x0));
+
+        restored.run_for_cycles(100);
+        assert_eq!(
+            (
+                restored.pc(),
+                restored.register(Register::D0),
+                restored.cycles()
+            ),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_invalid_files() {
+        assert!(matches!(
+            Snapshot::read_from(&b"DTRC\x01"[..]),
+            Err(SnapshotError::NotASnapshot)
+        ));
+        assert!(matches!(
+            Snapshot::read_from(&b"DSNP\x01"[..]),
+            Err(SnapshotError::UnsupportedVersion(1))
+        ));
+        assert!(matches!(
+            Snapshot::read_from(&b"DSNP\x02\x00"[..]),
+            Err(SnapshotError::Io(_))
+        ));
+
+        let mut snapshot = counting_cpu().snapshot();
+        snapshot.memory_size = 0x1000;
+        assert!(matches!(
+            Cpu::new().restore(&snapshot),
+            Err(SnapshotError::MemorySize(0x1000))
+        ));
+    }
+
+    #[test]
+    fn test_restore_needs_the_same_devices() {
+        let config = MachineConfig::new().video(Video::new());
+        let mut cpu = Cpu::with_config(config).unwrap();
+        cpu.bus().write_byte(VIDEO_MEMORY_START, 0x42).unwrap();
+        let snapshot = cpu.snapshot();
+        assert_eq!(snapshot.devices.len(), 1);
+
+        let mut bytes = Vec::new();
+        snapshot.write_to(&mut bytes).unwrap();
+        assert_eq!(Snapshot::read_from(bytes.as_slice()).unwrap(), snapshot);
+
+        let mut without_video = counting_cpu();
+        let untouched = without_video.snapshot();
+        assert!(matches!(
+            without_video.restore(&snapshot),
+            Err(SnapshotError::DeviceState(VIDEO_MEMORY_START))
+        ));
+        assert_eq!(without_video.snapshot(), untouched);
+
+        let config = MachineConfig::new().video(Video::new());
+        let mut with_video = Cpu::with_config(config).unwrap();
+        assert!(matches!(
+            with_video.restore(&untouched),
+            Err(SnapshotError::DeviceState(VIDEO_MEMORY_START))
+        ));
+    }
+}
@@ -0,0 +1,490 @@
+use std::{
+    fmt::Display,
+    io::{self, Read, Write},
+};
+
+use crate::{
+    bus::{Access, MemoryAccess},
+    cpu::{disassembler::Instruction, opcode_size::OpcodeSize, register::Register},
+};
+
+//NOTE: Binary traces start with the magic and the version, every record after that is stored big endian as:
+//          pc: u32, present: u8 (bit 0 word, bit 1 extension word), word: u32, extension word: u32,
+//          status: u16, register count: u8, (register: u8, value: u32)*,
+//          access count: u16, (kind: u8 (bit 0 write, bits 1-2 size), address: u32, value: u32)*
+//      The word and the extension word are only stored if their bit is set.
+pub const TRACE_MAGIC: [u8; 4] = *b"DTRC";
+pub const TRACE_VERSION: u8 = 1;
+
+const WORD_PRESENT: u8 = 0x01;
+const EXTENSION_WORD_PRESENT: u8 = 0x02;
+const ACCESS_WRITE: u8 = 0x01;
+
+//NOTE: Everything an executed instruction did. The word is missing if the fetch faulted, the extension word if the
+//      opcode has none. Registers and accesses include the interrupt dispatch and exception entry of the step.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct TraceRecord {
+    pub address: u32,
+    pub word: Option<u32>,
+    pub extension_word: Option<u32>,
+    pub registers: Vec<(Register, u32)>,
+    pub accesses: Vec<MemoryAccess>,
+    //NOTE: The status register after the instruction
+    pub status: u16,
+}
+
+impl TraceRecord {
+    pub fn disassembly(&self) -> String {
+        match self.word {
+            Some(word) => Instruction::new(self.address, word, self.extension_word).to_string(),
+            None => "-".to_string(),
+        }
+    }
+}
+
+//NOTE: The line of the text trace, every field has a fixed position so two traces can be diffed line by line
+impl Display for TraceRecord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let word = self
+            .word
+            .map_or("????????".to_string(), |word| format!("{:08x}", word));
+        let extension_word = self
+            .extension_word
+            .map_or(String::new(), |word| format!("{:08x}", word));
+        let registers: Vec<String> = self
+            .registers
+            .iter()
+            .map(|(register, value)| format!("{}={:#010x}", register, value))
+            .collect();
+        let accesses: Vec<String> = self
+            .accesses
+            .iter()
+            .map(|access| access.to_string())
+            .collect();
+
+        write!(
+            f,
+            "{:#010x}  {} {:<8}  {:<28} SR={:#06x} regs[{}] mem[{}]",
+            self.address,
+            word,
+            extension_word,
+            self.disassembly(),
+            self.status,
+            registers.join(" "),
+            accesses.join(" ")
+        )
+    }
+}
+
+//NOTE: Gets every record of an executed instruction while it is installed on the cpu, see Cpu::set_tracer
+pub trait Tracer {
+    fn trace(&mut self, record: &TraceRecord);
+
+    //NOTE: Flushes buffered output and reports the first error writing the trace ran into
+    fn finish(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<F: FnMut(&TraceRecord)> Tracer for F {
+    fn trace(&mut self, record: &TraceRecord) {
+        self(record)
+    }
+}
+
+//NOTE: Writes one line per instruction, writing stops at the first error which finish reports
+pub struct TextTracer<W: Write> {
+    writer: W,
+    error: Option<io::Error>,
+}
+
+impl<W: Write> TextTracer<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            error: None,
+        }
+    }
+}
+
+impl<W: Write> Tracer for TextTracer<W> {
+    fn trace(&mut self, record: &TraceRecord) {
+        if self.error.is_none() {
+            self.error = writeln!(self.writer, "{}", record).err();
+        }
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        finish(&mut self.writer, &mut self.error)
+    }
+}
+
+//NOTE: Writes the compact binary format described at the top, writing stops at the first error which finish reports
+pub struct BinaryTracer<W: Write> {
+    writer: W,
+    error: Option<io::Error>,
+}
+
+impl<W: Write> BinaryTracer<W> {
+    pub fn new(mut writer: W) -> io::Result<Self> {
+        writer.write_all(&TRACE_MAGIC)?;
+        writer.write_all(&[TRACE_VERSION])?;
+
+        Ok(Self {
+            writer,
+            error: None,
+        })
+    }
+}
+
+impl<W: Write> Tracer for BinaryTracer<W> {
+    fn trace(&mut self, record: &TraceRecord) {
+        if self.error.is_none() {
+            self.error = self.writer.write_all(&encode_record(record)).err();
+        }
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        finish(&mut self.writer, &mut self.error)
+    }
+}
+
+fn finish<W: Write>(writer: &mut W, error: &mut Option<io::Error>) -> io::Result<()> {
+    if let Some(error) = error.take() {
+        return Err(error);
+    }
+    writer.flush()
+}
+
+fn encode_record(record: &TraceRecord) -> Vec<u8> {
+    let mut bytes = record.address.to_be_bytes().to_vec();
+
+    let mut present = 0x00;
+    if record.word.is_some() {
+        present |= WORD_PRESENT;
+    }
+    if record.extension_word.is_some() {
+        present |= EXTENSION_WORD_PRESENT;
+    }
+    bytes.push(present);
+    for word in [record.word, record.extension_word].into_iter().flatten() {
+        bytes.extend(word.to_be_bytes());
+    }
+
+    bytes.extend(record.status.to_be_bytes());
+
+    bytes.push(record.registers.len() as u8);
+    for (register, value) in &record.registers {
+        bytes.push(u32::from(*register) as u8);
+        bytes.extend(value.to_be_bytes());
+    }
+
+    bytes.extend((record.accesses.len() as u16).to_be_bytes());
+    for access in &record.accesses {
+        let mut kind = (u32::from(access.size) as u8) << 1;
+        if access.access == Access::Write {
+            kind |= ACCESS_WRITE;
+        }
+        bytes.push(kind);
+        bytes.extend(access.address.to_be_bytes());
+        bytes.extend(access.value.to_be_bytes());
+    }
+
+    bytes
+}
+
+//NOTE: Reads the records of a binary trace back, i.e. to print it as text or to diff two of them
+pub struct TraceReader<R: Read> {
+    reader: R,
+}
+
+impl<R: Read> TraceReader<R> {
+    pub fn new(mut reader: R) -> io::Result<Self> {
+        let mut header = [0x00; 5];
+        reader.read_exact(&mut header)?;
+
+        if header[..4] != TRACE_MAGIC {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "not a dielectric trace",
+            ));
+        }
+        if header[4] != TRACE_VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("unsupported trace version {}", header[4]),
+            ));
+        }
+
+        Ok(Self { reader })
+    }
+
+    //NOTE: Returns None at the end of the trace, a record that is cut off is an error
+    pub fn read_record(&mut self) -> io::Result<Option<TraceRecord>> {
+        let mut address = [0x00; 4];
+        match self.reader.read(&mut address[..1])? {
+            0 => return Ok(None),
+            _ => self.reader.read_exact(&mut address[1..])?,
+        }
+
+        let present = self.u8()?;
+        let word = if present & WORD_PRESENT != 0 {
+            Some(self.u32()?)
+        } else {
+            None
+        };
+        let extension_word = if present & EXTENSION_WORD_PRESENT != 0 {
+            Some(self.u32()?)
+        } else {
+            None
+        };
+
+        let status = self.u16()?;
+
+        let mut registers = vec![];
+        for _ in 0..self.u8()? {
+            let register = Register::new(self.u8()? as u32);
+            registers.push((register, self.u32()?));
+        }
+
+        let mut accesses = vec![];
+        for _ in 0..self.u16()? {
+            let kind = self.u8()?;
+            let access = if kind & ACCESS_WRITE != 0 {
+                Access::Write
+            } else {
+                Access::Read
+            };
+            accesses.push(MemoryAccess {
+                access,
+                size: OpcodeSize::new((kind >> 1) as u32 & 0x03),
+                address: self.u32()?,
+                value: self.u32()?,
+            });
+        }
+
+        Ok(Some(TraceRecord {
+            address: u32::from_be_bytes(address),
+            word,
+            extension_word,
+            registers,
+            accesses,
+            status,
+        }))
+    }
+
+    fn u8(&mut self) -> io::Result<u8> {
+        let mut bytes = [0x00; 1];
+        self.reader.read_exact(&mut bytes)?;
+        Ok(bytes[0])
+    }
+
+    fn u16(&mut self) -> io::Result<u16> {
+        let mut bytes = [0x00; 2];
+        self.reader.read_exact(&mut bytes)?;
+        Ok(u16::from_be_bytes(bytes))
+    }
+
+    fn u32(&mut self) -> io::Result<u32> {
+        let mut bytes = [0x00; 4];
+        self.reader.read_exact(&mut bytes)?;
+        Ok(u32::from_be_bytes(bytes))
+    }
+}
+
+impl<R: Read> Iterator for TraceReader<R> {
+    type Item = io::Result<TraceRecord>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_record().transpose()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{cell::RefCell, rc::Rc};
+
+    use super::*;
+    use crate::cpu::core::Cpu;
+
+    fn record() -> TraceRecord {
+        TraceRecord {
+            address: 0x00001000,
+            word: Some(0x80000105),
+            extension_word: Some(0x00000001),
+            registers: vec![(Register::D0, 0x00000001), (Register::A15, 0x00100000)],
+            accesses: vec![
+                MemoryAccess {
+                    address: 0x00002000,
+                    access: Access::Read,
+                    size: OpcodeSize::Word,
+                    value: 0xBEEF,
+                },
+                MemoryAccess {
+                    address: 0x000FFFFC,
+                    access: Access::Write,
+                    size: OpcodeSize::Dword,
+                    value: 0x00001008,
+                },
+            ],
+            status: 0x0004,
+        }
+    }
+
+    //NOTE: Counts D0 up in a loop and stores it through A0 with a post increment
+    fn looping_cpu() -> Cpu {
+        let mut cpu = Cpu::new();
+        cpu.load_words(
+            0x00001000,
+            &[
+                0x80000105,
+                0x00000001,
+                0x80000401 | (0x10 << 14),
+                0x80000114,
+                0x00001000,
+            ],
+        )
+        .unwrap();
+        cpu.set_pc(0x00001000);
+        cpu.set_register(Register::A0, 0x00004000);
+        cpu
+    }
+
+    #[test]
+    fn test_text_line() {
+        assert_eq!(
+            record().to_string(),
+            "0x00001000  80000105 00000001  ADD.DW D0,$00000001          SR=0x0004 \
+             regs[D0=0x00000001 A15=0x00100000] mem[R.W 0x00002000=0xbeef W.DW 0x000ffffc=0x1008]"
+        );
+
+        let faulted = TraceRecord {
+            word: None,
+            extension_word: None,
+            registers: vec![],
+            accesses: vec![],
+            ..record()
+        };
+        assert_eq!(
+            faulted.to_string(),
+            "0x00001000  ????????           -                            SR=0x0004 regs[] mem[]"
+        );
+    }
+
+    #[test]
+    fn test_binary_round_trip() {
+        let faulted = TraceRecord {
+            word: None,
+            extension_word: None,
+            ..record()
+        };
+
+        let mut tracer = BinaryTracer::new(vec![]).unwrap();
+        tracer.trace(&record());
+        tracer.trace(&faulted);
+        tracer.finish().unwrap();
+
+        let bytes = tracer.writer;
+        assert_eq!(&bytes[..5], b"DTRC\x01");
+
+        let records: Vec<TraceRecord> = TraceReader::new(bytes.as_slice())
+            .unwrap()
+            .collect::<io::Result<_>>()
+            .unwrap();
+        assert_eq!(records, vec![record(), faulted]);
+
+        assert!(TraceReader::new(&bytes[..bytes.len() - 1])
+            .unwrap()
+            .nth(1)
+            .unwrap()
+            .is_err());
+        assert!(TraceReader::new(b"ELF\x00\x01".as_slice()).is_err());
+    }
+
+    #[test]
+    fn test_cpu_records_every_instruction() {
+        let records = Rc::new(RefCell::new(vec![]));
+        let recorded = records.clone();
+
+        let mut cpu = looping_cpu();
+        cpu.set_tracer(Box::new(move |record: &TraceRecord| {
+            recorded.borrow_mut().push(record.clone())
+        }));
+        for _ in 0..4 {
+            cpu.step();
+        }
+
+        let records = records.borrow();
+        assert_eq!(records.len(), 4);
+
+        assert_eq!(records[0].address, 0x00001000);
+        assert_eq!(records[0].extension_word, Some(0x00000001));
+        assert_eq!(records[0].registers, vec![(Register::D0, 0x00000001)]);
+        assert!(records[0].accesses.is_empty());
+
+        assert_eq!(records[1].disassembly(), "MOVE.DW (A0)+,D0");
+        assert_eq!(records[1].extension_word, None);
+        assert_eq!(records[1].registers, vec![(Register::A0, 0x00004004)]);
+        assert_eq!(
+            records[1].accesses,
+            vec![MemoryAccess {
+                address: 0x00004000,
+                access: Access::Write,
+                size: OpcodeSize::Dword,
+                value: 0x00000001,
+            }]
+        );
+
+        assert_eq!(records[2].disassembly(), "JMP $00001000");
+        assert!(records[2].registers.is_empty());
+        assert_eq!(records[3].registers, vec![(Register::D0, 0x00000002)]);
+    }
+
+    #[test]
+    fn test_text_tracer_and_removing_it() {
+        let mut cpu = looping_cpu();
+        cpu.set_tracer(Box::new(TextTracer::new(vec![])));
+        cpu.step();
+        cpu.step();
+
+        let mut tracer = cpu.take_tracer().unwrap();
+        assert!(tracer.finish().is_ok());
+        assert!(cpu.take_tracer().is_none());
+
+        //NOTE: Without a tracer the bus doesn't log anything anymore
+        cpu.step();
+        assert!(cpu.bus().take_accesses().is_empty());
+    }
+
+    #[test]
+    fn test_trace_includes_the_exception_entry() {
+        let records = Rc::new(RefCell::new(vec![]));
+        let recorded = records.clone();
+
+        let mut cpu = Cpu::new();
+        cpu.load_words(0x00001000, &[0x000000FF]).unwrap();
+        cpu.load_words(0x00002008, &[0x00003000]).unwrap();
+        cpu.set_pc(0x00001000);
+        cpu.set_register(Register::A15, 0x00010000);
+        cpu.set_tracer(Box::new(move |record: &TraceRecord| {
+            recorded.borrow_mut().push(record.clone())
+        }));
+        cpu.step();
+
+        let records = records.borrow();
+        assert_eq!(records[0].disassembly(), "ILLEGAL");
+        assert_eq!(records[0].registers, vec![(Register::A15, 0x0000FFF8)]);
+        assert_eq!(
+            records[0]
+                .accesses
+                .iter()
+                .map(|access| (access.access, access.address))
+                .collect::<Vec<_>>(),
+            vec![
+                (Access::Read, 0x00002008),
+                (Access::Write, 0x0000FFFC),
+                (Access::Write, 0x0000FFF8),
+            ]
+        );
+    }
+}
@@ -0,0 +1,551 @@
+use std::{
+    cell::RefCell,
+    fs::File,
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    path::Path,
+    rc::Rc,
+};
+
+use crate::bus::{BusError, Device, VIDEO_MEMORY_END, VIDEO_MEMORY_START};
+
+pub const SCREEN_WIDTH: usize = 480;
+pub const SCREEN_HEIGHT: usize = 270;
+
+//NOTE: Offsets inside the video memory, see docs/Memory.md. The pixels start at offset 0, 3 bytes (R, G, B) per pixel
+//      in framebuffer mode and one color table index per pixel in color table mode.
+pub const VIDEO_MEMORY_SIZE: usize = (VIDEO_MEMORY_END - VIDEO_MEMORY_START) as usize;
+pub const MODE_REGISTER: u32 = 0x0007E000;
+//NOTE: Read only word holding the scanline the beam is on
+pub const SCANLINE_REGISTER: u32 = 0x0007E004;
+//NOTE: 256 dwords 0x00RRGGBB, the color table
+pub const PALETTE_START: u32 = 0x0007E100;
+pub const PALETTE_END: u32 = PALETTE_START + PALETTE_ENTRIES as u32 * 4;
+pub const PALETTE_ENTRIES: usize = 256;
+
+//NOTE: Bit 0 of the mode register, cleared it is the framebuffer mode
+pub const MODE_COLOR_TABLE: u8 = 0x01;
+
+//NOTE: The beam moves on to the next scanline every SCANLINE_CYCLES cycles and starts over at the top after the last
+//      one, there is no blanking period
+pub const SCANLINE_CYCLES: u32 = 512;
+
+pub type Palette = [[u8; 3]; PALETTE_ENTRIES];
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum VideoMode {
+    FrameBuffer,
+    ColorTable,
+}
+
+struct VideoState {
+    memory: Vec<u8>,
+    scanline: usize,
+    line_cycles: u32,
+    //NOTE: The palette every scanline was drawn with, taken when the beam entered the line
+    latched: Vec<Option<Palette>>,
+    //NOTE: The beam starts out on line 0 without ever entering it, until it moves on the program is still setting
+    //      the line up and writes to the mode register or the palette latch it again
+    power_on_line: bool,
+}
+
+impl VideoState {
+    fn palette(&self) -> Palette {
+        let mut palette = [[0x00; 3]; PALETTE_ENTRIES];
+        let entries = self.memory[PALETTE_START as usize..PALETTE_END as usize].chunks(4);
+        for (color, entry) in palette.iter_mut().zip(entries) {
+            color.copy_from_slice(&entry[1..]);
+        }
+        palette
+    }
+
+    //NOTE: The snapshot state is the video memory, the beam (scanline: u32, line cycles: u32, power on line: u8) and
+    //      for every scanline a flag telling if a palette is latched followed by the latched palette
+    fn save(&self) -> Vec<u8> {
+        let mut bytes = self.memory.clone();
+        bytes.extend((self.scanline as u32).to_be_bytes());
+        bytes.extend(self.line_cycles.to_be_bytes());
+        bytes.push(self.power_on_line as u8);
+        for latched in &self.latched {
+            match latched {
+                Some(palette) => {
+                    bytes.push(0x01);
+                    bytes.extend(palette.iter().flatten());
+                }
+                None => bytes.push(0x00),
+            }
+        }
+        bytes
+    }
+
+    fn load(bytes: &[u8]) -> Option<Self> {
+        let (memory, mut rest) = bytes.split_at_checked(VIDEO_MEMORY_SIZE)?;
+        let mut take = |count: usize| {
+            let (taken, remaining) = rest.split_at_checked(count)?;
+            rest = remaining;
+            Some(taken)
+        };
+
+        let scanline = u32::from_be_bytes(take(4)?.try_into().ok()?) as usize;
+        let line_cycles = u32::from_be_bytes(take(4)?.try_into().ok()?);
+        if scanline >= SCREEN_HEIGHT || line_cycles >= SCANLINE_CYCLES {
+            return None;
+        }
+        let power_on_line = match take(1)?[0] {
+            0x00 => false,
+            0x01 => true,
+            _ => return None,
+        };
+
+        let mut latched = vec![None; SCREEN_HEIGHT];
+        for line in latched.iter_mut() {
+            *line = match take(1)?[0] {
+                0x00 => None,
+                0x01 => {
+                    let mut palette = [[0x00; 3]; PALETTE_ENTRIES];
+                    palette
+                        .as_flattened_mut()
+                        .copy_from_slice(take(PALETTE_ENTRIES * 3)?);
+                    Some(palette)
+                }
+                _ => return None,
+            };
+        }
+        if !rest.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            memory: memory.to_vec(),
+            scanline,
+            line_cycles,
+            latched,
+            power_on_line,
+        })
+    }
+}
+
+//NOTE: The video memory of the console. Every byte of the region but the scanline register behaves like ram, the
+//      pixels and the mode register are only interpreted when a frame is rendered. Clones share the same memory so
+//      one can be mapped onto the bus while the other one renders.
+#[derive(Clone)]
+pub struct Video {
+    state: Rc<RefCell<VideoState>>,
+}
+
+impl Default for Video {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Video {
+    //NOTE: The palette starts out as RRRGGGBB, 3 bits of red, 3 bits of green and 2 bits of blue
+    pub fn new() -> Self {
+        let mut memory = vec![0x00; VIDEO_MEMORY_SIZE];
+        let entries = memory[PALETTE_START as usize..PALETTE_END as usize].chunks_mut(4);
+        for (index, entry) in entries.enumerate() {
+            entry[1..].copy_from_slice(&default_color(index as u8));
+        }
+
+        let mut state = VideoState {
+            memory,
+            scanline: 0,
+            line_cycles: 0,
+            latched: vec![None; SCREEN_HEIGHT],
+            power_on_line: true,
+        };
+        state.latched[0] = Some(state.palette());
+
+        Self {
+            state: Rc::new(RefCell::new(state)),
+        }
+    }
+
+    pub fn mode(&self) -> VideoMode {
+        match self.state.borrow().memory[MODE_REGISTER as usize] & MODE_COLOR_TABLE {
+            MODE_COLOR_TABLE => VideoMode::ColorTable,
+            _ => VideoMode::FrameBuffer,
+        }
+    }
+
+    pub fn scanline(&self) -> usize {
+        self.state.borrow().scanline
+    }
+
+    //NOTE: The color table as it is right now
+    pub fn palette(&self) -> Palette {
+        self.state.borrow().palette()
+    }
+
+    //NOTE: In color table mode every scanline uses the palette latched when the beam entered it so palette swaps
+    //      mid frame show up like on the real thing. Scanlines the beam hasn't reached yet in this frame use the
+    //      current palette.
+    pub fn render(&self) -> Frame {
+        let state = self.state.borrow();
+        let pixels = match self.mode() {
+            VideoMode::FrameBuffer => state.memory[..SCREEN_WIDTH * SCREEN_HEIGHT * 3].to_vec(),
+            VideoMode::ColorTable => {
+                let current = state.palette();
+                state.memory[..SCREEN_WIDTH * SCREEN_HEIGHT]
+                    .chunks(SCREEN_WIDTH)
+                    .zip(&state.latched)
+                    .flat_map(|(row, latched)| {
+                        let palette = latched.as_ref().unwrap_or(&current);
+                        row.iter().flat_map(|index| palette[*index as usize])
+                    })
+                    .collect()
+            }
+        };
+        Frame { pixels }
+    }
+}
+
+fn default_color(index: u8) -> [u8; 3] {
+    let scale = |value: u8, max: u8| (value as u32 * 255 / max as u32) as u8;
+    [
+        scale(index >> 5, 7),
+        scale((index >> 2) & 0x07, 7),
+        scale(index & 0x03, 3),
+    ]
+}
+
+impl Device for Video {
+    fn read_byte(&mut self, offset: u32) -> Result<u8, BusError> {
+        let state = self.state.borrow();
+        match offset {
+            SCANLINE_REGISTER => Ok((state.scanline >> 8) as u8),
+            _ if offset == SCANLINE_REGISTER + 1 => Ok(state.scanline as u8),
+            _ => state
+                .memory
+                .get(offset as usize)
+                .copied()
+                .ok_or(BusError::OutOfBounds(offset)),
+        }
+    }
+
+    fn write_byte(&mut self, offset: u32, value: u8) -> Result<(), BusError> {
+        if (SCANLINE_REGISTER..SCANLINE_REGISTER + 2).contains(&offset) {
+            return Err(BusError::ReadOnly(offset));
+        }
+
+        let mut state = self.state.borrow_mut();
+        let byte = state
+            .memory
+            .get_mut(offset as usize)
+            .ok_or(BusError::OutOfBounds(offset))?;
+        *byte = value;
+
+        let latches = offset == MODE_REGISTER || (PALETTE_START..PALETTE_END).contains(&offset);
+        if latches && state.power_on_line {
+            let (scanline, palette) = (state.scanline, state.palette());
+            state.latched[scanline] = Some(palette);
+        }
+        Ok(())
+    }
+
+    fn tick(&mut self, cycles: u32) -> u16 {
+        let mut state = self.state.borrow_mut();
+        state.line_cycles += cycles;
+        while state.line_cycles >= SCANLINE_CYCLES {
+            state.line_cycles -= SCANLINE_CYCLES;
+            state.scanline = (state.scanline + 1) % SCREEN_HEIGHT;
+            state.power_on_line = false;
+            //NOTE: A new frame starts, nothing of the last one is latched anymore
+            if state.scanline == 0 {
+                state.latched.fill(None);
+            }
+
+            let (scanline, palette) = (state.scanline, state.palette());
+            state.latched[scanline] = Some(palette);
+        }
+
+        0x00
+    }
+
+    fn save_state(&self) -> Option<Vec<u8>> {
+        Some(self.state.borrow().save())
+    }
+
+    fn restore_state(&mut self, state: &[u8]) -> bool {
+        match VideoState::load(state) {
+            Some(state) => {
+                *self.state.borrow_mut() = state;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+//NOTE: A rendered frame, SCREEN_WIDTH * SCREEN_HEIGHT pixels of 3 bytes (R, G, B) row by row
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Frame {
+    pixels: Vec<u8>,
+}
+
+impl Frame {
+    pub fn rgb(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    pub fn pixel(&self, x: usize, y: usize) -> [u8; 3] {
+        let offset = (y * SCREEN_WIDTH + x) * 3;
+        self.pixels[offset..offset + 3].try_into().unwrap()
+    }
+
+    //NOTE: Binary PPM (P6), almost every image viewer and diff tool reads it
+    pub fn write_ppm<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        write!(writer, "P6\n{} {}\n255\n", SCREEN_WIDTH, SCREEN_HEIGHT)?;
+        writer.write_all(&self.pixels)?;
+        writer.flush()
+    }
+
+    pub fn save_ppm<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        self.write_ppm(BufWriter::new(File::create(path)?))
+    }
+
+    //NOTE: Reads frames written by write_ppm back, i.e. reference images for tests. Only P6 images of the screen
+    //      size with 8 bits per channel are frames.
+    pub fn read_ppm<R: BufRead>(mut reader: R) -> io::Result<Self> {
+        let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message);
+
+        let mut header = Vec::new();
+        while header.len() < 4 {
+            let mut line = String::new();
+            if reader.read_line(&mut line)? == 0 {
+                return Err(invalid("the header of the image is incomplete"));
+            }
+            let line = line.split('#').next().unwrap_or_default();
+            header.extend(line.split_whitespace().map(str::to_string));
+        }
+
+        let expected = [
+            "P6".to_string(),
+            SCREEN_WIDTH.to_string(),
+            SCREEN_HEIGHT.to_string(),
+            "255".to_string(),
+        ];
+        if header != expected {
+            return Err(invalid(
+                "the image is no 480x270 P6 image with 8 bit channels",
+            ));
+        }
+
+        let mut pixels = vec![0x00; SCREEN_WIDTH * SCREEN_HEIGHT * 3];
+        reader.read_exact(&mut pixels)?;
+        Ok(Self { pixels })
+    }
+
+    pub fn load_ppm<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::read_ppm(BufReader::new(File::open(path)?))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::bus::Bus;
+    use crate::cpu::core::Cpu;
+    use crate::machine::MachineConfig;
+
+    fn cpu_with_video() -> (Cpu, Video) {
+        let video = Video::new();
+        let cpu = Cpu::with_config(MachineConfig::new().video(video.clone())).unwrap();
+        (cpu, video)
+    }
+
+    fn pixel_address(x: usize, y: usize, bytes_per_pixel: usize) -> u32 {
+        VIDEO_MEMORY_START + ((y * SCREEN_WIDTH + x) * bytes_per_pixel) as u32
+    }
+
+    #[test]
+    fn test_framebuffer_mode() {
+        let (mut cpu, video) = cpu_with_video();
+        assert_eq!(video.mode(), VideoMode::FrameBuffer);
+
+        cpu.bus()
+            .write_dword(pixel_address(0, 0, 3), 0xFF800011)
+            .unwrap();
+        cpu.load_bytes(pixel_address(479, 269, 3), &[0x01, 0x02, 0x03])
+            .unwrap();
+
+        let frame = video.render();
+        assert_eq!(frame.pixel(0, 0), [0xFF, 0x80, 0x00]);
+        assert_eq!(frame.pixel(1, 0), [0x11, 0x00, 0x00]);
+        assert_eq!(frame.pixel(479, 269), [0x01, 0x02, 0x03]);
+        assert_eq!(frame.rgb().len(), SCREEN_WIDTH * SCREEN_HEIGHT * 3);
+    }
+
+    #[test]
+    fn test_color_table_mode() {
+        let (mut cpu, video) = cpu_with_video();
+
+        cpu.bus()
+            .write_byte(VIDEO_MEMORY_START + MODE_REGISTER, MODE_COLOR_TABLE)
+            .unwrap();
+        cpu.load_bytes(pixel_address(10, 1, 1), &[0xE0, 0x1C, 0x03, 0xFF])
+            .unwrap();
+        assert_eq!(video.mode(), VideoMode::ColorTable);
+
+        let frame = video.render();
+        assert_eq!(frame.pixel(10, 1), [0xFF, 0x00, 0x00]);
+        assert_eq!(frame.pixel(11, 1), [0x00, 0xFF, 0x00]);
+        assert_eq!(frame.pixel(12, 1), [0x00, 0x00, 0xFF]);
+        assert_eq!(frame.pixel(13, 1), [0xFF, 0xFF, 0xFF]);
+        assert_eq!(frame.pixel(0, 0), [0x00, 0x00, 0x00]);
+    }
+
+    #[test]
+    fn test_palette_is_writable() {
+        let (mut cpu, video) = cpu_with_video();
+
+        cpu.bus()
+            .write_byte(VIDEO_MEMORY_START + MODE_REGISTER, MODE_COLOR_TABLE)
+            .unwrap();
+        cpu.bus()
+            .write_dword(VIDEO_MEMORY_START + PALETTE_START + 0x03 * 4, 0x00123456)
+            .unwrap();
+        cpu.load_bytes(pixel_address(0, 0, 1), &[0x03, 0xFF])
+            .unwrap();
+
+        assert_eq!(video.palette()[0x03], [0x12, 0x34, 0x56]);
+        assert_eq!(
+            cpu.bus()
+                .read_dword(VIDEO_MEMORY_START + PALETTE_START + 0xFF * 4),
+            Ok(0x00FFFFFF)
+        );
+        let frame = video.render();
+        assert_eq!(frame.pixel(0, 0), [0x12, 0x34, 0x56]);
+        assert_eq!(frame.pixel(1, 0), [0xFF, 0xFF, 0xFF]);
+    }
+
+    #[test]
+    fn test_palette_swap_mid_frame() {
+        let mut video = Video::new();
+        let set_color = |video: &mut Video, color: [u8; 3]| {
+            for (offset, value) in color.iter().enumerate() {
+                //NOTE: Entry 0x01, the first byte of an entry is unused
+                let offset = PALETTE_START + 4 + 1 + offset as u32;
+                video.write_byte(offset, *value).unwrap();
+            }
+        };
+        video.write_byte(MODE_REGISTER, MODE_COLOR_TABLE).unwrap();
+        for offset in 0..(SCREEN_WIDTH * SCREEN_HEIGHT) as u32 {
+            video.write_byte(offset, 0x01).unwrap();
+        }
+
+        //NOTE: Red for the first 100 scanlines of a full frame, blue after that
+        set_color(&mut video, [0xFF, 0x00, 0x00]);
+        video.tick(SCANLINE_CYCLES * 100);
+        set_color(&mut video, [0x00, 0x00, 0xFF]);
+        video.tick(SCANLINE_CYCLES * (SCREEN_HEIGHT as u32 - 101) + SCANLINE_CYCLES / 2);
+        assert_eq!(video.scanline(), SCREEN_HEIGHT - 1);
+
+        let frame = video.render();
+        assert_eq!(frame.pixel(0, 0), [0xFF, 0x00, 0x00]);
+        assert_eq!(frame.pixel(479, 1), [0xFF, 0x00, 0x00]);
+        assert_eq!(frame.pixel(0, 100), [0xFF, 0x00, 0x00]);
+        assert_eq!(frame.pixel(0, 101), [0x00, 0x00, 0xFF]);
+        assert_eq!(frame.pixel(479, 269), [0x00, 0x00, 0xFF]);
+    }
+
+    #[test]
+    fn test_palette_swap_across_frames() {
+        let mut video = Video::new();
+        let set_color = |video: &mut Video, color: [u8; 3]| {
+            for (offset, value) in color.iter().enumerate() {
+                let offset = PALETTE_START + 4 + 1 + offset as u32;
+                video.write_byte(offset, *value).unwrap();
+            }
+        };
+        video.write_byte(MODE_REGISTER, MODE_COLOR_TABLE).unwrap();
+        for offset in 0..(SCREEN_WIDTH * SCREEN_HEIGHT) as u32 {
+            video.write_byte(offset, 0x01).unwrap();
+        }
+
+        //NOTE: A red frame, the beam is back at the top when the palette turns blue
+        set_color(&mut video, [0xFF, 0x00, 0x00]);
+        video.tick(SCANLINE_CYCLES * SCREEN_HEIGHT as u32);
+        assert_eq!(video.scanline(), 0);
+        set_color(&mut video, [0x00, 0x00, 0xFF]);
+        video.tick(SCANLINE_CYCLES * 50);
+
+        //NOTE: Only the top of the new frame is latched, the red scanlines of the last frame are gone
+        let frame = video.render();
+        assert_eq!(frame.pixel(0, 0), [0xFF, 0x00, 0x00]);
+        assert_eq!(frame.pixel(0, 1), [0x00, 0x00, 0xFF]);
+        assert_eq!(frame.pixel(0, 50), [0x00, 0x00, 0xFF]);
+        assert_eq!(frame.pixel(0, 100), [0x00, 0x00, 0xFF]);
+        assert_eq!(frame.pixel(479, 269), [0x00, 0x00, 0xFF]);
+    }
+
+    #[test]
+    fn test_beam_follows_the_cpu() {
+        let (mut cpu, _) = cpu_with_video();
+        cpu.load_words(0x1000, &[0x80000105, 0x1, 0x80000114, 0x1000])
+            .unwrap();
+        cpu.set_pc(0x1000);
+
+        cpu.run_for_cycles(SCANLINE_CYCLES as u64 * 300);
+        let scanline = (cpu.cycles() / SCANLINE_CYCLES as u64) % SCREEN_HEIGHT as u64;
+        let register = VIDEO_MEMORY_START + SCANLINE_REGISTER;
+        assert_eq!(cpu.bus().read_word(register), Ok(scanline as u16));
+        assert_eq!(
+            cpu.bus().write_word(register, 0x0000),
+            Err(BusError::ReadOnly(register))
+        );
+    }
+
+    #[test]
+    fn test_ppm_round_trip() {
+        let (mut cpu, video) = cpu_with_video();
+        cpu.load_bytes(pixel_address(5, 5, 3), &[0x12, 0x34, 0x56])
+            .unwrap();
+        let frame = video.render();
+
+        let mut bytes = Vec::new();
+        frame.write_ppm(&mut bytes).unwrap();
+        assert!(bytes.starts_with(b"P6\n480 270\n255\n"));
+        assert_eq!(Frame::read_ppm(bytes.as_slice()).unwrap(), frame);
+
+        let commented = [b"P6\n# reference\n480 270 255\n".as_slice(), frame.rgb()].concat();
+        assert_eq!(Frame::read_ppm(commented.as_slice()).unwrap(), frame);
+        assert!(Frame::read_ppm(&b"P6\n640 480\n255\n"[..]).is_err());
+    }
+
+    #[test]
+    fn test_snapshot_keeps_latched_palettes() {
+        let (mut cpu, video) = cpu_with_video();
+        cpu.bus()
+            .write_byte(VIDEO_MEMORY_START + MODE_REGISTER, MODE_COLOR_TABLE)
+            .unwrap();
+        cpu.bus().write_byte(pixel_address(0, 0, 1), 0x01).unwrap();
+        cpu.bus()
+            .write_byte(pixel_address(0, 200, 1), 0x01)
+            .unwrap();
+
+        //NOTE: Entry 0x01 turns from RRRGGGBB's dark blue to white once the beam is on line 100
+        let mut moved = video.clone();
+        moved.tick(SCANLINE_CYCLES * 100 + 7);
+        cpu.load_bytes(
+            VIDEO_MEMORY_START + PALETTE_START + 4,
+            &[0x00, 0xFF, 0xFF, 0xFF],
+        )
+        .unwrap();
+        let snapshot = cpu.snapshot();
+
+        let (mut restored, restored_video) = cpu_with_video();
+        restored.restore(&snapshot).unwrap();
+        assert_eq!(restored_video.scanline(), 100);
+        assert_eq!(restored_video.render(), video.render());
+        assert_eq!(restored_video.render().pixel(0, 0), [0x00, 0x00, 0x55]);
+        assert_eq!(restored_video.render().pixel(0, 200), [0xFF, 0xFF, 0xFF]);
+
+        let mut rejecting = restored_video.clone();
+        assert!(!rejecting.restore_state(&[0x00; 16]));
+        let mut truncated = video.save_state().unwrap();
+        truncated.pop();
+        assert!(!rejecting.restore_state(&truncated));
+        assert_eq!(restored_video.render(), video.render());
+    }
+}
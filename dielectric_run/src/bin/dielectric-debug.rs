@@ -0,0 +1,81 @@
+use std::{
+    io::{self, BufRead, Write},
+    process::ExitCode,
+};
+
+use dielectric_run::{
+    debugger::{Command, Debugger},
+    options::{self, Options, OptionsError},
+    program,
+};
+
+fn main() -> ExitCode {
+    let options = match Options::parse(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(OptionsError::Help) => {
+            println!("{}", options::usage("dielectric-debug"));
+            return ExitCode::SUCCESS;
+        }
+        Err(error) => {
+            eprintln!("error: {}\n\n{}", error, options::usage("dielectric-debug"));
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let (cpu, video) = match program::load_cpu(&options) {
+        Ok(machine) => machine,
+        Err(error) => {
+            eprintln!("error: {}", error);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mut debugger = Debugger::new(cpu);
+    let mut last_command: Option<Command> = None;
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+
+    print!(
+        "{}",
+        debugger.execute(&Command::Disassemble {
+            address: None,
+            count: 1
+        })
+    );
+    loop {
+        print!("(ddb) ");
+        io::stdout().flush().unwrap();
+
+        let Some(Ok(line)) = lines.next() else {
+            break;
+        };
+
+        //NOTE: Like gdb an empty line repeats the last command
+        let command = if line.trim().is_empty() {
+            match &last_command {
+                Some(command) => Ok(command.clone()),
+                None => continue,
+            }
+        } else {
+            Command::parse(&line)
+        };
+
+        match command {
+            Ok(Command::Quit) => break,
+            Ok(command) => {
+                print!("{}", debugger.execute(&command));
+                last_command = Some(command);
+            }
+            Err(error) => println!("{}", error),
+        }
+    }
+
+    if let Err(error) = program::finish_trace(debugger.cpu())
+        .and_then(|_| program::save_snapshot(debugger.cpu(), &options))
+        .and_then(|_| program::save_frame(video.as_ref(), &options))
+    {
+        eprintln!("error: {}", error);
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,80 @@
+use std::process::ExitCode;
+
+use dielectric_run::{
+    options::{parse_format, parse_number, Format, OptionsError},
+    program, report,
+};
+
+const USAGE: &str = "\
+usage: disasm [OPTIONS] <IMAGE>
+
+options:
+    --format <bin|hex>        bin: raw big endian bytes (default)
+                              hex: 32 bit words in hex like the assembler prints them
+    --load <ADDR>             address the image is loaded at, branch targets are relative to it (default 0x00000000)
+    -h, --help                prints this message
+
+numbers are decimal or hex with a 0x or $ prefix";
+
+struct Arguments {
+    image: String,
+    format: Format,
+    load_address: u32,
+}
+
+fn parse_arguments() -> Result<Arguments, OptionsError> {
+    let mut format = Format::Binary;
+    let mut load_address = 0x00000000;
+    let mut image = None;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(OptionsError::MissingValue(arg.clone()));
+
+        match arg.as_str() {
+            "-h" | "--help" => return Err(OptionsError::Help),
+            "--format" => format = parse_format(&value()?)?,
+            "--load" => load_address = parse_number(&value()?)?,
+            _ if arg.starts_with('-') => return Err(OptionsError::UnknownOption(arg)),
+            _ => image = Some(arg),
+        }
+    }
+
+    Ok(Arguments {
+        image: image.ok_or(OptionsError::MissingProgram)?,
+        format,
+        load_address,
+    })
+}
+
+fn main() -> ExitCode {
+    let arguments = match parse_arguments() {
+        Ok(arguments) => arguments,
+        Err(OptionsError::Help) => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Err(error) => {
+            eprintln!("error: {}\n\n{}", error, USAGE);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let contents = match std::fs::read(&arguments.image) {
+        Ok(contents) => contents,
+        Err(error) => {
+            eprintln!("error: failed to read {}, {}", arguments.image, error);
+            return ExitCode::FAILURE;
+        }
+    };
+    let bytes = match program::decode(arguments.format, contents) {
+        Ok(bytes) => bytes,
+        Err(error) => {
+            eprintln!("error: {}", error);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    print!("{}", report::disassembly(&bytes, arguments.load_address));
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,530 @@
+use std::collections::VecDeque;
+
+use dielectric_cpu::{
+    bus::WatchKind,
+    cpu::{
+        core::{Cpu, Step},
+        disassembler::Instruction,
+        register::Register,
+    },
+};
+
+use crate::{options::parse_number, report, runner::StopReason};
+
+//NOTE: Continue and until give the prompt back after this many instructions even if nothing stopped them
+pub const CONTINUE_LIMIT: u64 = 1_000_000;
+const HISTORY_LENGTH: usize = 3;
+const DEFAULT_MEMORY_LENGTH: u32 = 64;
+const DEFAULT_LISTING_LENGTH: usize = 5;
+
+pub const HELP: &str = "\
+commands:
+    s, step [N]               executes N instructions (default 1)
+    c, continue               runs until an exception, an interrupt or the cpu halts
+    u, until <ADDR>           runs until the pc reaches ADDR
+    r, regs                   prints the registers and flags
+    m, mem <ADDR> [LEN]       prints LEN bytes of memory starting at ADDR (default 64)
+    d, disasm [ADDR] [N]      lists N instructions at ADDR (default around the pc)
+    set <REG|PC> <VALUE>      writes a register or the pc
+    b, break <ADDR>           sets a breakpoint
+    w, watch [r|w|rw] <ADDR> [LEN]
+                              watches LEN bytes (default 1) for reads, writes or both (default)
+    delete <ADDR>             removes the breakpoint and watchpoints at ADDR
+    i, info                   lists the breakpoints and watchpoints
+    h, help                   prints this message
+    q, quit                   leaves the debugger
+
+an empty line repeats the last command";
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Command {
+    Step(u64),
+    Continue,
+    Until(u32),
+    Registers,
+    Memory {
+        address: u32,
+        length: u32,
+    },
+    Disassemble {
+        address: Option<u32>,
+        count: usize,
+    },
+    SetRegister {
+        register: Register,
+        value: u32,
+    },
+    SetPc(u32),
+    Break(u32),
+    Watch {
+        address: u32,
+        length: u32,
+        kind: WatchKind,
+    },
+    Delete(u32),
+    Info,
+    Help,
+    Quit,
+}
+
+impl Command {
+    pub fn parse(line: &str) -> Result<Command, String> {
+        let mut words = line.split_whitespace();
+        let Some(command) = words.next() else {
+            return Err("no command given".to_string());
+        };
+        let arguments: Vec<&str> = words.collect();
+        let number = |index: usize| -> Result<Option<u32>, String> {
+            arguments
+                .get(index)
+                .map(|argument| parse_number(argument).map_err(|error| error.to_string()))
+                .transpose()
+        };
+        let required = |index: usize| -> Result<u32, String> {
+            number(index)?.ok_or(format!("{} expects more arguments", command))
+        };
+
+        let parsed = match command {
+            "s" | "step" => Command::Step(number(0)?.unwrap_or(1) as u64),
+            "c" | "continue" => Command::Continue,
+            "u" | "until" => Command::Until(required(0)?),
+            "r" | "regs" => Command::Registers,
+            "m" | "mem" => Command::Memory {
+                address: required(0)?,
+                length: number(1)?.unwrap_or(DEFAULT_MEMORY_LENGTH),
+            },
+            "d" | "disasm" => Command::Disassemble {
+                address: number(0)?,
+                count: number(1)?.map_or(DEFAULT_LISTING_LENGTH, |count| count as usize),
+            },
+            "set" => {
+                let target = arguments
+                    .first()
+                    .ok_or("set expects a register and a value")?;
+                let value = required(1)?;
+                if target.eq_ignore_ascii_case("pc") {
+                    Command::SetPc(value)
+                } else {
+                    Command::SetRegister {
+                        register: parse_register(target)?,
+                        value,
+                    }
+                }
+            }
+            "b" | "break" => Command::Break(required(0)?),
+            "w" | "watch" => {
+                //NOTE: The kind is optional so the numbers shift by one if it is there
+                let kind = match arguments.first().copied() {
+                    Some("r") => Some(WatchKind::Read),
+                    Some("w") => Some(WatchKind::Write),
+                    Some("rw") => Some(WatchKind::ReadWrite),
+                    _ => None,
+                };
+                let first = kind.is_some() as usize;
+                Command::Watch {
+                    address: required(first)?,
+                    length: number(first + 1)?.unwrap_or(1),
+                    kind: kind.unwrap_or(WatchKind::ReadWrite),
+                }
+            }
+            "delete" => Command::Delete(required(0)?),
+            "i" | "info" => Command::Info,
+            "h" | "help" => Command::Help,
+            "q" | "quit" => Command::Quit,
+            _ => return Err(format!("unknown command {}, try help", command)),
+        };
+
+        Ok(parsed)
+    }
+}
+
+//NOTE: SP is accepted as an alias for A15
+fn parse_register(name: &str) -> Result<Register, String> {
+    if name.eq_ignore_ascii_case("sp") {
+        return Ok(Register::A15);
+    }
+
+    (0..32)
+        .map(Register::new)
+        .find(|register| register.to_string().eq_ignore_ascii_case(name))
+        .ok_or(format!("{} is not a register", name))
+}
+
+pub struct Debugger {
+    cpu: Cpu,
+    //NOTE: Addresses of the last executed instructions, shown before the pc in listings
+    history: VecDeque<u32>,
+}
+
+impl Debugger {
+    pub fn new(cpu: Cpu) -> Self {
+        Self {
+            cpu,
+            history: VecDeque::with_capacity(HISTORY_LENGTH),
+        }
+    }
+
+    pub fn cpu(&mut self) -> &mut Cpu {
+        &mut self.cpu
+    }
+
+    //NOTE: Returns the text to show for the command
+    pub fn execute(&mut self, command: &Command) -> String {
+        match command {
+            Command::Step(count) => {
+                let mut output = String::new();
+                for _ in 0..*count {
+                    let step = self.step();
+                    output.push_str(&self.describe_step(&step));
+                    if self.cpu.is_halted() {
+                        break;
+                    }
+                }
+                output.push_str(&self.status_line());
+                output
+            }
+            Command::Continue => self.run(None),
+            Command::Until(address) => self.run(Some(*address)),
+            Command::Registers => report::registers(&self.cpu),
+            Command::Memory { address, length } => {
+                let end = address.saturating_add(*length);
+                report::memory(self.cpu.bus(), *address..end)
+            }
+            Command::Disassemble { address, count } => self.listing(*address, *count),
+            Command::SetRegister { register, value } => {
+                self.cpu.set_register(*register, *value);
+                format!("{} = {:#010x}\n", register, value)
+            }
+            Command::SetPc(value) => {
+                self.cpu.set_pc(*value);
+                format!("PC = {:#010x}\n", value)
+            }
+            Command::Break(address) => {
+                self.cpu.add_breakpoint(*address);
+                format!("breakpoint at {:#010x}\n", address)
+            }
+            Command::Watch {
+                address,
+                length,
+                kind,
+            } => {
+                let end = address.saturating_add((*length).max(1));
+                self.cpu.add_watchpoint(*address..end, *kind);
+                format!(
+                    "watchpoint {:?} at {:#010x}..{:#010x}\n",
+                    kind, address, end
+                )
+            }
+            Command::Delete(address) => {
+                let breakpoint = self.cpu.remove_breakpoint(*address);
+                let watchpoints = self.cpu.remove_watchpoints(*address);
+                if breakpoint || watchpoints {
+                    format!("deleted {:#010x}\n", address)
+                } else {
+                    format!("nothing set at {:#010x}\n", address)
+                }
+            }
+            Command::Info => {
+                let mut output = String::new();
+                for address in self.cpu.breakpoints() {
+                    output.push_str(&format!("breakpoint {:#010x}\n", address));
+                }
+                for watchpoint in self.cpu.bus().watchpoints() {
+                    output.push_str(&format!(
+                        "watchpoint {:?} {:#010x}..{:#010x}\n",
+                        watchpoint.kind, watchpoint.range.start, watchpoint.range.end
+                    ));
+                }
+                output
+            }
+            Command::Help => format!("{}\n", HELP),
+            Command::Quit => String::new(),
+        }
+    }
+
+    fn step(&mut self) -> Step {
+        let step = self.cpu.step();
+        if self.history.len() == HISTORY_LENGTH {
+            self.history.pop_front();
+        }
+        self.history.push_back(step.address);
+        step
+    }
+
+    fn run(&mut self, stop_at: Option<u32>) -> String {
+        let mut reason = None;
+        for _ in 0..CONTINUE_LIMIT {
+            let step = self.step();
+
+            reason = if self.cpu.is_halted() {
+                Some(StopReason::Halted)
+            } else if let Some(event) = step.event {
+                Some(StopReason::Event(event))
+            } else if let Some(exception) = step.exception {
+                Some(StopReason::Exception(exception))
+            } else if let Some(line) = step.interrupt {
+                Some(StopReason::Interrupt(line))
+            } else if stop_at == Some(self.cpu.pc()) {
+                Some(StopReason::Address(self.cpu.pc()))
+            } else {
+                None
+            };
+
+            if reason.is_some() {
+                break;
+            }
+        }
+
+        let reason = reason.unwrap_or(StopReason::InstructionLimit);
+        format!("stopped: {}\n{}", reason, self.status_line())
+    }
+
+    fn describe_step(&mut self, step: &Step) -> String {
+        let mut output = self.listing_line(step.address);
+        if let Some(line) = step.interrupt {
+            output.push_str(&format!("    interrupt request {} dispatched\n", line));
+        }
+        if let Some(exception) = step.exception {
+            output.push_str(&format!("    exception: {}\n", exception));
+        }
+        if self.cpu.is_halted() {
+            output.push_str("    cpu halted\n");
+        }
+        if let Some(event) = step.event {
+            output.push_str(&format!("    {}\n", event));
+        }
+        output
+    }
+
+    fn status_line(&mut self) -> String {
+        let next = self.listing_line(self.cpu.pc());
+        format!("{}{}\n", next, report::flags(self.cpu.status()))
+    }
+
+    //NOTE: Without an address the listing starts with the last executed instructions and continues at the pc
+    fn listing(&mut self, address: Option<u32>, count: usize) -> String {
+        let mut output = String::new();
+        let mut address = match address {
+            Some(address) => address,
+            None => {
+                let pc = self.cpu.pc();
+                for previous in self.history.clone() {
+                    if previous != pc {
+                        output.push_str(&self.listing_line(previous));
+                    }
+                }
+                pc
+            }
+        };
+
+        for _ in 0..count {
+            output.push_str(&self.listing_line(address));
+            let length = Instruction::read(self.cpu.bus(), address)
+                .map_or(4, |instruction| instruction.length());
+            let Some(next) = address.checked_add(length) else {
+                break;
+            };
+            address = next;
+        }
+
+        output
+    }
+
+    fn listing_line(&mut self, address: u32) -> String {
+        let marker = if address == self.cpu.pc() { "=>" } else { "  " };
+        let instruction = match Instruction::read(self.cpu.bus(), address) {
+            Ok(instruction) => instruction,
+            Err(error) => return format!("{} {:#010x}  {}\n", marker, address, error),
+        };
+
+        let extension = match (instruction.length(), instruction.extension_word) {
+            (8, Some(extension)) => format!("{:08x}", extension),
+            (8, None) => "????????".to_string(),
+            _ => String::new(),
+        };
+
+        format!(
+            "{} {:#010x}  {:08x} {:<8}  {}\n",
+            marker, address, instruction.word, extension, instruction
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use dielectric_cpu::cpu::exception::Exception;
+
+    use super::*;
+
+    //NOTE: add.dw D0, #1 followed by jmp #0x1000
+    const COUNTING_LOOP: [u32; 4] = [0x80000105, 0x00000001, 0x80000114, 0x00001000];
+
+    fn debugger() -> Debugger {
+        let mut cpu = Cpu::new();
+        cpu.load_words(0x00001000, &COUNTING_LOOP).unwrap();
+        //NOTE: An illegal instruction to run into
+        cpu.load_words(0x00002000, &[0x000000FF]).unwrap();
+        cpu.set_pc(0x00001000);
+        cpu.set_register(Register::A15, 0x00100000);
+        Debugger::new(cpu)
+    }
+
+    #[test]
+    fn test_parse_breakpoint_commands() {
+        assert_eq!(Command::parse("b 0x1008"), Ok(Command::Break(0x1008)));
+        assert_eq!(
+            Command::parse("watch 0x2000"),
+            Ok(Command::Watch {
+                address: 0x2000,
+                length: 1,
+                kind: WatchKind::ReadWrite
+            })
+        );
+        assert_eq!(
+            Command::parse("w w 0x2000 4"),
+            Ok(Command::Watch {
+                address: 0x2000,
+                length: 4,
+                kind: WatchKind::Write
+            })
+        );
+        assert_eq!(Command::parse("delete 8"), Ok(Command::Delete(8)));
+        assert!(Command::parse("watch r").is_err());
+    }
+
+    #[test]
+    fn test_parse_commands() {
+        assert_eq!(Command::parse("s"), Ok(Command::Step(1)));
+        assert_eq!(Command::parse("step 0x10"), Ok(Command::Step(16)));
+        assert_eq!(Command::parse("until $1008"), Ok(Command::Until(0x1008)));
+        assert_eq!(
+            Command::parse("m 0x1000"),
+            Ok(Command::Memory {
+                address: 0x1000,
+                length: DEFAULT_MEMORY_LENGTH
+            })
+        );
+        assert_eq!(
+            Command::parse("d"),
+            Ok(Command::Disassemble {
+                address: None,
+                count: DEFAULT_LISTING_LENGTH
+            })
+        );
+        assert_eq!(
+            Command::parse("set sp 0x2000"),
+            Ok(Command::SetRegister {
+                register: Register::A15,
+                value: 0x2000
+            })
+        );
+        assert_eq!(
+            Command::parse("set d12 7"),
+            Ok(Command::SetRegister {
+                register: Register::D12,
+                value: 7
+            })
+        );
+        assert_eq!(Command::parse("set PC 8"), Ok(Command::SetPc(8)));
+        assert!(Command::parse("until").is_err());
+        assert!(Command::parse("set X3 1").is_err());
+        assert!(Command::parse("jump").is_err());
+        assert!(Command::parse("   ").is_err());
+    }
+
+    #[test]
+    fn test_step_and_until() {
+        let mut debugger = debugger();
+
+        let output = debugger.execute(&Command::Step(2));
+        assert_eq!(debugger.cpu().pc(), 0x00001000);
+        assert_eq!(debugger.cpu().register(Register::D0), 1);
+        assert!(output.starts_with("   0x00001000  80000105 00000001"));
+        assert!(output.contains("=> 0x00001000"));
+
+        let output = debugger.execute(&Command::Until(0x00001008));
+        assert_eq!(output.lines().next(), Some("stopped: reached 0x00001008"));
+        assert_eq!(debugger.cpu().register(Register::D0), 2);
+    }
+
+    #[test]
+    fn test_continue_stops_at_exceptions() {
+        let mut debugger = debugger();
+        debugger.execute(&Command::SetPc(0x00002000));
+
+        let output = debugger.execute(&Command::Continue);
+
+        assert_eq!(
+            output.lines().next().unwrap(),
+            format!("stopped: {}", Exception::IllegalInstruction)
+        );
+    }
+
+    #[test]
+    fn test_listing_shows_history() {
+        let mut debugger = debugger();
+        debugger.execute(&Command::Step(1));
+
+        let output = debugger.execute(&Command::Disassemble {
+            address: None,
+            count: 2,
+        });
+        let lines: Vec<&str> = output.lines().collect();
+
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("   0x00001000"));
+        assert!(lines[0].ends_with("ADD.DW D0,$00000001"));
+        assert!(lines[1].starts_with("=> 0x00001008  80000114 00001000"));
+        assert!(lines[1].ends_with("JMP $00001000"));
+        assert!(lines[2].starts_with("   0x00001010  00000000"));
+    }
+
+    #[test]
+    fn test_memory_and_registers() {
+        let mut debugger = debugger();
+        debugger.execute(&Command::SetRegister {
+            register: Register::D3,
+            value: 0xCAFE,
+        });
+
+        assert!(debugger
+            .execute(&Command::Registers)
+            .contains("D3  0x0000cafe"));
+        assert_eq!(
+            debugger.execute(&Command::Memory {
+                address: 0x00001000,
+                length: 4
+            }),
+            "0x00001000  80 00 01 05\n"
+        );
+    }
+
+    #[test]
+    fn test_continue_stops_at_breakpoints_and_watchpoints() {
+        let mut debugger = debugger();
+        debugger.execute(&Command::Break(0x00001008));
+        debugger.execute(&Command::Watch {
+            address: 0x00100000 - 4,
+            length: 4,
+            kind: WatchKind::Write,
+        });
+        assert_eq!(
+            debugger.execute(&Command::Info),
+            "breakpoint 0x00001008\nwatchpoint Write 0x000ffffc..0x00100000\n"
+        );
+
+        let output = debugger.execute(&Command::Continue);
+        assert_eq!(
+            output.lines().next(),
+            Some("stopped: breakpoint at 0x00001008")
+        );
+
+        debugger.execute(&Command::Delete(0x00001008));
+        debugger.execute(&Command::SetPc(0x00002000));
+        let output = debugger.execute(&Command::Continue);
+        assert_eq!(
+            output.lines().next(),
+            Some("stopped: write watchpoint at 0x000ffffc")
+        );
+    }
+}
@@ -0,0 +1,536 @@
+use std::{
+    io::{self, Read, Write},
+    net::{TcpListener, TcpStream},
+};
+
+use dielectric_cpu::{
+    bus::{Bus, WatchKind},
+    cpu::{core::Cpu, exception::Exception, register::Register},
+};
+
+//NOTE: Register numbers as gdb sees them, D0-D15 are 0-15, A0-A15 are 16-31 followed by the pc and the status
+//      register. Every register is transferred as 32 bit big endian value.
+pub const REGISTER_COUNT: u32 = 34;
+const PC_REGISTER: u32 = 32;
+const SR_REGISTER: u32 = 33;
+
+const SIGINT: u8 = 2;
+const SIGILL: u8 = 4;
+const SIGTRAP: u8 = 5;
+const SIGABRT: u8 = 6;
+const SIGBUS: u8 = 10;
+
+//NOTE: How many instructions run between checks for an interrupt request (ctrl-c) from gdb
+const INTERRUPT_CHECK_INTERVAL: u64 = 4096;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Reply {
+    Packet(String),
+    //NOTE: Sends the packet and ends the session
+    Close(Option<String>),
+}
+
+pub struct GdbStub {
+    cpu: Cpu,
+}
+
+impl GdbStub {
+    pub fn new(cpu: Cpu) -> Self {
+        Self { cpu }
+    }
+
+    pub fn cpu(&mut self) -> &mut Cpu {
+        &mut self.cpu
+    }
+
+    pub fn into_cpu(self) -> Cpu {
+        self.cpu
+    }
+
+    //NOTE: Handles the contents of a single packet, interrupted is polled while the cpu runs and stops it once it
+    //      returns true. Unsupported packets get the empty reply like the protocol asks for.
+    pub fn handle(&mut self, packet: &str, interrupted: &mut dyn FnMut() -> bool) -> Reply {
+        let Some(command) = packet.chars().next() else {
+            return Reply::Packet(String::new());
+        };
+        let arguments = &packet[command.len_utf8()..];
+
+        let reply = match command {
+            '?' => stop_reply(SIGTRAP),
+            'g' => (0..REGISTER_COUNT)
+                .map(|register| format!("{:08x}", self.read_register(register)))
+                .collect(),
+            'G' => self.write_registers(arguments),
+            'p' => match parse_hex(arguments) {
+                Some(register) if register < REGISTER_COUNT => {
+                    format!("{:08x}", self.read_register(register))
+                }
+                _ => error(0x01),
+            },
+            'P' => self.write_register(arguments),
+            'm' => self.read_memory(arguments),
+            'M' => self.write_memory(arguments),
+            's' => {
+                if let Some(address) = parse_hex(arguments) {
+                    self.cpu.set_pc(address);
+                }
+                let signal = self.step().unwrap_or(SIGTRAP);
+                stop_reply(signal)
+            }
+            'c' => {
+                if let Some(address) = parse_hex(arguments) {
+                    self.cpu.set_pc(address);
+                }
+                stop_reply(self.resume(interrupted))
+            }
+            'Z' => self.breakpoint(arguments, true),
+            'z' => self.breakpoint(arguments, false),
+            'H' => "OK".to_string(),
+            'q' => self.query(arguments),
+            'D' => return Reply::Close(Some("OK".to_string())),
+            'k' => return Reply::Close(None),
+            _ => String::new(),
+        };
+
+        Reply::Packet(reply)
+    }
+
+    fn query(&self, query: &str) -> String {
+        if query.starts_with("Supported") {
+            "PacketSize=1000".to_string()
+        } else if query == "Attached" {
+            "1".to_string()
+        } else if query == "C" {
+            "QC1".to_string()
+        } else if query == "fThreadInfo" {
+            "m1".to_string()
+        } else if query == "sThreadInfo" {
+            "l".to_string()
+        } else {
+            String::new()
+        }
+    }
+
+    fn read_register(&self, register: u32) -> u32 {
+        match register {
+            PC_REGISTER => self.cpu.pc(),
+            SR_REGISTER => self.cpu.status().status_bits() as u32,
+            register => self.cpu.register(Register::new(register)),
+        }
+    }
+
+    fn set_register(&mut self, register: u32, value: u32) {
+        match register {
+            PC_REGISTER => self.cpu.set_pc(value),
+            SR_REGISTER => self.cpu.set_status(value as u16),
+            register => self.cpu.set_register(Register::new(register), value),
+        }
+    }
+
+    fn write_registers(&mut self, values: &str) -> String {
+        if values.len() != REGISTER_COUNT as usize * 8 {
+            return error(0x01);
+        }
+
+        let mut parsed = vec![];
+        for register in 0..REGISTER_COUNT as usize {
+            //NOTE: The packet might not be ascii, a slice splitting a character is an error like any other bad digit
+            match values
+                .get(register * 8..register * 8 + 8)
+                .and_then(parse_hex)
+            {
+                Some(value) => parsed.push(value),
+                None => return error(0x01),
+            }
+        }
+        for (register, value) in parsed.into_iter().enumerate() {
+            self.set_register(register as u32, value);
+        }
+
+        "OK".to_string()
+    }
+
+    fn write_register(&mut self, arguments: &str) -> String {
+        let Some((register, value)) = arguments.split_once('=') else {
+            return error(0x01);
+        };
+        match (parse_hex(register), parse_hex(value)) {
+            (Some(register), Some(value)) if register < REGISTER_COUNT => {
+                self.set_register(register, value);
+                "OK".to_string()
+            }
+            _ => error(0x01),
+        }
+    }
+
+    fn read_memory(&mut self, arguments: &str) -> String {
+        let Some((address, length)) = parse_address_length(arguments) else {
+            return error(0x01);
+        };
+
+        let mut reply = String::new();
+        for offset in 0..length {
+            let byte = address
+                .checked_add(offset)
+                .and_then(|address| self.cpu.bus().read_byte(address).ok());
+            match byte {
+                Some(byte) => reply.push_str(&format!("{:02x}", byte)),
+                //NOTE: A partial read is fine as long as at least one byte could be read
+                None if offset > 0 => break,
+                None => return error(0x0E),
+            }
+        }
+        reply
+    }
+
+    fn write_memory(&mut self, arguments: &str) -> String {
+        let Some((location, data)) = arguments.split_once(':') else {
+            return error(0x01);
+        };
+        let Some((address, length)) = parse_address_length(location) else {
+            return error(0x01);
+        };
+        let Some(bytes) = parse_bytes(data) else {
+            return error(0x01);
+        };
+        if bytes.len() != length as usize {
+            return error(0x01);
+        }
+
+        match self.cpu.load_bytes(address, &bytes) {
+            Ok(()) => "OK".to_string(),
+            Err(_) => error(0x0E),
+        }
+    }
+
+    //NOTE: Type 0 and 1 are software and hardware breakpoints, 2 to 4 are write, read and access watchpoints
+    fn breakpoint(&mut self, arguments: &str, insert: bool) -> String {
+        let mut fields = arguments.split(',');
+        let kind = fields.next();
+        let address = fields.next().and_then(parse_hex);
+        let length = fields.next().and_then(parse_hex).unwrap_or(1).max(1);
+        let Some(address) = address else {
+            return error(0x01);
+        };
+
+        let watch_kind = match kind {
+            Some("0") | Some("1") => None,
+            Some("2") => Some(WatchKind::Write),
+            Some("3") => Some(WatchKind::Read),
+            Some("4") => Some(WatchKind::ReadWrite),
+            _ => return String::new(),
+        };
+
+        match (watch_kind, insert) {
+            (None, true) => {
+                self.cpu.add_breakpoint(address);
+            }
+            (None, false) => {
+                self.cpu.remove_breakpoint(address);
+            }
+            (Some(kind), true) => self
+                .cpu
+                .add_watchpoint(address..address.saturating_add(length), kind),
+            (Some(_), false) => {
+                self.cpu.remove_watchpoints(address);
+            }
+        }
+
+        "OK".to_string()
+    }
+
+    //NOTE: Returns the signal to report if the step has to stop the cpu
+    fn step(&mut self) -> Option<u8> {
+        let step = self.cpu.step();
+
+        if self.cpu.is_halted() {
+            return Some(SIGABRT);
+        }
+        if step.event.is_some() {
+            return Some(SIGTRAP);
+        }
+        match step.exception {
+            Some(Exception::IllegalInstruction) => Some(SIGILL),
+            Some(Exception::BusError(_)) => Some(SIGBUS),
+            _ => None,
+        }
+    }
+
+    fn resume(&mut self, interrupted: &mut dyn FnMut() -> bool) -> u8 {
+        let mut executed: u64 = 0;
+        loop {
+            if let Some(signal) = self.step() {
+                return signal;
+            }
+
+            executed += 1;
+            if executed.is_multiple_of(INTERRUPT_CHECK_INTERVAL) && interrupted() {
+                return SIGINT;
+            }
+        }
+    }
+}
+
+fn stop_reply(signal: u8) -> String {
+    format!("S{:02x}", signal)
+}
+
+fn error(code: u8) -> String {
+    format!("E{:02x}", code)
+}
+
+fn parse_hex(value: &str) -> Option<u32> {
+    u32::from_str_radix(value, 16).ok()
+}
+
+fn parse_address_length(value: &str) -> Option<(u32, u32)> {
+    let (address, length) = value.split_once(',')?;
+    Some((parse_hex(address)?, parse_hex(length)?))
+}
+
+fn parse_bytes(value: &str) -> Option<Vec<u8>> {
+    if !value.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..value.len())
+        .step_by(2)
+        .map(|index| u8::from_str_radix(value.get(index..index + 2)?, 16).ok())
+        .collect()
+}
+
+pub fn encode_packet(data: &str) -> String {
+    let checksum = data.bytes().fold(0u8, |sum, byte| sum.wrapping_add(byte));
+    format!("${}#{:02x}", data, checksum)
+}
+
+//NOTE: Reads the next packet and acknowledges it, acks from gdb are skipped. Returns None once the connection closed.
+//      A ctrl-c outside of a packet is turned into the '?' query so gdb gets a stop reply.
+fn read_packet(stream: &mut TcpStream) -> io::Result<Option<String>> {
+    let mut byte = [0u8; 1];
+    loop {
+        if stream.read(&mut byte)? == 0 {
+            return Ok(None);
+        }
+
+        match byte[0] {
+            b'$' => {}
+            0x03 => return Ok(Some("?".to_string())),
+            _ => continue,
+        }
+
+        let mut data = vec![];
+        loop {
+            if stream.read(&mut byte)? == 0 {
+                return Ok(None);
+            }
+            if byte[0] == b'#' {
+                break;
+            }
+            data.push(byte[0]);
+        }
+
+        let mut checksum = [0u8; 2];
+        stream.read_exact(&mut checksum)?;
+        let expected = std::str::from_utf8(&checksum)
+            .ok()
+            .and_then(|checksum| u8::from_str_radix(checksum, 16).ok());
+        let actual = data.iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte));
+
+        if expected != Some(actual) {
+            stream.write_all(b"-")?;
+            continue;
+        }
+        stream.write_all(b"+")?;
+        return Ok(Some(String::from_utf8_lossy(&data).into_owned()));
+    }
+}
+
+//NOTE: Checks for a pending ctrl-c without blocking the running cpu
+fn interrupt_pending(stream: &TcpStream) -> bool {
+    let mut byte = [0u8; 1];
+    if stream.set_nonblocking(true).is_err() {
+        return false;
+    }
+    let pending = matches!(stream.peek(&mut byte), Ok(1) if byte[0] == 0x03);
+    if pending {
+        let _ = (&*stream).read(&mut byte);
+    }
+    let _ = stream.set_nonblocking(false);
+    pending
+}
+
+//NOTE: Serves a single gdb session and hands the cpu back once gdb detached or killed it
+pub fn serve(stub: &mut GdbStub, listener: &TcpListener) -> io::Result<()> {
+    let (mut stream, _) = listener.accept()?;
+    let watcher = stream.try_clone()?;
+
+    while let Some(packet) = read_packet(&mut stream)? {
+        match stub.handle(&packet, &mut || interrupt_pending(&watcher)) {
+            Reply::Packet(reply) => stream.write_all(encode_packet(&reply).as_bytes())?,
+            Reply::Close(reply) => {
+                if let Some(reply) = reply {
+                    stream.write_all(encode_packet(&reply).as_bytes())?;
+                }
+                break;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::{io::BufReader, thread};
+
+    use super::*;
+
+    //NOTE: add.dw D0, #1 followed by jmp #0
+    const COUNTING_LOOP: [u32; 4] = [0x80000105, 0x00000001, 0x80000114, 0x00000000];
+
+    fn counting_stub() -> GdbStub {
+        let mut cpu = Cpu::new();
+        cpu.load_words(0x00000000, &COUNTING_LOOP).unwrap();
+        cpu.set_register(Register::A15, 0x00100000);
+        GdbStub::new(cpu)
+    }
+
+    fn packet(stub: &mut GdbStub, packet: &str) -> String {
+        match stub.handle(packet, &mut || false) {
+            Reply::Packet(reply) => reply,
+            Reply::Close(_) => panic!("the session closed"),
+        }
+    }
+
+    #[test]
+    fn test_encode_packet() {
+        assert_eq!(encode_packet("OK"), "$OK#9a");
+        assert_eq!(encode_packet(""), "$#00");
+    }
+
+    #[test]
+    fn test_registers() {
+        let mut stub = counting_stub();
+        stub.cpu().set_register(Register::D1, 0xDEADBEEF);
+
+        let registers = packet(&mut stub, "g");
+        assert_eq!(registers.len(), REGISTER_COUNT as usize * 8);
+        assert_eq!(&registers[8..16], "deadbeef");
+        assert_eq!(&registers[31 * 8..32 * 8], "00100000");
+
+        assert_eq!(packet(&mut stub, "P20=00001000"), "OK");
+        assert_eq!(packet(&mut stub, "P21=00000800"), "OK");
+        assert_eq!(packet(&mut stub, "p20"), "00001000");
+        assert_eq!(stub.cpu().status().status_bits(), 0x0800);
+        assert_eq!(packet(&mut stub, "p22"), "E01");
+
+        let mut registers = "0".repeat(REGISTER_COUNT as usize * 8);
+        registers.replace_range(0..8, "00000007");
+        assert_eq!(packet(&mut stub, &format!("G{}", registers)), "OK");
+        assert_eq!(stub.cpu().register(Register::D0), 7);
+        assert_eq!(stub.cpu().pc(), 0);
+        assert_eq!(packet(&mut stub, "G0000"), "E01");
+
+        //NOTE: The right length in bytes, but the é straddles the end of the first register
+        let registers = format!("0000000é{}", "0".repeat(REGISTER_COUNT as usize * 8 - 9));
+        assert_eq!(packet(&mut stub, &format!("G{}", registers)), "E01");
+        assert_eq!(stub.cpu().register(Register::D0), 7);
+    }
+
+    #[test]
+    fn test_memory() {
+        let mut stub = counting_stub();
+
+        assert_eq!(packet(&mut stub, "m0,8"), "8000010500000001");
+        assert_eq!(packet(&mut stub, "M1000,3:aabbcc"), "OK");
+        assert_eq!(packet(&mut stub, "m1000,4"), "aabbcc00");
+        assert_eq!(packet(&mut stub, "m7fffffe,4"), "0000");
+        assert_eq!(packet(&mut stub, "mffffffff,1"), "E0e");
+        assert_eq!(packet(&mut stub, "M1000,2:aa"), "E01");
+    }
+
+    #[test]
+    fn test_step_continue_and_breakpoints() {
+        let mut stub = counting_stub();
+
+        assert_eq!(packet(&mut stub, "s"), "S05");
+        assert_eq!(stub.cpu().pc(), 0x00000008);
+
+        assert_eq!(packet(&mut stub, "Z0,8,4"), "OK");
+        assert_eq!(packet(&mut stub, "c"), "S05");
+        assert_eq!(stub.cpu().pc(), 0x00000008);
+        assert_eq!(stub.cpu().register(Register::D0), 2);
+
+        assert_eq!(packet(&mut stub, "z0,8,4"), "OK");
+        assert_eq!(packet(&mut stub, "Z2,1000,4"), "OK");
+        stub.cpu().load_words(0x00002000, &[0x000000FF]).unwrap();
+        assert_eq!(packet(&mut stub, "c2000"), "S04");
+
+        //NOTE: Without breakpoints only ctrl-c stops the loop
+        let mut stub = counting_stub();
+        let mut polls = 0;
+        let reply = stub.handle("c", &mut || {
+            polls += 1;
+            polls == 3
+        });
+        assert_eq!(reply, Reply::Packet("S02".to_string()));
+    }
+
+    #[test]
+    fn test_session_over_tcp() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+
+        //NOTE: The cpu isn't Send so the client is the one running on the other thread
+        let client = thread::spawn(move || {
+            let stream = TcpStream::connect(address).unwrap();
+            let mut writer = stream.try_clone().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut exchange = |request: &str| -> String {
+                writer.write_all(encode_packet(request).as_bytes()).unwrap();
+
+                let mut ack = [0u8; 1];
+                reader.read_exact(&mut ack).unwrap();
+                assert_eq!(ack[0], b'+');
+
+                let mut reply = vec![];
+                let mut byte = [0u8; 1];
+                loop {
+                    reader.read_exact(&mut byte).unwrap();
+                    if byte[0] == b'#' {
+                        break;
+                    }
+                    reply.push(byte[0]);
+                }
+                let mut checksum = [0u8; 2];
+                reader.read_exact(&mut checksum).unwrap();
+                writer.write_all(b"+").unwrap();
+
+                let reply = String::from_utf8(reply).unwrap();
+                assert_eq!(reply.chars().next(), Some('$'));
+                reply[1..].to_string()
+            };
+
+            [
+                "qSupported:swbreak+",
+                "?",
+                "Z0,0,4",
+                "c",
+                "p0",
+                "vMustReplyEmpty",
+                "D",
+            ]
+            .map(&mut exchange)
+        });
+
+        let mut stub = counting_stub();
+        serve(&mut stub, &listener).unwrap();
+
+        assert_eq!(
+            client.join().unwrap(),
+            ["PacketSize=1000", "S05", "OK", "S05", "00000001", "", "OK"]
+        );
+        assert_eq!(stub.cpu().pc(), 0x00000000);
+        assert_eq!(stub.cpu().register(Register::D0), 1);
+    }
+}
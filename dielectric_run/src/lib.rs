@@ -0,0 +1,6 @@
+pub mod debugger;
+pub mod gdb;
+pub mod options;
+pub mod program;
+pub mod report;
+pub mod runner;
@@ -0,0 +1,76 @@
+use std::{net::TcpListener, process::ExitCode};
+
+use dielectric_run::{
+    gdb::{self, GdbStub},
+    options::{self, Options, OptionsError},
+    program, report,
+    runner::{self, Limits},
+};
+
+fn main() -> ExitCode {
+    let options = match Options::parse(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(OptionsError::Help) => {
+            println!("{}", options::usage("dielectric-run"));
+            return ExitCode::SUCCESS;
+        }
+        Err(error) => {
+            eprintln!("error: {}\n\n{}", error, options::usage("dielectric-run"));
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match run(&options) {
+        Ok(output) => {
+            print!("{}", output);
+            ExitCode::SUCCESS
+        }
+        Err(error) => {
+            eprintln!("error: {}", error);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(options: &Options) -> Result<String, String> {
+    let (mut cpu, video) = program::load_cpu(options)?;
+
+    if let Some(port) = options.gdb_port {
+        let listener = TcpListener::bind(("127.0.0.1", port))
+            .map_err(|error| format!("failed to listen on port {}, {}", port, error))?;
+        println!("waiting for gdb on 127.0.0.1:{}", port);
+
+        let mut stub = GdbStub::new(cpu);
+        gdb::serve(&mut stub, &listener)
+            .map_err(|error| format!("gdb session failed, {}", error))?;
+        program::finish_trace(stub.cpu())?;
+        program::save_snapshot(stub.cpu(), options)?;
+        program::save_frame(video.as_ref(), options)?;
+        return Ok(report::registers(stub.cpu()));
+    }
+
+    let limits = Limits {
+        max_instructions: options.max_instructions,
+        max_cycles: options.max_cycles,
+        stop_at: options.stop_at,
+        stop_on_exception: options.stop_on_exception,
+    };
+    let summary = runner::run(&mut cpu, &limits);
+    program::finish_trace(&mut cpu)?;
+    program::save_snapshot(&cpu, options)?;
+    program::save_frame(video.as_ref(), options)?;
+
+    let mut output = format!(
+        "stopped: {}\ninstructions: {}\ncycles: {}\n\n{}",
+        summary.reason,
+        summary.instructions,
+        summary.cycles,
+        report::registers(&cpu)
+    );
+    for range in &options.dumps {
+        output.push('\n');
+        output.push_str(&report::memory(cpu.bus(), range.clone()));
+    }
+
+    Ok(output)
+}
@@ -0,0 +1,344 @@
+use std::{fmt::Display, ops::Range};
+
+const OPTIONS: &str = "\
+options:
+    --format <bin|hex>        bin: raw big endian bytes (default)
+                              hex: 32 bit words in hex like the assembler prints them
+    --load <ADDR>             address the program is loaded at (default 0x00000000)
+    --entry <ADDR>            address execution starts at (default the load address or the bios reset vector)
+    --bios <FILE>             maps the image read only at 0x00000000 and resets the cpu
+    --ram-size <N>            bytes of ram starting at 0x00000000 (default 128MB)
+    --sp <ADDR>               initial stack pointer (A15)
+    --max-instructions <N>    stops after N instructions (default 1000000 if no limit is given)
+    --max-cycles <N>          stops once N cycles elapsed
+    --stop-at <ADDR>          stops once the pc reaches ADDR
+    --break <ADDR>            sets a breakpoint, can be repeated
+    --stop-on-exception       stops after the first exception or interrupt
+    --strict                  faults on misaligned word and dword accesses
+    --dump <START>:<END>      prints the memory START..END after the run, can be repeated
+    --gdb <PORT>              waits for gdb on 127.0.0.1:PORT instead of running on its own
+    --trace <FILE>            writes a record of every executed instruction to FILE
+    --trace-format <text|bin> text: one line per instruction (default)
+                              bin: compact binary records, see dielectric_cpu::trace
+    --restore <FILE>          starts from a snapshot, the program is optional and loaded on top of it
+    --save-snapshot <FILE>    writes a snapshot of the cpu, its ram and its devices to FILE after the run
+    --frame <FILE>            maps the video memory and writes the frame as a PPM image to FILE after the run
+    -h, --help                prints this message
+
+numbers are decimal or hex with a 0x or $ prefix";
+
+pub fn usage(binary: &str) -> String {
+    format!("usage: {} [OPTIONS] <PROGRAM>\n\n{}", binary, OPTIONS)
+}
+
+pub const DEFAULT_MAX_INSTRUCTIONS: u64 = 1_000_000;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Format {
+    Binary,
+    Hex,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum TraceFormat {
+    Text,
+    Binary,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Options {
+    //NOTE: Only missing if a snapshot is restored
+    pub program: Option<String>,
+    pub format: Format,
+    pub load_address: u32,
+    pub entry: Option<u32>,
+    pub bios: Option<String>,
+    pub ram_size: Option<u32>,
+    pub stack_pointer: Option<u32>,
+    pub max_instructions: Option<u64>,
+    pub max_cycles: Option<u64>,
+    pub stop_at: Option<u32>,
+    pub breakpoints: Vec<u32>,
+    pub stop_on_exception: bool,
+    pub strict: bool,
+    pub dumps: Vec<Range<u32>>,
+    pub gdb_port: Option<u16>,
+    pub trace: Option<String>,
+    pub trace_format: TraceFormat,
+    pub restore: Option<String>,
+    pub save_snapshot: Option<String>,
+    pub frame: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum OptionsError {
+    Help,
+    MissingProgram,
+    MissingValue(String),
+    UnknownOption(String),
+    InvalidNumber(String),
+    InvalidFormat(String),
+    InvalidTraceFormat(String),
+    InvalidRange(String),
+}
+
+impl Display for OptionsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OptionsError::Help => write!(f, "help requested"),
+            OptionsError::MissingProgram => write!(f, "no program given"),
+            OptionsError::MissingValue(option) => write!(f, "{} expects a value", option),
+            OptionsError::UnknownOption(option) => write!(f, "unknown option {}", option),
+            OptionsError::InvalidNumber(value) => write!(f, "{} is not a number", value),
+            OptionsError::InvalidFormat(value) => {
+                write!(f, "unknown format {}, expected bin or hex", value)
+            }
+            OptionsError::InvalidTraceFormat(value) => {
+                write!(f, "unknown trace format {}, expected text or bin", value)
+            }
+            OptionsError::InvalidRange(value) => {
+                write!(f, "{} is not a range, expected START:END", value)
+            }
+        }
+    }
+}
+
+impl Options {
+    pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Self, OptionsError> {
+        let mut options = Options {
+            program: None,
+            format: Format::Binary,
+            load_address: 0x00000000,
+            entry: None,
+            bios: None,
+            ram_size: None,
+            stack_pointer: None,
+            max_instructions: None,
+            max_cycles: None,
+            stop_at: None,
+            breakpoints: vec![],
+            stop_on_exception: false,
+            strict: false,
+            dumps: vec![],
+            gdb_port: None,
+            trace: None,
+            trace_format: TraceFormat::Text,
+            restore: None,
+            save_snapshot: None,
+            frame: None,
+        };
+
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or(OptionsError::MissingValue(arg.clone()));
+
+            match arg.as_str() {
+                "-h" | "--help" => return Err(OptionsError::Help),
+                "--format" => options.format = parse_format(&value()?)?,
+                "--load" => options.load_address = parse_number(&value()?)?,
+                "--entry" => options.entry = Some(parse_number(&value()?)?),
+                "--bios" => options.bios = Some(value()?),
+                "--ram-size" => options.ram_size = Some(parse_number(&value()?)?),
+                "--sp" => options.stack_pointer = Some(parse_number(&value()?)?),
+                "--max-instructions" => options.max_instructions = Some(parse_number(&value()?)?),
+                "--max-cycles" => options.max_cycles = Some(parse_number(&value()?)?),
+                "--stop-at" => options.stop_at = Some(parse_number(&value()?)?),
+                "--break" => options.breakpoints.push(parse_number(&value()?)?),
+                "--stop-on-exception" => options.stop_on_exception = true,
+                "--strict" => options.strict = true,
+                "--dump" => options.dumps.push(parse_range(&value()?)?),
+                "--gdb" => options.gdb_port = Some(parse_number(&value()?)?),
+                "--trace" => options.trace = Some(value()?),
+                "--trace-format" => options.trace_format = parse_trace_format(&value()?)?,
+                "--restore" => options.restore = Some(value()?),
+                "--save-snapshot" => options.save_snapshot = Some(value()?),
+                "--frame" => options.frame = Some(value()?),
+                _ if arg.starts_with('-') => return Err(OptionsError::UnknownOption(arg)),
+                _ => options.program = Some(arg),
+            }
+        }
+
+        if options.program.is_none() && options.restore.is_none() {
+            return Err(OptionsError::MissingProgram);
+        }
+        if options.max_instructions.is_none() && options.max_cycles.is_none() {
+            options.max_instructions = Some(DEFAULT_MAX_INSTRUCTIONS);
+        }
+
+        Ok(options)
+    }
+}
+
+pub fn parse_format(value: &str) -> Result<Format, OptionsError> {
+    match value {
+        "bin" => Ok(Format::Binary),
+        "hex" => Ok(Format::Hex),
+        _ => Err(OptionsError::InvalidFormat(value.to_string())),
+    }
+}
+
+fn parse_trace_format(value: &str) -> Result<TraceFormat, OptionsError> {
+    match value {
+        "text" => Ok(TraceFormat::Text),
+        "bin" => Ok(TraceFormat::Binary),
+        _ => Err(OptionsError::InvalidTraceFormat(value.to_string())),
+    }
+}
+
+//NOTE: Accepts the same number prefixes as the assembler, 0x and $ for hex everything else is decimal
+pub fn parse_number<T: TryFrom<u64>>(value: &str) -> Result<T, OptionsError> {
+    let invalid = || OptionsError::InvalidNumber(value.to_string());
+    let digits = value.replace('_', "");
+
+    let number = if let Some(hex) = digits
+        .strip_prefix("0x")
+        .or_else(|| digits.strip_prefix("0X"))
+        .or_else(|| digits.strip_prefix('$'))
+    {
+        u64::from_str_radix(hex, 16).map_err(|_| invalid())?
+    } else {
+        digits.parse::<u64>().map_err(|_| invalid())?
+    };
+
+    T::try_from(number).map_err(|_| invalid())
+}
+
+fn parse_range(value: &str) -> Result<Range<u32>, OptionsError> {
+    let invalid = || OptionsError::InvalidRange(value.to_string());
+    let (start, end) = value.split_once(':').ok_or_else(invalid)?;
+    let start = parse_number(start)?;
+    let end = parse_number(end)?;
+
+    if start >= end {
+        return Err(invalid());
+    }
+    Ok(start..end)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Options, OptionsError> {
+        Options::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn test_defaults() {
+        let options = parse(&["program.bin"]).unwrap();
+
+        assert_eq!(options.program, Some("program.bin".to_string()));
+        assert_eq!(options.format, Format::Binary);
+        assert_eq!(options.load_address, 0x00000000);
+        assert_eq!(options.max_instructions, Some(DEFAULT_MAX_INSTRUCTIONS));
+        assert_eq!(options.max_cycles, None);
+        assert_eq!(options.ram_size, None);
+        assert!(options.dumps.is_empty());
+        assert_eq!(options.trace, None);
+        assert_eq!(options.trace_format, TraceFormat::Text);
+        assert_eq!(options.restore, None);
+        assert_eq!(options.save_snapshot, None);
+        assert_eq!(options.frame, None);
+    }
+
+    #[test]
+    fn test_all_options() {
+        let options = parse(&[
+            "--format",
+            "hex",
+            "--load",
+            "0x1000",
+            "--entry",
+            "$1004",
+            "--ram-size",
+            "0x10000",
+            "--sp",
+            "0x00100000",
+            "--max-cycles",
+            "5_000",
+            "--stop-at",
+            "4096",
+            "--break",
+            "0x2000",
+            "--break",
+            "0x2004",
+            "--stop-on-exception",
+            "--strict",
+            "--dump",
+            "0x1000:0x1010",
+            "--dump",
+            "0:16",
+            "--gdb",
+            "1234",
+            "--trace",
+            "trace.bin",
+            "--trace-format",
+            "bin",
+            "--restore",
+            "start.snap",
+            "--save-snapshot",
+            "end.snap",
+            "--frame",
+            "frame.ppm",
+            "program.hex",
+        ])
+        .unwrap();
+
+        assert_eq!(options.format, Format::Hex);
+        assert_eq!(options.load_address, 0x00001000);
+        assert_eq!(options.entry, Some(0x00001004));
+        assert_eq!(options.ram_size, Some(0x10000));
+        assert_eq!(options.stack_pointer, Some(0x00100000));
+        assert_eq!(options.max_cycles, Some(5000));
+        assert_eq!(options.max_instructions, None);
+        assert_eq!(options.stop_at, Some(0x00001000));
+        assert_eq!(options.breakpoints, vec![0x00002000, 0x00002004]);
+        assert!(options.stop_on_exception);
+        assert!(options.strict);
+        assert_eq!(options.dumps, vec![0x1000..0x1010, 0..16]);
+        assert_eq!(options.gdb_port, Some(1234));
+        assert_eq!(options.trace, Some("trace.bin".to_string()));
+        assert_eq!(options.trace_format, TraceFormat::Binary);
+        assert_eq!(options.restore, Some("start.snap".to_string()));
+        assert_eq!(options.save_snapshot, Some("end.snap".to_string()));
+        assert_eq!(options.frame, Some("frame.ppm".to_string()));
+    }
+
+    #[test]
+    fn test_restore_without_program() {
+        let options = parse(&["--restore", "start.snap"]).unwrap();
+
+        assert_eq!(options.program, None);
+        assert_eq!(options.restore, Some("start.snap".to_string()));
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(parse(&[]), Err(OptionsError::MissingProgram));
+        assert_eq!(parse(&["--help"]), Err(OptionsError::Help));
+        assert_eq!(
+            parse(&["program.bin", "--load"]),
+            Err(OptionsError::MissingValue("--load".to_string()))
+        );
+        assert_eq!(
+            parse(&["--verbose", "program.bin"]),
+            Err(OptionsError::UnknownOption("--verbose".to_string()))
+        );
+        assert_eq!(
+            parse(&["--load", "0x1_0000_0000", "program.bin"]),
+            Err(OptionsError::InvalidNumber("0x1_0000_0000".to_string()))
+        );
+        assert_eq!(
+            parse(&["--format", "elf", "program.bin"]),
+            Err(OptionsError::InvalidFormat("elf".to_string()))
+        );
+        assert_eq!(
+            parse(&["--trace-format", "json", "program.bin"]),
+            Err(OptionsError::InvalidTraceFormat("json".to_string()))
+        );
+        assert_eq!(
+            parse(&["--dump", "0x20:0x10", "program.bin"]),
+            Err(OptionsError::InvalidRange("0x20:0x10".to_string()))
+        );
+    }
+}
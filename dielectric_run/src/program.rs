@@ -0,0 +1,191 @@
+use std::{fmt::Display, fs::File, io::BufWriter};
+
+use dielectric_cpu::{
+    bios::Bios,
+    bus::AccessMode,
+    cpu::{core::Cpu, register::Register},
+    machine::MachineConfig,
+    snapshot::Snapshot,
+    trace::{BinaryTracer, TextTracer},
+    video::Video,
+};
+
+use crate::options::{Format, Options, TraceFormat};
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ProgramError {
+    NotText,
+    InvalidWord(String),
+}
+
+impl Display for ProgramError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProgramError::NotText => write!(f, "hex programs have to be utf-8 text"),
+            ProgramError::InvalidWord(word) => write!(f, "{} is not a 32 bit hex word", word),
+        }
+    }
+}
+
+//NOTE: Builds the cpu the options describe and loads the program into it. Without a bios the program starts where
+//      it was loaded, with one at the bios reset vector. A restored snapshot continues at its pc, the program is
+//      loaded on top of the snapshot's ram. The video memory is only mapped if a frame is requested, the video is
+//      returned alongside the cpu to render it.
+pub fn load_cpu(options: &Options) -> Result<(Cpu, Option<Video>), String> {
+    let mut config = MachineConfig::new();
+    if let Some(size) = options.ram_size {
+        config = config.ram_size(size);
+    }
+    if let Some(path) = &options.bios {
+        config = config.bios(Bios::load(path).map_err(|error| error.to_string())?);
+    }
+    let video = options.frame.as_ref().map(|_| Video::new());
+    if let Some(video) = &video {
+        config = config.video(video.clone());
+    }
+    let mut cpu = Cpu::with_config(config).map_err(|error| error.to_string())?;
+    if options.strict {
+        cpu.bus().set_access_mode(AccessMode::Strict);
+    }
+
+    if let Some(path) = &options.restore {
+        let snapshot = Snapshot::load(path)
+            .map_err(|error| format!("failed to read the snapshot {}, {}", path, error))?;
+        cpu.restore(&snapshot)
+            .map_err(|error| format!("failed to restore the snapshot {}, {}", path, error))?;
+    }
+
+    if let Some(program) = &options.program {
+        let contents = std::fs::read(program)
+            .map_err(|error| format!("failed to read {}, {}", program, error))?;
+        let bytes = decode(options.format, contents).map_err(|error| error.to_string())?;
+        cpu.load_bytes(options.load_address, &bytes)
+            .map_err(|error| format!("failed to load the program, {}", error))?;
+    }
+
+    match (options.entry, &options.bios, &options.restore) {
+        (Some(entry), _, _) => cpu.set_pc(entry),
+        (None, None, None) => cpu.set_pc(options.load_address),
+        (None, _, _) => {}
+    }
+    if let Some(stack_pointer) = options.stack_pointer {
+        cpu.set_register(Register::A15, stack_pointer);
+    }
+    for breakpoint in &options.breakpoints {
+        cpu.add_breakpoint(*breakpoint);
+    }
+
+    if let Some(path) = &options.trace {
+        let trace_error = |error| format!("failed to create the trace {}, {}", path, error);
+        let writer = BufWriter::new(File::create(path).map_err(trace_error)?);
+        match options.trace_format {
+            TraceFormat::Text => cpu.set_tracer(Box::new(TextTracer::new(writer))),
+            TraceFormat::Binary => {
+                cpu.set_tracer(Box::new(BinaryTracer::new(writer).map_err(trace_error)?))
+            }
+        }
+    }
+
+    Ok((cpu, video))
+}
+
+//NOTE: Removes the tracer and flushes the trace, does nothing if the cpu isn't traced
+pub fn finish_trace(cpu: &mut Cpu) -> Result<(), String> {
+    match cpu.take_tracer() {
+        Some(mut tracer) => tracer
+            .finish()
+            .map_err(|error| format!("failed to write the trace, {}", error)),
+        None => Ok(()),
+    }
+}
+
+//NOTE: Writes the snapshot the options ask for, does nothing without --save-snapshot
+pub fn save_snapshot(cpu: &Cpu, options: &Options) -> Result<(), String> {
+    match &options.save_snapshot {
+        Some(path) => cpu
+            .snapshot()
+            .save(path)
+            .map_err(|error| format!("failed to write the snapshot {}, {}", path, error)),
+        None => Ok(()),
+    }
+}
+
+//NOTE: Renders the frame the options ask for, does nothing without --frame
+pub fn save_frame(video: Option<&Video>, options: &Options) -> Result<(), String> {
+    match (video, &options.frame) {
+        (Some(video), Some(path)) => video
+            .render()
+            .save_ppm(path)
+            .map_err(|error| format!("failed to write the frame {}, {}", path, error)),
+        _ => Ok(()),
+    }
+}
+
+//NOTE: Turns the file contents into the bytes that end up in memory
+pub fn decode(format: Format, contents: Vec<u8>) -> Result<Vec<u8>, ProgramError> {
+    match format {
+        Format::Binary => Ok(contents),
+        Format::Hex => {
+            let text = String::from_utf8(contents).map_err(|_| ProgramError::NotText)?;
+            Ok(decode_hex(&text)?
+                .iter()
+                .flat_map(|word| word.to_be_bytes())
+                .collect())
+        }
+    }
+}
+
+//NOTE: Words are separated by whitespace or commas, the brackets of the assembler's debug output are ignored so
+//      its output can be piped into a file as is
+fn decode_hex(text: &str) -> Result<Vec<u32>, ProgramError> {
+    text.split(|c: char| c.is_whitespace() || matches!(c, ',' | '[' | ']'))
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let digits = word.strip_prefix("0x").unwrap_or(word);
+            u32::from_str_radix(digits, 16).map_err(|_| ProgramError::InvalidWord(word.to_string()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_binary_is_loaded_as_is() {
+        let bytes = vec![0xDE, 0xAD, 0xBE, 0xEF, 0x01];
+        assert_eq!(decode(Format::Binary, bytes.clone()), Ok(bytes));
+    }
+
+    #[test]
+    fn test_assembler_output() {
+        let text = "[\n    0x40001,\n    0x8005c145,\n    0xaabbccdd,\n]\n";
+
+        assert_eq!(
+            decode(Format::Hex, text.as_bytes().to_vec()),
+            Ok(vec![
+                0x00, 0x04, 0x00, 0x01, 0x80, 0x05, 0xC1, 0x45, 0xAA, 0xBB, 0xCC, 0xDD
+            ])
+        );
+        assert_eq!(
+            decode(Format::Hex, b"00000001 0x2".to_vec()),
+            Ok(vec![0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x02])
+        );
+    }
+
+    #[test]
+    fn test_invalid_hex() {
+        assert_eq!(
+            decode(Format::Hex, b"0x1 move".to_vec()),
+            Err(ProgramError::InvalidWord("move".to_string()))
+        );
+        assert_eq!(
+            decode(Format::Hex, b"0x123456789".to_vec()),
+            Err(ProgramError::InvalidWord("0x123456789".to_string()))
+        );
+        assert_eq!(
+            decode(Format::Hex, vec![0xFF, 0xFE]),
+            Err(ProgramError::NotText)
+        );
+    }
+}
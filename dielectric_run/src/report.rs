@@ -0,0 +1,175 @@
+use std::ops::Range;
+
+use dielectric_cpu::{
+    bus::Bus,
+    cpu::{
+        core::Cpu,
+        disassembler,
+        register::Register,
+        status_register::{Flags, StatusRegister},
+    },
+};
+
+const BYTES_PER_LINE: u32 = 16;
+
+pub fn flag_name(flag: Flags) -> &'static str {
+    match flag {
+        Flags::Negative => "N",
+        Flags::Overflow => "V",
+        Flags::Carry => "C",
+        Flags::Parity => "P",
+        Flags::Zero => "Z",
+        Flags::Interrupt => "I",
+        Flags::InterruptEnable => "IE",
+        Flags::Trap => "T",
+    }
+}
+
+//NOTE: Set flags are printed with their name, cleared ones as '-'
+pub fn flags(status: &StatusRegister) -> String {
+    let flags: Vec<&str> = Flags::ALL
+        .iter()
+        .map(|flag| {
+            if status.is_set(*flag) {
+                flag_name(*flag)
+            } else {
+                "-"
+            }
+        })
+        .collect();
+
+    format!("SR {:#06x} [{}]", status.status_bits(), flags.join(" "))
+}
+
+//NOTE: PC and the status register followed by the data and address registers, four per line
+pub fn registers(cpu: &Cpu) -> String {
+    let mut report = format!("PC  {:#010x}  {}\n", cpu.pc(), flags(cpu.status()));
+
+    for row in (0..32).step_by(4) {
+        let line: Vec<String> = (row..row + 4)
+            .map(|index| {
+                let register = Register::new(index);
+                format!(
+                    "{:<3} {:#010x}",
+                    register.to_string(),
+                    cpu.register(register)
+                )
+            })
+            .collect();
+        report.push_str(&line.join("  "));
+        report.push('\n');
+    }
+
+    report
+}
+
+//NOTE: Hex dump of the range, bytes the bus faults on are printed as '??'
+pub fn memory(bus: &mut dyn Bus, range: Range<u32>) -> String {
+    let mut report = String::new();
+    let mut address = range.start;
+
+    while address < range.end {
+        let line_end = range.end.min(address.saturating_add(BYTES_PER_LINE));
+        let bytes: Vec<String> = (address..line_end)
+            .map(|address| match bus.read_byte(address) {
+                Ok(byte) => format!("{:02X}", byte),
+                Err(_) => "??".to_string(),
+            })
+            .collect();
+
+        report.push_str(&format!("{:#010x}  {}\n", address, bytes.join(" ")));
+        address = line_end;
+    }
+
+    report
+}
+
+//NOTE: Listing of a raw image loaded at the address, a trailing partial word is padded with zeros
+pub fn disassembly(bytes: &[u8], address: u32) -> String {
+    let words: Vec<u32> = bytes
+        .chunks(4)
+        .map(|chunk| {
+            let mut word = [0x00; 4];
+            word[..chunk.len()].copy_from_slice(chunk);
+            u32::from_be_bytes(word)
+        })
+        .collect();
+
+    disassembler::disassemble(&words, address)
+        .iter()
+        .map(|instruction| {
+            let extension = instruction
+                .extension_word
+                .map_or(String::new(), |extension| format!("{:08x}", extension));
+            format!(
+                "{:#010x}  {:08x} {:<8}  {}\n",
+                instruction.address, instruction.word, extension, instruction
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_flags() {
+        let cpu = Cpu::new();
+        assert_eq!(flags(cpu.status()), "SR 0x0000 [- - - - - - - -]");
+    }
+
+    #[test]
+    fn test_registers() {
+        let mut cpu = Cpu::new();
+        cpu.set_pc(0x00001000);
+        cpu.set_register(Register::D5, 0xDEADBEEF);
+        cpu.set_register(Register::A15, 0x00100000);
+
+        let report = registers(&cpu);
+        let lines: Vec<&str> = report.lines().collect();
+
+        assert_eq!(lines.len(), 9);
+        assert_eq!(lines[0], "PC  0x00001000  SR 0x0000 [- - - - - - - -]");
+        assert_eq!(
+            lines[2],
+            "D4  0x00000000  D5  0xdeadbeef  D6  0x00000000  D7  0x00000000"
+        );
+        assert_eq!(
+            lines[8],
+            "A12 0x00000000  A13 0x00000000  A14 0x00000000  A15 0x00100000"
+        );
+    }
+
+    #[test]
+    fn test_memory_dump() {
+        let mut cpu = Cpu::new();
+        cpu.load_bytes(0x00001000, &(0x00..0x14).collect::<Vec<u8>>())
+            .unwrap();
+
+        assert_eq!(
+            memory(cpu.bus(), 0x00001000..0x00001014),
+            "0x00001000  00 01 02 03 04 05 06 07 08 09 0A 0B 0C 0D 0E 0F\n\
+             0x00001010  10 11 12 13\n"
+        );
+        assert_eq!(
+            memory(cpu.bus(), 0x07FFFFFE..0x08000002),
+            "0x07fffffe  00 00 ?? ??\n"
+        );
+    }
+
+    #[test]
+    fn test_disassembly() {
+        let bytes = [
+            0x80, 0x00, 0x01, 0x05, 0x00, 0x00, 0x00, 0x01, 0x80, 0x00, 0x01, 0x14, 0x00, 0x00,
+            0x10, 0x00, 0x80, 0x00, 0x00,
+        ];
+
+        assert_eq!(
+            disassembly(&bytes, 0x00001000),
+            "0x00001000  80000105 00000001  ADD.DW D0,$00000001\n\
+             0x00001008  80000114 00001000  JMP $00001000\n\
+             0x00001010  80000000           ILLEGAL\n"
+        );
+    }
+}
@@ -0,0 +1,201 @@
+use std::fmt::Display;
+
+use dielectric_cpu::cpu::{
+    core::{Cpu, DebugEvent},
+    exception::Exception,
+};
+
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct Limits {
+    pub max_instructions: Option<u64>,
+    pub max_cycles: Option<u64>,
+    pub stop_at: Option<u32>,
+    pub stop_on_exception: bool,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum StopReason {
+    InstructionLimit,
+    CycleLimit,
+    Address(u32),
+    Exception(Exception),
+    Interrupt(u8),
+    Event(DebugEvent),
+    Halted,
+}
+
+impl Display for StopReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StopReason::InstructionLimit => write!(f, "instruction limit reached"),
+            StopReason::CycleLimit => write!(f, "cycle limit reached"),
+            StopReason::Address(address) => write!(f, "reached {:#010x}", address),
+            StopReason::Exception(exception) => write!(f, "{}", exception),
+            StopReason::Interrupt(line) => write!(f, "interrupt request {}", line),
+            StopReason::Event(event) => write!(f, "{}", event),
+            StopReason::Halted => write!(f, "cpu halted"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Summary {
+    pub reason: StopReason,
+    pub instructions: u64,
+    pub cycles: u64,
+}
+
+//NOTE: Runs until one of the limits is hit or a breakpoint or watchpoint of the cpu triggered, the limits are checked
+//      after every instruction so at least one instruction is executed
+pub fn run(cpu: &mut Cpu, limits: &Limits) -> Summary {
+    let start_cycles = cpu.cycles();
+    let mut executed = 0;
+    let mut reason = StopReason::Halted;
+
+    let instructions = cpu.run_until(|cpu, step| {
+        executed += 1;
+        let stop = if cpu.is_halted() {
+            Some(StopReason::Halted)
+        } else if let Some(event) = step.event {
+            Some(StopReason::Event(event))
+        } else if let (true, Some(exception)) = (limits.stop_on_exception, step.exception) {
+            Some(StopReason::Exception(exception))
+        } else if let (true, Some(line)) = (limits.stop_on_exception, step.interrupt) {
+            Some(StopReason::Interrupt(line))
+        } else if limits.stop_at == Some(cpu.pc()) {
+            Some(StopReason::Address(cpu.pc()))
+        } else if limits
+            .max_cycles
+            .is_some_and(|max| cpu.cycles() - start_cycles >= max)
+        {
+            Some(StopReason::CycleLimit)
+        } else if limits.max_instructions.is_some_and(|max| executed >= max) {
+            Some(StopReason::InstructionLimit)
+        } else {
+            None
+        };
+
+        match stop {
+            Some(stop) => {
+                reason = stop;
+                true
+            }
+            None => false,
+        }
+    });
+
+    Summary {
+        reason,
+        instructions,
+        cycles: cpu.cycles() - start_cycles,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use dielectric_cpu::cpu::register::Register;
+
+    use super::*;
+
+    //NOTE: add.dw D0, #1 followed by jmp #0
+    const COUNTING_LOOP: [u32; 4] = [0x80000105, 0x00000001, 0x80000114, 0x00000000];
+
+    fn counting_cpu() -> Cpu {
+        let mut cpu = Cpu::new();
+        cpu.load_words(0x00000000, &COUNTING_LOOP).unwrap();
+        cpu
+    }
+
+    #[test]
+    fn test_instruction_limit() {
+        let mut cpu = counting_cpu();
+        let limits = Limits {
+            max_instructions: Some(10),
+            ..Default::default()
+        };
+
+        let summary = run(&mut cpu, &limits);
+
+        assert_eq!(summary.reason, StopReason::InstructionLimit);
+        assert_eq!(summary.instructions, 10);
+        assert_eq!(summary.cycles, cpu.cycles());
+        assert_eq!(cpu.register(Register::D0), 5);
+    }
+
+    #[test]
+    fn test_cycle_limit_and_address() {
+        let mut cpu = counting_cpu();
+        let summary = run(
+            &mut cpu,
+            &Limits {
+                max_cycles: Some(20),
+                ..Default::default()
+            },
+        );
+        assert_eq!(summary.reason, StopReason::CycleLimit);
+        assert!(summary.cycles >= 20);
+
+        let mut cpu = counting_cpu();
+        let summary = run(
+            &mut cpu,
+            &Limits {
+                stop_at: Some(0x00000008),
+                max_instructions: Some(10),
+                ..Default::default()
+            },
+        );
+        assert_eq!(summary.reason, StopReason::Address(0x00000008));
+        assert_eq!(summary.instructions, 1);
+    }
+
+    #[test]
+    fn test_exceptions_and_halts() {
+        let mut cpu = Cpu::new();
+        cpu.load_words(0x00000000, &[0x000000FF]).unwrap();
+        cpu.set_register(Register::A15, 0x00100000);
+        let limits = Limits {
+            stop_on_exception: true,
+            max_instructions: Some(10),
+            ..Default::default()
+        };
+
+        let summary = run(&mut cpu, &limits);
+        assert_eq!(
+            summary.reason,
+            StopReason::Exception(Exception::IllegalInstruction)
+        );
+
+        //NOTE: Without a stack the exception can't be entered
+        let mut cpu = Cpu::new();
+        cpu.load_words(0x00000000, &[0x000000FF]).unwrap();
+        let summary = run(
+            &mut cpu,
+            &Limits {
+                max_instructions: Some(10),
+                ..Default::default()
+            },
+        );
+        assert_eq!(summary.reason, StopReason::Halted);
+        assert_eq!(summary.instructions, 1);
+    }
+
+    #[test]
+    fn test_breakpoints_stop_the_run() {
+        let mut cpu = counting_cpu();
+        cpu.add_breakpoint(0x00000008);
+
+        let summary = run(
+            &mut cpu,
+            &Limits {
+                max_instructions: Some(10),
+                ..Default::default()
+            },
+        );
+
+        assert_eq!(
+            summary.reason,
+            StopReason::Event(DebugEvent::Breakpoint(0x00000008))
+        );
+        assert_eq!(summary.instructions, 1);
+    }
+}